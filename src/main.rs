@@ -4,150 +4,160 @@ mod shell_parsers;
 mod shell_structures;
 mod shell_input_iterator;
 mod shell_state;
+mod shell_expansions;
+mod shell_translation;
+mod shell_execution;
+mod shell_builtins;
 
-use std::fmt::Debug;
+use is_terminal::IsTerminal;
 use rustyline::{DefaultEditor};
 use rustyline::error::ReadlineError;
 
-use shell_parser_base::{ParseError, ParseResult};
+use shell_parser_base::ParseError;
 
-use shell_structures::shell_word::ShellWord;
 use crate::shell_input_iterator::ShellInputIterator;
-use crate::shell_parser_base::{ShellParsingRules, ShellWordParsingRules};
-use crate::shell_parsers::shell_word_parser::ShellWordParser;
-use crate::shell_structures::shell_variable_assignment::ShellVariableAssignment;
-use crate::shell_parsers::shell_variable_assignment_parser::ShellVariableAssignmentParser;
+use crate::shell_parser_base::ShellParsingRules;
+use crate::shell_parsers::shell_input_parser::ShellInputParser;
+use crate::shell_structures::shell_list::ShellList;
+use crate::shell_expansions::history_expansion::expand_history;
+use crate::shell_translation::po_template::dump_po_strings;
+use crate::shell_execution::execute_list;
+use crate::shell_execution::job_control::{install_child_signal_handler, notify_job_changes};
+use crate::shell_execution::process::{flush_output, ignore_job_control_signals};
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::{ControlFlow, ShellState};
 
-struct ShellInputParser<I>
-where
-    I: Iterator<Item = u8>,
-    I: Clone,
-{
-    iter: ShellInputIterator<I>,
+// The expanded line is echoed and stored in the history instead of the typed one, None means nothing to execute
+fn apply_history_expansion(ed: &mut DefaultEditor, line: String) -> Option<String> {
+    let history: Vec<Vec<u8>> = ed.history().iter().map(|entry| entry.clone().into_bytes()).collect();
+    match expand_history(line.as_bytes(), &history) {
+        Ok(expanded) => {
+            let expanded_line = String::from_utf8_lossy(&expanded.line).into_owned();
+            if expanded.is_expanded {
+                println!("{expanded_line}");
+            }
+            ed.add_history_entry(expanded_line.as_str()).expect("TODO: panic message");
+            if expanded.is_print_only {
+                None
+            } else {
+                Some(expanded_line)
+            }
+        },
+        Err(err) => {
+            eprintln!("cosh: {}", err.get_message());
+            None
+        },
+    }
 }
 
-impl<I> ShellInputParser<I>
-where
-    I: Iterator<Item = u8>,
-    I: Clone,
-{
-    fn new(iter: ShellInputIterator<I>) -> Self {
-        Self {
-            iter
-        }
+fn read_line(ed: &mut DefaultEditor, prompt: &str, parsing_rules: &ShellParsingRules) -> Result<Option<String>, ReadlineError> {
+    let line = ed.readline(prompt)?;
+    if shell_parser_base::get_default_word_parsing_rules(parsing_rules).parse_history_expansions {
+        return Ok(apply_history_expansion(ed, line));
     }
+    ed.add_history_entry(line.as_str()).expect("TODO: panic message");
+    Ok(Some(line))
+}
 
-    //fn try_parse_command(&mut self) -> ParseResult<ShellInput> {
-    //    let iter_state = self.iter.clone();
-    //    let assignments: Vec<ShellAssign> = self.try_parse_assignments()?.unwrap_or_else(|| vec![]);
-    //    // TODO: Add redirections
-    //    let words = match self.try_parse_words()? {
-    //        None => {
-    //            self.iter = iter_state;
-    //            return Ok(None);
-    //        },
-    //        Some(words) => words,
-    //    };
-    //    Ok(Some(ShellInput::Command(assignments, words, vec![])))
-    //}
-
-    // fn try_parse_words(&mut self) -> ParseResult<Vec<ShellWord>> {
-    //     let iter_state = self.iter.clone();
-    //     let mut words: Vec<ShellWord> = vec![];
-    //     self.skip_whitespace();
-    //     while let Some(word) = self.try_parse_word(true)? {
-    //         words.push(word);
-    //         self.skip_whitespace();
-    //     }
-    //     if words.is_empty() {
-    //         self.iter = iter_state;
-    //         return Ok(None);
-    //     }
-    //     Ok(Some(words))
-    // }
-
-    // fn try_parse_assignments(&mut self) -> ParseResult<Vec<ShellAssign>> {
-    //     let iter_state = self.iter.clone();
-    //     let mut assignments: Vec<ShellAssign> = vec![];
-    //     self.skip_whitespace();
-    //     while let Some(assign) = self.try_get_assignment()? {
-    //         assignments.push(assign);
-    //         self.skip_whitespace();
-    //     }
-    //     if assignments.is_empty() {
-    //         self.iter = iter_state;
-    //         return Ok(None);
-    //     }
-    //     Ok(Some(assignments))
-    // }
-
-
-    // fn try_parse(&mut self, subshell_end: Option<u8>) -> ParseResult<Vec<ShellInput>> {
-    //     let mut parsed: Vec<ShellInput> = vec![];
-    //     loop {
-    //         self.skip_whitespace();
-    //         if let Some(command) = self.try_parse_command()? {
-    //             parsed.push(command);
-    //         }
-    //         if self.check_subshell_end(subshell_end) {
-    //             return Ok(None); // replace with value
-    //         }
-    //         if self.iter.peek().is_none() {
-    //             if subshell_end.is_some() {
-    //                 return Err(ParseError::RequiresNextLine);
-    //             }
-    //             if parsed.is_empty() {
-    //                 return Ok(None);
-    //             }
-    //             return Ok(Some(parsed));
-    //         }
-    //     }
-    // }
-
-    fn try_parse_word(&mut self) -> ParseResult<ShellWord> {
-        let d_rules = ShellParsingRules {
-            is_interactive: true,
-        };
-        let w_rules = shell_parser_base::get_default_word_parsing_rules(&d_rules);
-        let mut word_parser = ShellWordParser::new(self.iter.clone(), &w_rules);
-        word_parser.parse()
+// Lines are read until the commands are complete, "> " asks for the continuation of an unclosed
+// quote or list, or for the lines of a here-document; None when there is nothing to run. The
+// number of lines read comes along, continuation lines included
+fn read_commands(ed: &mut DefaultEditor, mut input: String, parsing_rules: &ShellParsingRules) -> (Option<ShellList>, usize) {
+    let mut line_count = 1;
+    loop {
+        let iter = ShellInputIterator::new(input.clone().into_bytes().into_iter().peekable());
+        let mut parser = ShellInputParser::new(iter, parsing_rules);
+        match parser.parse() {
+            Ok(commands) => return (commands, line_count),
+            Err(ParseError::IncorrectSyntax) => {
+                eprintln!("cosh: syntax error");
+                return (None, line_count);
+            },
+            Err(ParseError::RequiresNextLine) => match read_line(ed, "> ", parsing_rules) {
+                Ok(Some(line)) => {
+                    input.push('\n');
+                    input.push_str(&line);
+                    line_count += 1;
+                },
+                Ok(None) => return (None, line_count),
+                Err(_) => {
+                    eprintln!("cosh: syntax error: unexpected end of file");
+                    return (None, line_count);
+                },
+            },
+        }
     }
+}
 
-    fn try_parse_assignment(&mut self) -> ParseResult<ShellVariableAssignment> {
-        let mut parser = ShellVariableAssignmentParser::new(self.iter.clone());
-        parser.parse()
+// --dump-po-strings script...: prints a .pot template of the translatable strings instead of running anything
+fn run_dump_po_strings(file_names: &[String]) -> i32 {
+    let mut scripts = vec![];
+    for file_name in file_names {
+        match std::fs::read(file_name) {
+            Ok(script) => scripts.push((file_name.clone(), script)),
+            Err(err) => {
+                eprintln!("cosh: {file_name}: {err}");
+                return 1;
+            },
+        }
+    }
+    // The scripts are only parsed, as a non-interactive shell with the default options would
+    let parsing_rules = ShellParsingRules { is_interactive: false, is_extglob_enabled: false };
+    match dump_po_strings(&scripts, &parsing_rules) {
+        Ok(template) => {
+            print!("{template}");
+            0
+        },
+        Err(_) => {
+            eprintln!("cosh: syntax error");
+            2
+        },
     }
 }
 
 fn main() {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(String::as_str) == Some("--dump-po-strings") {
+        std::process::exit(run_dump_po_strings(&args[2..]));
+    }
     let mut ed = DefaultEditor::new().unwrap();
+    let parameters = args.iter().skip(1).map(|arg| arg.clone().into_bytes()).collect();
+    let is_interactive = std::io::stdin().is_terminal();
+    let mut state = ShellState::new(args[0].clone().into_bytes(), parameters, is_interactive);
+    if is_interactive {
+        state.options.set(ShellOption::Monitor, true);
+        ignore_job_control_signals();
+    }
+    install_child_signal_handler();
     loop {
-        let next_line = ed.readline("cosh $ ");
+        notify_job_changes(&mut state);
+        let parsing_rules = state.get_parsing_rules();
+        let next_line = read_line(&mut ed, "cosh $ ", &parsing_rules);
         match next_line {
-            Ok(line) => {
-                ed.add_history_entry(line.as_str()).expect("TODO: panic message");
-                let mut parser = ShellInputParser {
-                    iter: ShellInputIterator::new(line.into_bytes().into_iter().peekable()),
-                };
-                match parser.try_parse_assignment() {
-                    Ok(Some(input)) => {
-                        println!("{input:?}");
-                    },
-                    Ok(None) => {
-                        println!("Empty input");
-                    },
-                    Err(_) => {
-
-                    }
+            Ok(Some(line)) => {
+                // LINENO is the line the commands start on while they run, the lines after it are
+                // counted once they are done
+                let first_line = state.variables.dynamic.line_number + 1;
+                state.variables.dynamic.line_number = first_line;
+                let (commands, line_count) = read_commands(&mut ed, line, &parsing_rules);
+                if let Some(list) = commands {
+                    execute_list(&list, &mut state);
+                }
+                state.variables.dynamic.line_number = first_line + line_count - 1;
+                if let Some(ControlFlow::Exit(status)) = state.control_flow {
+                    flush_output();
+                    std::process::exit(status);
                 }
             },
+            Ok(None) => {},
             Err(ReadlineError::Interrupted) => {
                 println!("^C");
                 break;
             },
             Err(ReadlineError::Eof) => {
                 println!("exit");
-                break;
+                flush_output();
+                std::process::exit(state.last_exit_status);
             },
             Err(err) => {
                 println!("Error {err:?}");
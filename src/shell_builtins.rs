@@ -0,0 +1,239 @@
+pub mod control_builtins;
+pub mod command_builtins;
+pub mod option_builtins;
+pub mod variable_builtins;
+pub mod job_builtins;
+pub mod directory_builtins;
+pub mod output_builtins;
+pub mod input_builtins;
+pub mod test_builtins;
+pub mod declaration_builtins;
+
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::io::Write;
+use std::rc::Rc;
+
+use crate::shell_expansions::assignment_expansion::ExpandedAssignment;
+use crate::shell_state::ShellState;
+
+// Commands run by the shell itself. They are found before the commands in PATH, special builtins
+// even before functions
+pub trait Builtin {
+    fn get_name(&self) -> &'static [u8];
+
+    // POSIX special builtins: their prefix assignments persist and their errors end a shell that is
+    // not interactive
+    fn is_special(&self) -> bool {
+        false
+    }
+
+    // The arguments come after the name; descriptors 0, 1 and 2 already have the redirections of the
+    // command applied
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32;
+}
+
+pub struct BuiltinContext<'a> {
+    pub state: &'a mut ShellState,
+    name: &'static [u8],
+    pub is_failed: bool, // an error was reported
+    pub is_keeping_redirections: bool, // exec without a command, the redirections stay in the shell
+    pub assignments: HashMap<usize, ExpandedAssignment>, // arguments parsed as assignments, by index: declare a=(1 2)
+}
+
+impl<'a> BuiltinContext<'a> {
+    pub fn new(name: &'static [u8], state: &'a mut ShellState) -> Self {
+        Self { state, name, is_failed: false, is_keeping_redirections: false, assignments: HashMap::new() }
+    }
+
+    // cosh: name: message
+    pub fn print_error(&mut self, message: impl Display) {
+        eprintln!("cosh: {}: {message}", String::from_utf8_lossy(self.name));
+        self.is_failed = true;
+    }
+
+    // Usage errors have status 2
+    pub fn print_usage(&mut self, usage: &str) -> i32 {
+        eprintln!("{}: usage: {usage}", String::from_utf8_lossy(self.name));
+        self.is_failed = true;
+        2
+    }
+
+    pub fn print_invalid_option(&mut self, option: impl Display, usage: &str) -> i32 {
+        self.print_error(format_args!("{option}: invalid option"));
+        self.print_usage(usage)
+    }
+
+    // Output goes to descriptor 1 right away, a builtin writing to a closed descriptor fails instead
+    // of ending the shell
+    pub fn write_output(&mut self, output: &[u8]) -> bool {
+        let mut stdout = std::io::stdout().lock();
+        match stdout.write_all(output).and_then(|_| stdout.flush()) {
+            Ok(()) => true,
+            Err(err) => {
+                drop(stdout);
+                self.print_error(format_args!("write error: {}", errno::Errno(err.raw_os_error().unwrap_or(0))));
+                false
+            },
+        }
+    }
+
+    pub fn write_line(&mut self, line: impl AsRef<[u8]>) -> bool {
+        self.write_output(&[line.as_ref(), b"\n"].concat())
+    }
+
+    // builtin and command run another builtin in the same shell, its errors are its own
+    pub fn run_builtin(&mut self, builtin: &dyn Builtin, arguments: &[Vec<u8>]) -> i32 {
+        let mut context = BuiltinContext::new(builtin.get_name(), self.state);
+        let status = builtin.run(arguments, &mut context);
+        self.is_keeping_redirections |= context.is_keeping_redirections;
+        status
+    }
+}
+
+struct RegisteredBuiltin {
+    builtin: Rc<dyn Builtin>,
+    is_enabled: bool,
+}
+
+// Builtins by name, enable -n hides one so that the command in PATH runs instead
+pub struct BuiltinRegistry {
+    builtins: Vec<RegisteredBuiltin>,
+}
+
+impl Default for BuiltinRegistry {
+    fn default() -> Self {
+        let mut result = Self { builtins: vec![] };
+        for builtin in control_builtins::get_builtins()
+            .into_iter()
+            .chain(command_builtins::get_builtins())
+            .chain(option_builtins::get_builtins())
+            .chain(variable_builtins::get_builtins())
+            .chain(job_builtins::get_builtins())
+            .chain(directory_builtins::get_builtins())
+            .chain(output_builtins::get_builtins())
+            .chain(input_builtins::get_builtins())
+            .chain(test_builtins::get_builtins())
+            .chain(declaration_builtins::get_builtins())
+        {
+            result.register(builtin);
+        }
+        result
+    }
+}
+
+impl BuiltinRegistry {
+    // A builtin with the name of a registered one replaces it
+    pub fn register(&mut self, builtin: Rc<dyn Builtin>) {
+        let name = builtin.get_name();
+        match self.builtins.iter_mut().find(|registered| registered.builtin.get_name() == name) {
+            Some(registered) => registered.builtin = builtin,
+            None => self.builtins.push(RegisteredBuiltin { builtin, is_enabled: true }),
+        }
+    }
+
+    // Enabled builtins only
+    pub fn get(&self, name: &[u8]) -> Option<Rc<dyn Builtin>> {
+        self.builtins
+            .iter()
+            .find(|registered| registered.is_enabled && registered.builtin.get_name() == name)
+            .map(|registered| registered.builtin.clone())
+    }
+
+    // false when there is no such builtin
+    pub fn set_enabled(&mut self, name: &[u8], is_enabled: bool) -> bool {
+        match self.builtins.iter_mut().find(|registered| registered.builtin.get_name() == name) {
+            Some(registered) => {
+                registered.is_enabled = is_enabled;
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Sorted by name, with whether each one is enabled
+    pub fn iter(&self) -> impl Iterator<Item = (&dyn Builtin, bool)> {
+        let mut builtins: Vec<(&dyn Builtin, bool)> =
+            self.builtins.iter().map(|registered| (registered.builtin.as_ref(), registered.is_enabled)).collect();
+        builtins.sort_by(|a, b| a.0.get_name().cmp(b.0.get_name()));
+        builtins.into_iter()
+    }
+}
+
+// Single quotes unless the value only has characters that never need quoting, for set and hash -l
+pub fn quote_value(value: &[u8]) -> Vec<u8> {
+    let is_plain = |c: &u8| c.is_ascii_alphanumeric() || b"_-+./:=@%,".contains(c);
+    if !value.is_empty() && value.iter().all(is_plain) {
+        return value.to_vec();
+    }
+    let mut result = vec![b'\''];
+    for &c in value {
+        match c {
+            b'\'' => result.extend(b"'\\''"),
+            _ => result.push(c),
+        }
+    }
+    result.push(b'\'');
+    result
+}
+
+// No control characters and valid UTF-8: the value can be written as it is between quotes
+pub fn is_printable(value: &[u8]) -> bool {
+    std::str::from_utf8(value).is_ok() && !value.iter().any(|&c| c < 0x20 || c == 0x7F)
+}
+
+// $'...' with escapes for the control characters and the bytes that are not UTF-8
+pub fn quote_ansi_c(value: &[u8]) -> Vec<u8> {
+    let mut result = b"$'".to_vec();
+    let mut rest = value;
+    while let Some(&c) = rest.first() {
+        // Characters of several bytes are kept as they are when they are valid UTF-8
+        let length = (2..=rest.len().min(4)).find(|&length| c >= 0x80 && std::str::from_utf8(&rest[..length]).is_ok());
+        if let Some(length) = length {
+            result.extend(&rest[..length]);
+            rest = &rest[length..];
+            continue;
+        }
+        match c {
+            0x07 => result.extend(b"\\a"),
+            0x08 => result.extend(b"\\b"),
+            0x1B => result.extend(b"\\E"),
+            0x0C => result.extend(b"\\f"),
+            b'\n' => result.extend(b"\\n"),
+            b'\r' => result.extend(b"\\r"),
+            b'\t' => result.extend(b"\\t"),
+            0x0B => result.extend(b"\\v"),
+            b'\\' | b'\'' => result.extend([b'\\', c]),
+            0x20..=0x7E => result.push(c),
+            _ => result.extend(format!("\\x{c:02x}").into_bytes()),
+        }
+        rest = &rest[1..];
+    }
+    result.push(b'\'');
+    result
+}
+
+// printf %q: backslashes before the characters the word parser treats specially, $'...' when there are
+// control characters or bytes that are not UTF-8; parsing the result gives the value back
+pub fn quote_word(value: &[u8]) -> Vec<u8> {
+    if value.is_empty() {
+        return b"''".to_vec();
+    }
+    if !is_printable(value) {
+        return quote_ansi_c(value);
+    }
+    let mut result = vec![];
+    for (index, &c) in value.iter().enumerate() {
+        let is_special = b" \t\n'\"\\|&;()<>{}[]*?^$`!,".contains(&c) || (index == 0 && (c == b'~' || c == b'#'));
+        if is_special {
+            result.push(b'\\');
+        }
+        result.push(c);
+    }
+    result
+}
+
+// Integer arguments: exit 3, shift 2
+pub fn parse_number(argument: &[u8]) -> Option<i64> {
+    String::from_utf8_lossy(argument).trim().parse().ok()
+}
@@ -0,0 +1,294 @@
+use std::rc::Rc;
+
+use super::{quote_value, Builtin, BuiltinContext};
+
+use crate::shell_execution::command_search::locate_command;
+use crate::shell_execution::simple_command::run_external_command;
+use crate::shell_structures::ShellToken;
+
+// Words that start or end compound commands, command -v reports them before anything else
+const RESERVED_WORDS: [&[u8]; 22] = [
+    b"if", b"then", b"elif", b"else", b"fi", b"case", b"esac", b"for", b"select", b"while", b"until", b"do",
+    b"done", b"in", b"function", b"time", b"{", b"}", b"!", b"[[", b"]]", b"coproc",
+];
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(BuiltinBuiltin), Rc::new(CommandBuiltin), Rc::new(EnableBuiltin), Rc::new(HashBuiltin)]
+}
+
+// builtin [name [argument...]]: runs the builtin even when a function has its name
+struct BuiltinBuiltin;
+
+impl Builtin for BuiltinBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"builtin"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let Some(name) = arguments.first() else { return 0 };
+        match context.state.builtins.get(name) {
+            Some(builtin) => context.run_builtin(builtin.as_ref(), &arguments[1..]),
+            None => {
+                context.print_error(format_args!("{}: not a shell builtin", String::from_utf8_lossy(name)));
+                1
+            },
+        }
+    }
+}
+
+// command [-pvV] name [argument...]: runs a builtin or a command from PATH, never a function; the
+// special builtins lose their special properties. -v and -V tell what the names are instead
+struct CommandBuiltin;
+
+impl CommandBuiltin {
+    // None when there is no such command
+    fn describe(&self, name: &[u8], is_verbose: bool, context: &mut BuiltinContext) -> Option<Vec<u8>> {
+        let text = String::from_utf8_lossy(name).into_owned();
+        let state = &mut *context.state;
+        if RESERVED_WORDS.contains(&name) {
+            return Some(match is_verbose {
+                true => format!("{text} is a shell keyword").into_bytes(),
+                false => name.to_vec(),
+            });
+        }
+        let builtin = state.builtins.get(name);
+        if let Some(builtin) = builtin.as_ref().filter(|builtin| builtin.is_special()) {
+            return Some(match is_verbose {
+                true => format!("{text} is a special shell builtin").into_bytes(),
+                false => builtin.get_name().to_vec(),
+            });
+        }
+        if let Some(function) = state.functions.get(name) {
+            return Some(match is_verbose {
+                true => [format!("{text} is a function\n").into_bytes(), function.definition.restore_original()].concat(),
+                false => name.to_vec(),
+            });
+        }
+        if builtin.is_some() {
+            return Some(match is_verbose {
+                true => format!("{text} is a shell builtin").into_bytes(),
+                false => name.to_vec(),
+            });
+        }
+        let (path, is_hashed) = locate_command(name, state)?;
+        Some(match (is_verbose, is_hashed) {
+            (true, true) => format!("{text} is hashed ({})", String::from_utf8_lossy(&path)).into_bytes(),
+            (true, false) => format!("{text} is {}", String::from_utf8_lossy(&path)).into_bytes(),
+            (false, _) => path,
+        })
+    }
+}
+
+impl Builtin for CommandBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"command"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "command [-pVv] command [arg ...]";
+        let mut is_default_path = false;
+        let mut description = None;
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            if argument == b"--" {
+                break;
+            }
+            for &flag in &argument[1..] {
+                match flag {
+                    b'p' => is_default_path = true,
+                    b'v' => description = Some(false),
+                    b'V' => description = Some(true),
+                    _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                }
+            }
+        }
+        let arguments: Vec<Vec<u8>> = arguments.cloned().collect();
+        if let Some(is_verbose) = description {
+            let mut status = 0;
+            for name in arguments.iter() {
+                match self.describe(name, is_verbose, context) {
+                    Some(line) => {
+                        if !context.write_line(line) {
+                            return 1;
+                        }
+                    },
+                    None => {
+                        if is_verbose {
+                            context.print_error(format_args!("{}: not found", String::from_utf8_lossy(name)));
+                        }
+                        status = 1;
+                    },
+                }
+            }
+            return status;
+        }
+        let Some(name) = arguments.first() else { return 0 };
+        match context.state.builtins.get(name) {
+            Some(builtin) => context.run_builtin(builtin.as_ref(), &arguments[1..]),
+            None => run_external_command(arguments, is_default_path, context.state),
+        }
+    }
+}
+
+// enable [-anps] [name...]: -n disables the builtins, so that the commands in PATH with their names
+// run instead; without names the builtins are listed
+struct EnableBuiltin;
+
+impl Builtin for EnableBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"enable"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "enable [-anps] [name ...]";
+        let mut is_disabling = false;
+        let mut is_all = false;
+        let mut is_special_only = false;
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            if argument == b"--" {
+                break;
+            }
+            for &flag in &argument[1..] {
+                match flag {
+                    b'n' => is_disabling = true,
+                    b'a' => is_all = true,
+                    b's' => is_special_only = true,
+                    b'p' => {},
+                    _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                }
+            }
+        }
+        let names: Vec<&Vec<u8>> = arguments.collect();
+        if names.is_empty() {
+            let lines: Vec<Vec<u8>> = context
+                .state
+                .builtins
+                .iter()
+                .filter(|(builtin, is_enabled)| (is_all || *is_enabled != is_disabling) && (!is_special_only || builtin.is_special()))
+                .map(|(builtin, is_enabled)| [if is_enabled { b"enable ".as_slice() } else { b"enable -n " }, builtin.get_name()].concat())
+                .collect();
+            return match lines.into_iter().all(|line| context.write_line(line)) {
+                true => 0,
+                false => 1,
+            };
+        }
+        let mut status = 0;
+        for name in names {
+            if !context.state.builtins.set_enabled(name, !is_disabling) {
+                context.print_error(format_args!("{}: not a shell builtin", String::from_utf8_lossy(name)));
+                status = 1;
+            }
+        }
+        status
+    }
+}
+
+// hash [-lrt] [-p path] [-d] [name...]: remembers where the commands are, without names it lists
+// them with the number of times each one was run
+struct HashBuiltin;
+
+impl HashBuiltin {
+    fn print_entries(&self, is_reusable: bool, context: &mut BuiltinContext) -> i32 {
+        let entries = context.state.command_hash.get_entries();
+        let mut lines: Vec<Vec<u8>> = match (entries.is_empty(), is_reusable) {
+            (true, _) => vec![b"hash: hash table empty".to_vec()],
+            (false, true) => vec![],
+            (false, false) => vec![b"hits\tcommand".to_vec()],
+        };
+        for (name, command) in entries {
+            lines.push(match is_reusable {
+                true => [b"builtin hash -p ".as_slice(), &quote_value(&command.path), b" ", &quote_value(name)].concat(),
+                false => [format!("{:>4}\t", command.hits).into_bytes(), command.path.clone()].concat(),
+            });
+        }
+        match lines.into_iter().all(|line| context.write_line(line)) {
+            true => 0,
+            false => 1,
+        }
+    }
+}
+
+impl Builtin for HashBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"hash"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "hash [-lr] [-p pathname] [-dt] [name ...]";
+        let mut is_reusable = false;
+        let mut is_clearing = false;
+        let mut is_deleting = false;
+        let mut is_printing = false;
+        let mut path = None;
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            if argument == b"--" {
+                break;
+            }
+            for &flag in &argument[1..] {
+                match flag {
+                    b'r' => {
+                        is_clearing = true;
+                        context.state.command_hash.clear();
+                    },
+                    b'l' => is_reusable = true,
+                    b'd' => is_deleting = true,
+                    b't' => is_printing = true,
+                    b'p' => match arguments.next() {
+                        Some(value) => path = Some(value.clone()),
+                        None => {
+                            context.print_error("-p: option requires an argument");
+                            return context.print_usage(USAGE);
+                        },
+                    },
+                    _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                }
+            }
+        }
+        let names: Vec<&Vec<u8>> = arguments.collect();
+        if names.is_empty() {
+            return match is_clearing && !is_reusable {
+                true => 0,
+                false => self.print_entries(is_reusable, context),
+            };
+        }
+        let mut status = 0;
+        for name in names.iter() {
+            let text = String::from_utf8_lossy(name).into_owned();
+            if let Some(path) = path.as_ref() {
+                context.state.command_hash.insert(name, path.clone());
+            } else if is_deleting {
+                if context.state.command_hash.get_entries().iter().all(|(entry, _)| entry != &name.as_slice()) {
+                    context.print_error(format_args!("{text}: not found"));
+                    status = 1;
+                }
+                context.state.command_hash.remove(name);
+            } else if is_printing {
+                let entry = context.state.command_hash.get_entries().into_iter().find(|(entry, _)| entry == &name.as_slice());
+                let line = match entry {
+                    Some((_, command)) if names.len() == 1 => command.path.clone(),
+                    Some((_, command)) => [name.as_slice(), b"\t", &command.path].concat(),
+                    None => {
+                        context.print_error(format_args!("{text}: not found"));
+                        status = 1;
+                        continue;
+                    },
+                };
+                if !context.write_line(line) {
+                    return 1;
+                }
+            } else if !name.contains(&b'/') {
+                context.state.command_hash.remove(name);
+                match locate_command(name, context.state) {
+                    Some((path, _)) => context.state.command_hash.insert(name, path),
+                    None => {
+                        context.print_error(format_args!("{text}: not found"));
+                        status = 1;
+                    },
+                }
+            }
+        }
+        status
+    }
+}
@@ -0,0 +1,330 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::{parse_number, Builtin, BuiltinContext};
+
+use crate::shell_execution::command_search::find_command;
+use crate::shell_execution::job_control::install_child_signal_handler;
+use crate::shell_execution::process::{flush_output, ignore_job_control_signals, reset_signals};
+use crate::shell_execution::simple_command::execute_file_or_script;
+use crate::shell_execution::{execute_input, execute_list};
+use crate::shell_input_iterator::ShellInputIterator;
+use crate::shell_parsers::shell_input_parser::ShellInputParser;
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ControlFlow;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![
+        Rc::new(StatusBuiltin { name: b":", status: 0, is_special: true }),
+        Rc::new(StatusBuiltin { name: b"true", status: 0, is_special: false }),
+        Rc::new(StatusBuiltin { name: b"false", status: 1, is_special: false }),
+        Rc::new(ExitBuiltin),
+        Rc::new(ReturnBuiltin),
+        Rc::new(LoopControlBuiltin { name: b"break", is_continue: false }),
+        Rc::new(LoopControlBuiltin { name: b"continue", is_continue: true }),
+        Rc::new(EvalBuiltin),
+        Rc::new(SourceBuiltin { name: b".", is_special: true }),
+        Rc::new(SourceBuiltin { name: b"source", is_special: false }),
+        Rc::new(ExecBuiltin),
+    ]
+}
+
+// Exit statuses are taken modulo 256
+fn parse_status(argument: &[u8], context: &mut BuiltinContext) -> Option<i32> {
+    match parse_number(argument) {
+        Some(value) => Some((value & 0xff) as i32),
+        None => {
+            context.print_error(format_args!("{}: numeric argument required", String::from_utf8_lossy(argument)));
+            None
+        },
+    }
+}
+
+// :, true and false ignore their arguments
+struct StatusBuiltin {
+    name: &'static [u8],
+    status: i32,
+    is_special: bool,
+}
+
+impl Builtin for StatusBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        self.name
+    }
+
+    fn is_special(&self) -> bool {
+        self.is_special
+    }
+
+    fn run(&self, _arguments: &[Vec<u8>], _context: &mut BuiltinContext) -> i32 {
+        self.status
+    }
+}
+
+// exit [n], n defaults to the status of the last command
+struct ExitBuiltin;
+
+impl Builtin for ExitBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"exit"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        if context.state.is_interactive && context.state.functions.call_stack.is_empty() {
+            eprintln!("exit");
+        }
+        if arguments.len() > 1 {
+            context.print_error("too many arguments");
+            return 1;
+        }
+        let status = match arguments.first() {
+            None => context.state.last_exit_status,
+            Some(argument) => parse_status(argument, context).unwrap_or(2),
+        };
+        context.state.control_flow = Some(ControlFlow::Exit(status));
+        status
+    }
+}
+
+// return [n], from a function or a file read by .
+struct ReturnBuiltin;
+
+impl Builtin for ReturnBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"return"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        if context.state.functions.call_stack.is_empty() && context.state.source_depth == 0 {
+            context.print_error("can only `return' from a function or sourced script");
+            return 1;
+        }
+        let status = match arguments.first() {
+            None => context.state.last_exit_status,
+            Some(argument) => parse_status(argument, context).unwrap_or(2),
+        };
+        context.state.control_flow = Some(ControlFlow::Return);
+        status
+    }
+}
+
+// break [n] and continue [n], n counts the enclosing loops and is capped to their number; an n below
+// 1 leaves all of them
+struct LoopControlBuiltin {
+    name: &'static [u8],
+    is_continue: bool,
+}
+
+impl Builtin for LoopControlBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        self.name
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let loop_depth = context.state.loop_depth;
+        // Neither this warning nor a count out of range ends the shell, as in bash
+        if loop_depth == 0 {
+            eprintln!("cosh: {}: only meaningful in a `for', `while', or `until' loop", String::from_utf8_lossy(self.name));
+            return 0;
+        }
+        if arguments.len() > 1 {
+            context.print_error("too many arguments");
+            return 1;
+        }
+        let (count, status) = match arguments.first() {
+            None => (1, 0),
+            Some(argument) => match parse_number(argument) {
+                Some(value) if value >= 1 => (value as usize, 0),
+                Some(_) => {
+                    eprintln!("cosh: {}: {}: loop count out of range", String::from_utf8_lossy(self.name), String::from_utf8_lossy(argument));
+                    (loop_depth, 1)
+                },
+                None => {
+                    context.print_error(format_args!("{}: numeric argument required", String::from_utf8_lossy(argument)));
+                    return 1;
+                },
+            },
+        };
+        let count = count.min(loop_depth);
+        context.state.control_flow = Some(match self.is_continue && status == 0 {
+            true => ControlFlow::Continue(count),
+            false => ControlFlow::Break(count),
+        });
+        status
+    }
+}
+
+// eval [argument...]: the arguments joined with spaces are run as commands
+struct EvalBuiltin;
+
+impl Builtin for EvalBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"eval"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let input = arguments.join(&b' ');
+        let parsing_rules = context.state.get_parsing_rules();
+        let mut parser = ShellInputParser::new(ShellInputIterator::new(input.iter().copied().peekable()), &parsing_rules);
+        match parser.parse() {
+            Ok(Some(list)) => execute_list(&list, context.state),
+            Ok(None) => 0,
+            Err(_) => {
+                context.print_error("syntax error");
+                2
+            },
+        }
+    }
+}
+
+// . file [argument...]: the commands of the file run in the current shell, the arguments replace the
+// positional parameters while they do. A name without a slash is searched for in PATH first
+struct SourceBuiltin {
+    name: &'static [u8],
+    is_special: bool,
+}
+
+impl SourceBuiltin {
+    fn find_file(&self, name: &[u8], context: &BuiltinContext) -> Vec<u8> {
+        if name.contains(&b'/') {
+            return name.to_vec();
+        }
+        let path_variable = context.state.get_variable(b"PATH").unwrap_or_default();
+        path_variable
+            .split(|&c| c == b':')
+            .filter(|directory| !directory.is_empty())
+            .map(|directory| [directory, b"/", name].concat())
+            .find(|path| Path::new(OsStr::from_bytes(path)).is_file())
+            .unwrap_or_else(|| name.to_vec())
+    }
+}
+
+impl Builtin for SourceBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        self.name
+    }
+
+    fn is_special(&self) -> bool {
+        self.is_special
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let Some(name) = arguments.first() else {
+            context.print_error("filename argument required");
+            return context.print_usage(&format!("{} filename [arguments]", String::from_utf8_lossy(self.name)));
+        };
+        let path = self.find_file(name, context);
+        let script = match std::fs::read(OsStr::from_bytes(&path)) {
+            Ok(value) => value,
+            Err(err) => {
+                let err = errno::Errno(err.raw_os_error().unwrap_or(0));
+                context.print_error(format_args!("{}: {err}", String::from_utf8_lossy(name)));
+                return 1;
+            },
+        };
+        let state = &mut *context.state;
+        let saved_parameters = (arguments.len() > 1).then(|| state.get_positional_parameters().to_vec());
+        if saved_parameters.is_some() {
+            state.set_positional_parameters(arguments[1..].to_vec());
+        }
+        state.source_depth += 1;
+        let status = execute_input(&script, state);
+        state.source_depth -= 1;
+        if state.control_flow == Some(ControlFlow::Return) {
+            state.control_flow = None;
+        }
+        if let Some(parameters) = saved_parameters {
+            state.set_positional_parameters(parameters);
+        }
+        status
+    }
+}
+
+// exec [-cl] [-a name] [command [argument...]]: the shell becomes the command; without a command the
+// redirections stay in place for the rest of the shell
+struct ExecBuiltin;
+
+impl Builtin for ExecBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"exec"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "exec [-cl] [-a name] [command [argument ...]]";
+        let mut is_clearing_environment = false;
+        let mut is_login = false;
+        let mut command_name = None;
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            if argument == b"--" {
+                break;
+            }
+            for &flag in &argument[1..] {
+                match flag {
+                    b'c' => is_clearing_environment = true,
+                    b'l' => is_login = true,
+                    b'a' => match arguments.next() {
+                        Some(name) => command_name = Some(name.clone()),
+                        None => {
+                            context.print_error("-a: option requires an argument");
+                            return context.print_usage(USAGE);
+                        },
+                    },
+                    _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                }
+            }
+        }
+        let mut arguments: Vec<Vec<u8>> = arguments.cloned().collect();
+        if arguments.is_empty() {
+            context.is_keeping_redirections = true;
+            return 0;
+        }
+        let Some(path) = find_command(&arguments[0], context.state) else {
+            context.print_error(format_args!("{}: not found", String::from_utf8_lossy(&arguments[0])));
+            return 127;
+        };
+        if let Some(name) = command_name {
+            arguments[0] = name;
+        }
+        if is_login {
+            arguments[0].insert(0, b'-');
+        }
+        let environment = match is_clearing_environment {
+            true => vec![],
+            false => context.state.variables.get_exported(),
+        };
+        flush_output();
+        reset_signals();
+        let status = execute_file_or_script(&path, arguments, &environment, context.state);
+        // The shell goes on with its own signal dispositions
+        unsafe { libc::signal(libc::SIGPIPE, libc::SIG_IGN) };
+        if context.state.options.is_enabled(ShellOption::Monitor) {
+            ignore_job_control_signals();
+        }
+        install_child_signal_handler();
+        context.is_failed = true;
+        status
+    }
+}
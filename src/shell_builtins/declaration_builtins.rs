@@ -0,0 +1,395 @@
+use std::collections::BTreeMap;
+use std::rc::Rc;
+
+use super::option_builtins::format_variable;
+use super::{is_printable, quote_ansi_c, Builtin, BuiltinContext};
+
+use crate::shell_expansions::assignment_expansion::{perform_assignment, ExpandedAssignment, ExpandedAssignmentValue};
+use crate::shell_state::variables::{is_valid_name, Variable, VariableValue};
+use crate::shell_state::ShellState;
+use crate::shell_structures::ShellToken;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![
+        Rc::new(DeclarationBuiltin { name: b"declare", kind: DeclarationKind::Declare }),
+        Rc::new(DeclarationBuiltin { name: b"typeset", kind: DeclarationKind::Declare }),
+        Rc::new(DeclarationBuiltin { name: b"local", kind: DeclarationKind::Local }),
+        Rc::new(DeclarationBuiltin { name: b"export", kind: DeclarationKind::Export }),
+        Rc::new(DeclarationBuiltin { name: b"readonly", kind: DeclarationKind::Readonly }),
+    ]
+}
+
+// Flags of the attributes in the order declare -p shows them
+const ATTRIBUTE_FLAGS: &[u8] = b"aAinrtxlu";
+
+// Flags of the attributes a variable has: declare -ix n=1
+fn get_attribute_flags(variable: &Variable) -> Vec<u8> {
+    let attributes = &variable.attributes;
+    let is_indexed = matches!(variable.value, Some(VariableValue::Indexed(_)));
+    let flags = [
+        is_indexed,
+        variable.is_associative(),
+        attributes.is_integer,
+        attributes.is_nameref,
+        attributes.is_readonly,
+        attributes.is_traced,
+        attributes.is_exported,
+        attributes.is_lowercase,
+        attributes.is_uppercase,
+    ];
+    ATTRIBUTE_FLAGS.iter().zip(flags).filter(|(_, is_set)| *is_set).map(|(&flag, _)| flag).collect()
+}
+
+// Double quotes, where only \ " $ and ` need a backslash; $'...' for values with control characters,
+// which would not survive being read back as a line
+fn quote_declaration_value(value: &[u8]) -> Vec<u8> {
+    if !is_printable(value) {
+        return quote_ansi_c(value);
+    }
+    let mut result = vec![b'"'];
+    for &c in value {
+        if b"\\\"$`".contains(&c) {
+            result.push(b'\\');
+        }
+        result.push(c);
+    }
+    result.push(b'"');
+    result
+}
+
+// declare -a arr=([0]="a" [1]="b"), sourcing the line declares the variable again
+fn format_declaration(name: &[u8], variable: &Variable, state: &ShellState) -> Vec<u8> {
+    let flags = get_attribute_flags(variable);
+    let mut result = [b"declare -".as_slice(), if flags.is_empty() { b"-" } else { &flags }, b" ", name].concat();
+    let format_element = |key: Vec<u8>, value: &[u8]| [b"[".as_slice(), &key, b"]=", &quote_declaration_value(value)].concat();
+    let value = match &variable.value {
+        None => return result,
+        Some(VariableValue::Scalar(value)) if variable.attributes.is_nameref => quote_declaration_value(value),
+        Some(VariableValue::Scalar(value)) => quote_declaration_value(&state.variables.get_scalar(name).unwrap_or(value.clone())),
+        Some(VariableValue::Indexed(elements)) => {
+            let elements = elements.iter().map(|(index, value)| format_element(index.to_string().into_bytes(), value));
+            [b"(".as_slice(), &elements.collect::<Vec<Vec<u8>>>().join(&b' '), b")"].concat()
+        },
+        Some(VariableValue::Associative(elements)) => {
+            let is_plain = |key: &[u8]| !key.is_empty() && key.iter().all(|c| c.is_ascii_alphanumeric() || b"_-+./:@%,".contains(c));
+            let elements = elements.iter().map(|(key, value)| match is_plain(key) {
+                true => format_element(key.clone(), value),
+                false => format_element(quote_declaration_value(key), value),
+            });
+            [b"(".as_slice(), &elements.collect::<Vec<Vec<u8>>>().join(&b' '), b")"].concat()
+        },
+    };
+    result.push(b'=');
+    result.extend(value);
+    result
+}
+
+// declare "name=value" when the assignment comes from an expanded word instead of the parser
+fn parse_assignment_argument(argument: &[u8]) -> ExpandedAssignment {
+    let Some(index) = argument.iter().position(|&c| c == b'=') else {
+        return ExpandedAssignment {
+            identifier: argument.to_vec(),
+            subscript: None,
+            is_append: false,
+            value: ExpandedAssignmentValue::Scalar(vec![]),
+        };
+    };
+    let (target, is_append) = match argument[..index].strip_suffix(b"+") {
+        Some(target) => (target, true),
+        None => (&argument[..index], false),
+    };
+    let (identifier, subscript) = match target.iter().position(|&c| c == b'[') {
+        Some(position) if target.ends_with(b"]") => (&target[..position], Some(target[position + 1..target.len() - 1].to_vec())),
+        _ => (target, None),
+    };
+    let value = ExpandedAssignmentValue::Scalar(argument[index + 1..].to_vec());
+    ExpandedAssignment { identifier: identifier.to_vec(), subscript, is_append, value }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum DeclarationKind {
+    Declare, // declare and typeset
+    Local,
+    Export,
+    Readonly,
+}
+
+#[derive(Default)]
+struct DeclarationOptions {
+    adding: Vec<u8>, // attribute flags after -
+    removing: Vec<u8>, // attribute flags after +
+    is_printing: bool, // -p
+    is_functions: bool, // -f
+    is_function_names: bool, // -F
+    is_global: bool, // -g
+}
+
+// declare [-aAfFgilnprtux] [name[=value]...], local, export [-fnp] and readonly [-aAfp]: set
+// attributes and values, in functions declare and local make the variables local; without names
+// the variables are listed, with -p as declarations that can be sourced again
+struct DeclarationBuiltin {
+    name: &'static [u8],
+    kind: DeclarationKind,
+}
+
+impl DeclarationBuiltin {
+    fn get_usage(&self) -> String {
+        let usage = match self.kind {
+            DeclarationKind::Declare => "[-aAfFgilnrtux] [-p] [name[=value] ...]",
+            DeclarationKind::Local => "[-aAilnrtux] [name[=value] ...]",
+            DeclarationKind::Export => "[-fn] [name[=value] ...] or export -p",
+            DeclarationKind::Readonly => "[-aAf] [name[=value] ...] or readonly -p",
+        };
+        format!("{} {usage}", String::from_utf8_lossy(self.name))
+    }
+
+    // The options and the index of the first name
+    fn parse_options(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> Result<(DeclarationOptions, usize), i32> {
+        let allowed_flags: &[u8] = match self.kind {
+            DeclarationKind::Declare => b"aAfFgilnprtux",
+            DeclarationKind::Local => b"aAilnprtux",
+            DeclarationKind::Export => b"fnp",
+            DeclarationKind::Readonly => b"aAfp",
+        };
+        let mut options = DeclarationOptions::default();
+        let mut index = 0;
+        while let Some(argument) = arguments.get(index).filter(|argument| argument.len() > 1 && b"-+".contains(&argument[0])) {
+            index += 1;
+            if argument == b"--" {
+                break;
+            }
+            let is_adding = argument[0] == b'-';
+            for &flag in &argument[1..] {
+                if !allowed_flags.contains(&flag) {
+                    return Err(context.print_invalid_option(format_args!("{}{}", argument[0] as char, flag as char), &self.get_usage()));
+                }
+                match flag {
+                    b'p' => options.is_printing = true,
+                    b'f' => options.is_functions = true,
+                    b'F' => options.is_function_names = true,
+                    b'g' => options.is_global = true,
+                    b'n' if self.kind == DeclarationKind::Export => options.removing.push(b'x'),
+                    _ if is_adding => options.adding.push(flag),
+                    _ => options.removing.push(flag),
+                }
+            }
+        }
+        match self.kind {
+            DeclarationKind::Export if !options.removing.contains(&b'x') => options.adding.push(b'x'),
+            DeclarationKind::Readonly => options.adding.push(b'r'),
+            _ => {},
+        }
+        Ok((options, index))
+    }
+
+    // -f shows the definitions, -F only the names; export -f and readonly -f only check that the
+    // functions exist
+    fn print_functions(&self, names: &[Vec<u8>], options: &DeclarationOptions, context: &mut BuiltinContext) -> i32 {
+        let is_checking = matches!(self.kind, DeclarationKind::Export | DeclarationKind::Readonly);
+        let functions = &context.state.functions;
+        let names: Vec<Vec<u8>> = match names.is_empty() {
+            true if is_checking => vec![],
+            true => functions.iter().map(|function| function.definition.name.clone()).collect(),
+            false => names.to_vec(),
+        };
+        let mut status = 0;
+        for name in names.iter() {
+            let Some(function) = context.state.functions.get(name) else {
+                if is_checking {
+                    context.print_error(format_args!("{}: not a function", String::from_utf8_lossy(name)));
+                }
+                status = 1;
+                continue;
+            };
+            if is_checking {
+                continue;
+            }
+            let line = match options.is_function_names {
+                true => [b"declare -f ".as_slice(), name].concat(),
+                false => function.definition.restore_original(),
+            };
+            if !context.write_line(line) {
+                return 1;
+            }
+        }
+        status
+    }
+
+    // Without names: every variable with the given attributes, or name=value lines and the functions
+    // when there are no options at all
+    fn print_variables(&self, options: &DeclarationOptions, context: &mut BuiltinContext) -> i32 {
+        let state = &*context.state;
+        let is_plain = !options.is_printing && options.adding.is_empty() && options.removing.is_empty();
+        let variables = match self.kind {
+            DeclarationKind::Local => state.variables.get_locals(),
+            _ => state.variables.get_visible(),
+        };
+        let mut lines: Vec<Vec<u8>> = variables
+            .into_iter()
+            .filter(|(_, variable)| options.adding.iter().all(|flag| get_attribute_flags(variable).contains(flag)))
+            .filter_map(|(name, variable)| match is_plain {
+                true => Some(format_variable(name, variable.value.as_ref()?, state)),
+                false => Some(format_declaration(name, variable, state)),
+            })
+            .collect();
+        if is_plain && self.kind == DeclarationKind::Declare {
+            lines.extend(state.functions.iter().map(|function| function.definition.restore_original()));
+        }
+        match lines.into_iter().all(|line| context.write_line(line)) {
+            true => 0,
+            false => 1,
+        }
+    }
+
+    fn print_declarations(&self, names: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let mut status = 0;
+        for name in names {
+            let line = match context.state.variables.get_unresolved(name) {
+                Some(variable) => format_declaration(name, variable, context.state),
+                None => {
+                    context.print_error(format_args!("{}: not found", String::from_utf8_lossy(name)));
+                    status = 1;
+                    continue;
+                },
+            };
+            if !context.write_line(line) {
+                return 1;
+            }
+        }
+        status
+    }
+
+    // Attributes are set before the value, so that -i evaluates it and -l changes its case; -r comes
+    // after it
+    fn set_attributes(&self, name: &[u8], options: &DeclarationOptions, context: &mut BuiltinContext) -> Result<(), String> {
+        let text = String::from_utf8_lossy(name).into_owned();
+        let state = &mut *context.state;
+        let variable = match self.kind {
+            DeclarationKind::Export | DeclarationKind::Readonly => state.variables.declare_visible(name),
+            _ => state.variables.declare(name, options.is_global),
+        };
+        let is_changing = options.adding.iter().chain(options.removing.iter()).any(|flag| !b"rx".contains(flag));
+        if variable.attributes.is_readonly && (is_changing || options.removing.contains(&b'r')) {
+            return Err(format!("{text}: readonly variable"));
+        }
+        if options.removing.iter().any(|flag| b"aA".contains(flag)) && variable.is_array() {
+            return Err(format!("{text}: cannot destroy array variables in this way"));
+        }
+        if options.adding.contains(&b'A') {
+            variable.value = match variable.value.take() {
+                Some(VariableValue::Indexed(elements)) => {
+                    variable.value = Some(VariableValue::Indexed(elements));
+                    return Err(format!("{text}: cannot convert indexed to associative array"));
+                },
+                Some(VariableValue::Scalar(value)) => Some(VariableValue::Associative(BTreeMap::from([(b"0".to_vec(), value)]))),
+                Some(VariableValue::Associative(elements)) => Some(VariableValue::Associative(elements)),
+                None => Some(VariableValue::Associative(BTreeMap::new())),
+            };
+        } else if options.adding.contains(&b'a') {
+            variable.value = match variable.value.take() {
+                Some(VariableValue::Associative(elements)) => {
+                    variable.value = Some(VariableValue::Associative(elements));
+                    return Err(format!("{text}: cannot convert associative to indexed array"));
+                },
+                Some(VariableValue::Scalar(value)) => Some(VariableValue::Indexed(BTreeMap::from([(0, value)]))),
+                Some(VariableValue::Indexed(elements)) => Some(VariableValue::Indexed(elements)),
+                None => Some(VariableValue::Indexed(BTreeMap::new())),
+            };
+        }
+        let attributes = &mut variable.attributes;
+        for (flags, value) in [(&options.adding, true), (&options.removing, false)] {
+            for flag in flags.iter() {
+                match flag {
+                    b'i' => attributes.is_integer = value,
+                    b'n' => attributes.is_nameref = value,
+                    b't' => attributes.is_traced = value,
+                    b'x' => attributes.is_exported = value,
+                    b'l' => {
+                        attributes.is_lowercase = value;
+                        attributes.is_uppercase &= !value;
+                    },
+                    b'u' => {
+                        attributes.is_uppercase = value;
+                        attributes.is_lowercase &= !value;
+                    },
+                    _ => {},
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn declare(&self, argument: &[u8], assignment: Option<ExpandedAssignment>, options: &DeclarationOptions, context: &mut BuiltinContext) -> bool {
+        let has_value = assignment.is_some() || argument.contains(&b'=');
+        let assignment = assignment.unwrap_or_else(|| parse_assignment_argument(argument));
+        if !is_valid_name(&assignment.identifier) || (!has_value && assignment.subscript.is_some()) {
+            context.print_error(format_args!("`{}': not a valid identifier", String::from_utf8_lossy(argument)));
+            return false;
+        }
+        // The attributes of a nameref are the ones of the variable it points to, except -n itself
+        let is_reference = options.adding.contains(&b'n') || options.removing.contains(&b'n');
+        let name = match is_reference {
+            true => assignment.identifier.clone(),
+            false => context.state.variables.resolve_reference(&assignment.identifier).map_or(assignment.identifier.clone(), |(name, _)| name),
+        };
+        if let Err(message) = self.set_attributes(&name, options, context) {
+            context.print_error(message);
+            return false;
+        }
+        let state = &mut *context.state;
+        let result = match (has_value, assignment.value) {
+            (false, _) => Ok(()),
+            (true, ExpandedAssignmentValue::Scalar(target)) if options.adding.contains(&b'n') && assignment.subscript.is_none() => {
+                state.variables.set_reference(&name, target).map_err(|err| err.get_message())
+            },
+            (true, value) => perform_assignment(ExpandedAssignment { value, ..assignment }, state).map_err(|err| err.get_message()),
+        };
+        if let Err(message) = result {
+            context.print_error(message);
+            return false;
+        }
+        if options.adding.contains(&b'r') {
+            context.state.variables.declare_visible(&name).attributes.is_readonly = true;
+        }
+        true
+    }
+}
+
+impl Builtin for DeclarationBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        self.name
+    }
+
+    fn is_special(&self) -> bool {
+        matches!(self.kind, DeclarationKind::Export | DeclarationKind::Readonly)
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let (options, start) = match self.parse_options(arguments, context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        let names = &arguments[start..];
+        if self.kind == DeclarationKind::Local && !context.state.variables.is_in_function() {
+            context.print_error("can only be used in a function");
+            return 1;
+        }
+        if options.is_functions || options.is_function_names {
+            return self.print_functions(names, &options, context);
+        }
+        if names.is_empty() {
+            return self.print_variables(&options, context);
+        }
+        if options.is_printing {
+            return self.print_declarations(names, context);
+        }
+        let mut status = 0;
+        for (index, argument) in names.iter().enumerate() {
+            let assignment = context.assignments.remove(&(start + index));
+            if !self.declare(argument, assignment, &options, context) {
+                status = 1;
+            }
+        }
+        status
+    }
+}
@@ -0,0 +1,400 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::path::Path;
+use std::rc::Rc;
+
+use errno::errno;
+
+use super::{Builtin, BuiltinContext};
+
+use crate::shell_execution::process::to_c_string;
+use crate::shell_state::ShellState;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(CdBuiltin), Rc::new(PwdBuiltin), Rc::new(PushdBuiltin), Rc::new(PopdBuiltin), Rc::new(DirsBuiltin)]
+}
+
+fn is_directory(path: &[u8]) -> bool {
+    Path::new(OsStr::from_bytes(path)).is_dir()
+}
+
+fn get_physical_directory() -> Option<Vec<u8>> {
+    std::env::current_dir().ok().map(|path| path.into_os_string().into_vec())
+}
+
+// . and repeated slashes are removed and .. takes away the component before it, without looking at
+// symlinks: /a/link/.. is /a. None when a component before .. is not a directory
+fn canonicalize_logical(path: &[u8]) -> Option<Vec<u8>> {
+    let mut components: Vec<&[u8]> = vec![];
+    for component in path.split(|&c| c == b'/') {
+        match component {
+            b"" | b"." => {},
+            b".." => {
+                if !is_directory(&[b"/".as_slice(), &components.join(&b'/')].concat()) {
+                    return None;
+                }
+                components.pop();
+            },
+            _ => components.push(component),
+        }
+    }
+    Some([b"/".as_slice(), &components.join(&b'/')].concat())
+}
+
+fn change_physical_directory(path: &[u8]) -> Result<(), errno::Errno> {
+    match unsafe { libc::chdir(to_c_string(path).as_ptr()) } {
+        0 => Ok(()),
+        _ => Err(errno()),
+    }
+}
+
+// The new PWD is the logical path unless it cannot be used or is_physical is set, then it is the path
+// without symlinks. OLDPWD gets the previous PWD
+fn change_directory(directory: &[u8], is_physical: bool, context: &mut BuiltinContext) -> Option<Vec<u8>> {
+    let state = &mut *context.state;
+    let old_directory = state.get_working_directory().unwrap_or_default();
+    let absolute = match directory.starts_with(b"/") {
+        true => directory.to_vec(),
+        false => [old_directory.as_slice(), b"/", directory].concat(),
+    };
+    let logical = canonicalize_logical(&absolute).filter(|_| !is_physical);
+    let new_directory = match logical {
+        Some(path) if change_physical_directory(&path).is_ok() => path,
+        _ => match change_physical_directory(directory) {
+            Ok(()) => get_physical_directory().unwrap_or(absolute),
+            Err(err) => {
+                context.print_error(format_args!("{}: {err}", String::from_utf8_lossy(directory)));
+                return None;
+            },
+        },
+    };
+    let _ = state.set_variable(b"OLDPWD", old_directory);
+    let _ = state.set_variable(b"PWD", new_directory.clone());
+    Some(new_directory)
+}
+
+// The current directory first, then the ones saved by pushd
+fn get_directory_stack(state: &ShellState) -> Vec<Vec<u8>> {
+    let current = state.get_working_directory().unwrap_or_default();
+    [vec![current], state.directory_stack.clone()].concat()
+}
+
+// ~ for the home directory unless is_long
+fn format_directory(directory: &[u8], is_long: bool, state: &ShellState) -> Vec<u8> {
+    let home = state.get_variable(b"HOME").filter(|home| !home.is_empty() && home != b"/");
+    match home {
+        Some(home) if !is_long && directory.starts_with(&home) && matches!(directory.get(home.len()), None | Some(b'/')) => {
+            [b"~".as_slice(), &directory[home.len()..]].concat()
+        },
+        _ => directory.to_vec(),
+    }
+}
+
+// +N counts from the left of the list shown by dirs, starting at 0, -N from the right; None when the
+// argument is not an index
+fn parse_stack_index(argument: &[u8], length: usize) -> Option<Result<usize, ()>> {
+    let (is_from_left, digits) = match argument.split_first() {
+        Some((b'+', digits)) => (true, digits),
+        Some((b'-', digits)) => (false, digits),
+        _ => return None,
+    };
+    if digits.is_empty() || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let index: usize = match String::from_utf8_lossy(digits).parse() {
+        Ok(value) if value < length => value,
+        _ => return Some(Err(())),
+    };
+    Some(Ok(if is_from_left { index } else { length - 1 - index }))
+}
+
+fn print_stack(context: &mut BuiltinContext) -> i32 {
+    let stack = get_directory_stack(context.state);
+    let line = stack.iter().map(|directory| format_directory(directory, false, context.state)).collect::<Vec<Vec<u8>>>().join(&b' ');
+    match context.write_line(line) {
+        true => 0,
+        false => 1,
+    }
+}
+
+// The stack becomes the list shown by dirs, changing to its first directory unless is_staying
+fn set_directory_stack(mut stack: Vec<Vec<u8>>, is_staying: bool, context: &mut BuiltinContext) -> i32 {
+    if !is_staying {
+        let Some(directory) = change_directory(&stack[0], false, context) else { return 1 };
+        stack[0] = directory;
+    }
+    context.state.directory_stack = stack.split_off(1);
+    print_stack(context)
+}
+
+// -L, -P and -n style flags until the first other argument; +N and -N are arguments
+fn parse_flags<'a>(
+    arguments: &'a [Vec<u8>],
+    allowed: &[u8],
+    usage: &str,
+    context: &mut BuiltinContext,
+) -> Result<(Vec<u8>, &'a [Vec<u8>]), i32> {
+    let mut flags = vec![];
+    for (index, argument) in arguments.iter().enumerate() {
+        if argument == b"--" {
+            return Ok((flags, &arguments[index + 1..]));
+        }
+        let is_flags = argument.len() > 1 && argument.starts_with(b"-") && !argument[1..].iter().all(u8::is_ascii_digit);
+        if !is_flags {
+            return Ok((flags, &arguments[index..]));
+        }
+        for &flag in &argument[1..] {
+            if !allowed.contains(&flag) {
+                return Err(context.print_invalid_option(format_args!("-{}", flag as char), usage));
+            }
+            flags.push(flag);
+        }
+    }
+    Ok((flags, &[]))
+}
+
+// The directory is searched for in the directories of CDPATH unless it starts with /, . or ..; the
+// bool tells whether the directory found is shown, it is for a non-empty CDPATH entry
+fn search_cdpath(directory: &[u8], state: &ShellState) -> Option<(Vec<u8>, bool)> {
+    let first_component = directory.split(|&c| c == b'/').next().unwrap_or_default();
+    if directory.is_empty() || directory.starts_with(b"/") || first_component == b"." || first_component == b".." {
+        return None;
+    }
+    let cdpath = state.get_variable(b"CDPATH")?;
+    cdpath.split(|&c| c == b':').find_map(|entry| {
+        let candidate = match entry.is_empty() {
+            true => directory.to_vec(),
+            false => [entry, if entry.ends_with(b"/") { b"" } else { b"/" }, directory].concat(),
+        };
+        is_directory(&candidate).then_some((candidate, !entry.is_empty()))
+    })
+}
+
+// cd [-L|-P] [directory]: HOME by default, - is OLDPWD. PWD keeps the path through symlinks unless -P
+struct CdBuiltin;
+
+impl Builtin for CdBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"cd"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let (flags, arguments) = match parse_flags(arguments, b"LPe@", "cd [-L|[-P [-e]] [-@]] [dir]", context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        let is_physical = flags.iter().rev().find(|&&flag| flag == b'L' || flag == b'P') == Some(&b'P');
+        if arguments.len() > 1 {
+            context.print_error("too many arguments");
+            return 1;
+        }
+        let (directory, is_printed) = match arguments.first().map(Vec::as_slice) {
+            None => match context.state.get_variable(b"HOME") {
+                Some(home) => (home, false),
+                None => {
+                    context.print_error("HOME not set");
+                    return 1;
+                },
+            },
+            Some(b"-") => match context.state.get_variable(b"OLDPWD") {
+                Some(directory) => (directory, true),
+                None => {
+                    context.print_error("OLDPWD not set");
+                    return 1;
+                },
+            },
+            Some(directory) => search_cdpath(directory, context.state).unwrap_or((directory.to_vec(), false)),
+        };
+        if directory.is_empty() {
+            return 0;
+        }
+        let Some(new_directory) = change_directory(&directory, is_physical, context) else { return 1 };
+        if is_printed && !context.write_line(new_directory) {
+            return 1;
+        }
+        0
+    }
+}
+
+// pwd [-L|-P]
+struct PwdBuiltin;
+
+impl Builtin for PwdBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"pwd"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let (flags, _) = match parse_flags(arguments, b"LP", "pwd [-LP]", context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        let directory = match flags.last() {
+            Some(b'P') => get_physical_directory(),
+            _ => context.state.get_working_directory(),
+        };
+        let Some(directory) = directory else {
+            context.print_error(format_args!("error retrieving current directory: {}", errno()));
+            return 1;
+        };
+        match context.write_line(directory) {
+            true => 0,
+            false => 1,
+        }
+    }
+}
+
+// dirs [-clpv] [+N|-N]
+struct DirsBuiltin;
+
+impl Builtin for DirsBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"dirs"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "dirs [-clpv] [+N] [-N]";
+        let (flags, arguments) = match parse_flags(arguments, b"clpv", USAGE, context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        if flags.contains(&b'c') {
+            context.state.directory_stack.clear();
+            return 0;
+        }
+        let stack = get_directory_stack(context.state);
+        let is_long = flags.contains(&b'l');
+        let mut entries: Vec<(usize, &Vec<u8>)> = stack.iter().enumerate().collect();
+        if let Some(argument) = arguments.first() {
+            match parse_stack_index(argument, stack.len()) {
+                Some(Ok(index)) => entries = vec![(index, &stack[index])],
+                Some(Err(())) => {
+                    context.print_error(format_args!("{}: directory stack index out of range", String::from_utf8_lossy(argument)));
+                    return 1;
+                },
+                None => return context.print_invalid_option(String::from_utf8_lossy(argument), USAGE),
+            }
+        }
+        let lines: Vec<Vec<u8>> = entries.into_iter().map(|(index, directory)| {
+            let directory = format_directory(directory, is_long, context.state);
+            match flags.contains(&b'v') {
+                true => [format!("{index:2}  ").into_bytes(), directory].concat(),
+                false => directory,
+            }
+        }).collect();
+        let output = match flags.contains(&b'v') || flags.contains(&b'p') {
+            true => lines,
+            false => vec![lines.join(&b' ')],
+        };
+        match output.into_iter().all(|line| context.write_line(line)) {
+            true => 0,
+            false => 1,
+        }
+    }
+}
+
+// pushd [-n] [directory|+N|-N]: without arguments the first two directories are swapped, +N and -N
+// rotate the stack so that entry N comes first; -n only changes the stack
+struct PushdBuiltin;
+
+impl Builtin for PushdBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"pushd"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "pushd [-n] [+N | -N | dir]";
+        let (flags, arguments) = match parse_flags(arguments, b"n", USAGE, context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        let is_staying = flags.contains(&b'n');
+        let mut stack = get_directory_stack(context.state);
+        let Some(argument) = arguments.first() else {
+            if stack.len() < 2 {
+                context.print_error("no other directory");
+                return 1;
+            }
+            if !is_staying {
+                stack.swap(0, 1);
+            }
+            return set_directory_stack(stack, is_staying, context);
+        };
+        if arguments.len() > 1 {
+            context.print_error("too many arguments");
+            return context.print_usage(USAGE);
+        }
+        match parse_stack_index(argument, stack.len()) {
+            Some(Ok(index)) => {
+                match is_staying {
+                    true if index > 0 => stack[1..].rotate_left(index - 1),
+                    true => {},
+                    false => stack.rotate_left(index),
+                }
+                set_directory_stack(stack, is_staying, context)
+            },
+            Some(Err(())) => {
+                context.print_error(format_args!("{}: directory stack index out of range", String::from_utf8_lossy(argument)));
+                1
+            },
+            None if is_staying => {
+                stack.insert(1, argument.clone());
+                set_directory_stack(stack, true, context)
+            },
+            None => {
+                let Some(directory) = change_directory(argument, false, context) else { return 1 };
+                stack.insert(0, directory);
+                set_directory_stack(stack, true, context)
+            },
+        }
+    }
+}
+
+// popd [-n] [+N|-N]: removes the first directory and changes to the next one, or removes entry N; -n
+// removes the second one without changing the directory
+struct PopdBuiltin;
+
+impl Builtin for PopdBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"popd"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "popd [-n] [+N | -N]";
+        let (flags, arguments) = match parse_flags(arguments, b"n", USAGE, context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        let is_staying = flags.contains(&b'n');
+        let mut stack = get_directory_stack(context.state);
+        if stack.len() < 2 {
+            context.print_error("directory stack empty");
+            return 1;
+        }
+        let index = match arguments.first() {
+            None => 0,
+            Some(argument) => match parse_stack_index(argument, stack.len()) {
+                Some(Ok(index)) => index,
+                Some(Err(())) => {
+                    context.print_error(format_args!("{}: directory stack index out of range", String::from_utf8_lossy(argument)));
+                    return 1;
+                },
+                None => return context.print_invalid_option(String::from_utf8_lossy(argument), USAGE),
+            },
+        };
+        match (index, is_staying) {
+            (0, false) => {
+                stack.remove(0);
+                set_directory_stack(stack, false, context)
+            },
+            (0, true) => {
+                stack.remove(1);
+                set_directory_stack(stack, true, context)
+            },
+            _ => {
+                stack.remove(index);
+                set_directory_stack(stack, true, context)
+            },
+        }
+    }
+}
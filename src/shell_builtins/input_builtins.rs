@@ -0,0 +1,435 @@
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use errno::errno;
+use rustyline::error::ReadlineError;
+use rustyline::DefaultEditor;
+
+use super::{Builtin, BuiltinContext};
+
+use crate::shell_state::variables::is_valid_name;
+
+const DEFAULT_IFS: &[u8] = b" \t\n";
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(ReadBuiltin)]
+}
+
+// A character of the input, escaped ones are never delimiters
+type InputChar = (u8, bool);
+
+// How the reading ended: the delimiter, the end of the input or the timeout
+enum ReadEnd {
+    Delimiter,
+    EndOfInput,
+    Timeout,
+    Error(errno::Errno),
+}
+
+struct ReadOptions {
+    is_raw: bool,
+    is_silent: bool,
+    is_editing: bool,
+    array: Option<Vec<u8>>,
+    delimiter: Option<u8>, // None is -N, where no character ends the input
+    limit: Option<usize>, // characters
+    prompt: Option<Vec<u8>>,
+    initial_text: Vec<u8>,
+    timeout: Option<f64>,
+    fd: i32,
+}
+
+// Number of bytes of the UTF-8 character starting with the byte
+fn get_character_length(c: u8) -> usize {
+    match c {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    }
+}
+
+// Canonical mode off, so that -n and -d do not wait for a newline, echo off for -s; the previous
+// settings are returned to restore them
+fn set_terminal_mode(fd: i32, options: &ReadOptions) -> Option<libc::termios> {
+    if unsafe { libc::isatty(fd) } == 0 {
+        return None;
+    }
+    let mut termios: libc::termios = unsafe { std::mem::zeroed() };
+    if unsafe { libc::tcgetattr(fd, &mut termios) } == -1 {
+        return None;
+    }
+    let saved = termios;
+    if options.limit.is_some() || options.delimiter != Some(b'\n') {
+        termios.c_lflag &= !libc::ICANON;
+        termios.c_cc[libc::VMIN] = 1;
+        termios.c_cc[libc::VTIME] = 0;
+    }
+    if options.is_silent {
+        termios.c_lflag &= !(libc::ECHO | libc::ECHOE | libc::ECHOK | libc::ECHONL);
+    }
+    unsafe { libc::tcsetattr(fd, libc::TCSADRAIN, &termios) };
+    Some(saved)
+}
+
+// Bytes are read one at a time, the rest of the input stays for the commands that come after
+struct InputReader {
+    fd: i32,
+    deadline: Option<Instant>,
+}
+
+impl InputReader {
+    fn read_byte(&self) -> Result<Option<u8>, ReadEnd> {
+        loop {
+            if let Some(deadline) = self.deadline {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                let mut poll_fd = libc::pollfd { fd: self.fd, events: libc::POLLIN, revents: 0 };
+                let milliseconds = remaining.as_millis().min(i32::MAX as u128) as i32;
+                match unsafe { libc::poll(&mut poll_fd, 1, milliseconds) } {
+                    0 => return Err(ReadEnd::Timeout),
+                    -1 if errno().0 == libc::EINTR => continue,
+                    -1 => return Err(ReadEnd::Error(errno())),
+                    _ => {},
+                }
+            }
+            let mut c = 0u8;
+            match unsafe { libc::read(self.fd, &mut c as *mut u8 as *mut libc::c_void, 1) } {
+                1 => return Ok(Some(c)),
+                0 => return Ok(None),
+                _ if errno().0 == libc::EINTR => continue,
+                _ => return Err(ReadEnd::Error(errno())),
+            }
+        }
+    }
+
+    // Backslashes escape the next character and join lines unless -r; NUL bytes are dropped
+    fn read_input(&self, options: &ReadOptions) -> (Vec<InputChar>, ReadEnd) {
+        let mut input = vec![];
+        let mut characters = 0;
+        let mut continuation_bytes = 0;
+        let mut is_escaped = false;
+        loop {
+            if options.limit.is_some_and(|limit| characters >= limit) && continuation_bytes == 0 && !is_escaped {
+                return (input, ReadEnd::Delimiter);
+            }
+            let c = match self.read_byte() {
+                Ok(Some(c)) => c,
+                Ok(None) => return (input, ReadEnd::EndOfInput),
+                Err(end) => return (input, end),
+            };
+            if c == 0 && options.delimiter != Some(0) {
+                continue;
+            }
+            if continuation_bytes > 0 && (0x80..=0xBF).contains(&c) {
+                continuation_bytes -= 1;
+                input.push((c, false));
+                continue;
+            }
+            continuation_bytes = 0;
+            if is_escaped {
+                is_escaped = false;
+                if c != b'\n' {
+                    characters += 1;
+                    continuation_bytes = get_character_length(c) - 1;
+                    input.push((c, true));
+                }
+                continue;
+            }
+            if c == b'\\' && !options.is_raw {
+                is_escaped = true;
+                continue;
+            }
+            if Some(c) == options.delimiter {
+                return (input, ReadEnd::Delimiter);
+            }
+            characters += 1;
+            continuation_bytes = get_character_length(c) - 1;
+            input.push((c, false));
+        }
+    }
+}
+
+// -e reads the line with the line editor, which only works on a terminal
+fn read_edited_line(options: &ReadOptions) -> (Vec<InputChar>, ReadEnd) {
+    let mut editor = match DefaultEditor::new() {
+        Ok(value) => value,
+        Err(_) => return (vec![], ReadEnd::EndOfInput),
+    };
+    let prompt = String::from_utf8_lossy(options.prompt.as_deref().unwrap_or_default()).into_owned();
+    let initial_text = String::from_utf8_lossy(&options.initial_text).into_owned();
+    let line = match editor.readline_with_initial(&prompt, (&initial_text, "")) {
+        Ok(value) => value,
+        Err(ReadlineError::Interrupted) => return (vec![], ReadEnd::Error(errno::Errno(libc::EINTR))),
+        Err(_) => return (vec![], ReadEnd::EndOfInput),
+    };
+    let mut input = vec![];
+    let mut bytes = line.into_bytes().into_iter();
+    while let Some(c) = bytes.next() {
+        match c {
+            b'\\' if !options.is_raw => {
+                if let Some(c) = bytes.next() {
+                    input.push((c, true));
+                }
+            },
+            _ => input.push((c, false)),
+        }
+    }
+    (input, ReadEnd::Delimiter)
+}
+
+// Splits the input by IFS: whitespace around the fields is dropped, every other IFS character ends a
+// field
+struct FieldReader<'a> {
+    input: &'a [InputChar],
+    position: usize,
+    whitespace: Vec<u8>,
+    delimiters: Vec<u8>,
+}
+
+impl<'a> FieldReader<'a> {
+    fn new(input: &'a [InputChar], ifs: &[u8]) -> Self {
+        let (whitespace, delimiters) = ifs.iter().partition(|c| b" \t\n".contains(c));
+        let mut result = Self { input, position: 0, whitespace, delimiters };
+        result.skip_whitespace();
+        result
+    }
+
+    fn is_whitespace(&self, (c, is_escaped): InputChar) -> bool {
+        !is_escaped && self.whitespace.contains(&c)
+    }
+
+    fn is_delimiter(&self, (c, is_escaped): InputChar) -> bool {
+        !is_escaped && self.delimiters.contains(&c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.position < self.input.len() && self.is_whitespace(self.input[self.position]) {
+            self.position += 1;
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.position >= self.input.len()
+    }
+
+    // The field and the delimiters after it
+    fn next_field(&mut self) -> Vec<u8> {
+        let start = self.position;
+        while !self.is_done() && !self.is_whitespace(self.input[self.position]) && !self.is_delimiter(self.input[self.position]) {
+            self.position += 1;
+        }
+        let field = self.input[start..self.position].iter().map(|&(c, _)| c).collect();
+        self.skip_whitespace();
+        if !self.is_done() && self.is_delimiter(self.input[self.position]) {
+            self.position += 1;
+            self.skip_whitespace();
+        }
+        field
+    }
+
+    // The last name gets the rest of the line with its delimiters, unless only one field is left
+    fn rest(&mut self) -> Vec<u8> {
+        let start = self.position;
+        let field = self.next_field();
+        if self.is_done() {
+            return field;
+        }
+        let mut end = self.input.len();
+        while end > start && self.is_whitespace(self.input[end - 1]) {
+            end -= 1;
+        }
+        self.position = self.input.len();
+        self.input[start..end].iter().map(|&(c, _)| c).collect()
+    }
+}
+
+// read [-ers] [-a array] [-d delim] [-i text] [-n nchars] [-N nchars] [-p prompt] [-t timeout]
+// [-u fd] [name...]: reads a line and splits it into the names by IFS, the last one gets the rest of
+// the line; without names the whole line goes to REPLY
+struct ReadBuiltin;
+
+impl ReadBuiltin {
+    fn parse_options<'a>(
+        &self,
+        arguments: &'a [Vec<u8>],
+        context: &mut BuiltinContext,
+    ) -> Result<(ReadOptions, &'a [Vec<u8>]), i32> {
+        const USAGE: &str =
+            "read [-ers] [-a array] [-d delim] [-i text] [-n nchars] [-N nchars] [-p prompt] [-t timeout] [-u fd] [name ...]";
+        let mut options = ReadOptions {
+            is_raw: false,
+            is_silent: false,
+            is_editing: false,
+            array: None,
+            delimiter: Some(b'\n'),
+            limit: None,
+            prompt: None,
+            initial_text: vec![],
+            timeout: None,
+            fd: 0,
+        };
+        let mut index = 0;
+        while let Some(argument) = arguments.get(index).filter(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            index += 1;
+            if argument == b"--" {
+                break;
+            }
+            for (position, &flag) in argument.iter().enumerate().skip(1) {
+                match flag {
+                    b'r' => options.is_raw = true,
+                    b's' => options.is_silent = true,
+                    b'e' => options.is_editing = true,
+                    b'a' | b'd' | b'i' | b'n' | b'N' | b'p' | b't' | b'u' => {},
+                    _ => return Err(context.print_invalid_option(format_args!("-{}", flag as char), USAGE)),
+                }
+                if !b"adinNptu".contains(&flag) {
+                    continue;
+                }
+                // The value is the rest of the argument or the next one: -n1 or -n 1
+                let value = match argument.get(position + 1..).filter(|value| !value.is_empty()) {
+                    Some(value) => value.to_vec(),
+                    None => match arguments.get(index) {
+                        Some(value) => {
+                            index += 1;
+                            value.clone()
+                        },
+                        None => {
+                            context.print_error(format_args!("-{}: option requires an argument", flag as char));
+                            return Err(context.print_usage(USAGE));
+                        },
+                    },
+                };
+                let text = String::from_utf8_lossy(&value).into_owned();
+                match flag {
+                    b'a' => options.array = Some(value),
+                    b'd' => options.delimiter = Some(value.first().copied().unwrap_or(0)),
+                    b'i' => options.initial_text = value,
+                    b'p' => options.prompt = Some(value),
+                    b'n' | b'N' => match text.parse() {
+                        Ok(limit) => {
+                            options.limit = Some(limit);
+                            if flag == b'N' {
+                                options.delimiter = None;
+                            }
+                        },
+                        Err(_) => {
+                            context.print_error(format_args!("{text}: invalid number"));
+                            return Err(1);
+                        },
+                    },
+                    b't' => match text.parse::<f64>() {
+                        Ok(timeout) if timeout >= 0.0 && timeout.is_finite() => options.timeout = Some(timeout),
+                        _ => {
+                            context.print_error(format_args!("{text}: invalid timeout specification"));
+                            return Err(1);
+                        },
+                    },
+                    _ => match text.parse::<i32>() {
+                        Ok(fd) if fd >= 0 => {
+                            if unsafe { libc::fcntl(fd, libc::F_GETFD) } == -1 {
+                                context.print_error(format_args!("{text}: invalid file descriptor: {}", errno()));
+                                return Err(1);
+                            }
+                            options.fd = fd;
+                        },
+                        _ => {
+                            context.print_error(format_args!("{text}: invalid file descriptor specification"));
+                            return Err(1);
+                        },
+                    },
+                }
+                break;
+            }
+        }
+        Ok((options, &arguments[index..]))
+    }
+
+    fn assign(&self, input: &[InputChar], options: &ReadOptions, names: &[Vec<u8>], context: &mut BuiltinContext) -> bool {
+        let state = &mut *context.state;
+        let ifs = state.get_variable(b"IFS");
+        let ifs = match options.delimiter {
+            None => b"".as_slice(),
+            Some(_) => ifs.as_deref().unwrap_or(DEFAULT_IFS),
+        };
+        let mut fields = FieldReader::new(input, ifs);
+        let result = if let Some(array) = options.array.as_ref() {
+            let mut elements = vec![];
+            while !fields.is_done() {
+                elements.push((None, fields.next_field()));
+            }
+            state.variables.set_array(array, elements, false)
+        } else if let Some((last, names)) = names.split_last() {
+            let mut result = Ok(());
+            for name in names {
+                let value = fields.next_field();
+                result = result.and(state.set_variable(name, value));
+            }
+            result.and(state.set_variable(last, fields.rest()))
+        } else {
+            state.set_variable(b"REPLY", input.iter().map(|&(c, _)| c).collect())
+        };
+        if let Err(err) = result {
+            context.print_error(err.get_message());
+            return false;
+        }
+        true
+    }
+}
+
+impl Builtin for ReadBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"read"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let (options, names) = match self.parse_options(arguments, context) {
+            Ok(value) => value,
+            Err(status) => return status,
+        };
+        for name in names.iter().chain(options.array.as_ref()) {
+            if !is_valid_name(name) {
+                context.print_error(format_args!("`{}': not a valid identifier", String::from_utf8_lossy(name)));
+                return 1;
+            }
+        }
+        // -t 0 only tells whether there is input to read
+        if options.timeout == Some(0.0) {
+            let mut poll_fd = libc::pollfd { fd: options.fd, events: libc::POLLIN, revents: 0 };
+            return match unsafe { libc::poll(&mut poll_fd, 1, 0) } {
+                1 => 0,
+                _ => 1,
+            };
+        }
+        let is_terminal = unsafe { libc::isatty(options.fd) } == 1;
+        let (input, end) = if options.is_editing && options.fd == 0 && is_terminal {
+            read_edited_line(&options)
+        } else {
+            if let Some(prompt) = options.prompt.as_ref().filter(|_| is_terminal) {
+                eprint!("{}", String::from_utf8_lossy(prompt));
+            }
+            let saved_mode = set_terminal_mode(options.fd, &options);
+            let deadline = options.timeout.map(|timeout| Instant::now() + Duration::from_secs_f64(timeout));
+            let result = InputReader { fd: options.fd, deadline }.read_input(&options);
+            if let Some(saved_mode) = saved_mode {
+                unsafe { libc::tcsetattr(options.fd, libc::TCSADRAIN, &saved_mode) };
+            }
+            result
+        };
+        // What was read before the end of the input or the timeout is still assigned
+        if let ReadEnd::Error(err) = end {
+            if err.0 == libc::EINTR {
+                return 128 + libc::SIGINT;
+            }
+            context.print_error(format_args!("read error: {}: {err}", options.fd));
+            return 1;
+        }
+        if !self.assign(&input, &options, names, context) {
+            return 1;
+        }
+        match end {
+            ReadEnd::Delimiter => 0,
+            ReadEnd::Timeout => 128 + libc::SIGALRM,
+            _ => 1,
+        }
+    }
+}
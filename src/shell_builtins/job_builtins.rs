@@ -0,0 +1,178 @@
+use std::rc::Rc;
+
+use super::{Builtin, BuiltinContext};
+
+use crate::shell_execution::job_control::{keep_stopped_job, reap_children, wait_for_job};
+use crate::shell_execution::process::{give_terminal, take_terminal_back};
+use crate::shell_state::jobs::ProcessStatus;
+use crate::shell_state::shell_options::ShellOption;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(JobsBuiltin), Rc::new(FgBuiltin), Rc::new(BgBuiltin)]
+}
+
+fn find_job(spec: Option<&[u8]>, context: &mut BuiltinContext) -> Option<usize> {
+    match context.state.jobs.find(spec.unwrap_or(b"%+")) {
+        Ok(id) => Some(id),
+        Err(_) if spec.is_none() => {
+            context.print_error("current: no such job");
+            None
+        },
+        Err(err) => {
+            context.print_error(err.get_message());
+            None
+        },
+    }
+}
+
+fn continue_job(pgid: i32) {
+    unsafe { libc::kill(-pgid, libc::SIGCONT) };
+}
+
+fn check_job_control(context: &mut BuiltinContext) -> bool {
+    if context.state.options.is_enabled(ShellOption::Monitor) {
+        return true;
+    }
+    context.print_error("no job control");
+    false
+}
+
+// jobs [-lp] [jobspec...]
+struct JobsBuiltin;
+
+impl Builtin for JobsBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"jobs"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "jobs [-lp] [jobspec ...]";
+        let mut is_long = false;
+        let mut is_pgid_only = false;
+        let mut specs = vec![];
+        for argument in arguments {
+            match argument.as_slice() {
+                [b'-', flags @ ..] if !flags.is_empty() && specs.is_empty() => {
+                    for &flag in flags {
+                        match flag {
+                            b'l' => is_long = true,
+                            b'p' => is_pgid_only = true,
+                            _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                        }
+                    }
+                },
+                _ => specs.push(argument.as_slice()),
+            }
+        }
+        reap_children(context.state);
+        let mut ids = vec![];
+        let mut status = 0;
+        for spec in specs.iter() {
+            match find_job(Some(spec), context) {
+                Some(id) => ids.push(id),
+                None => status = 1,
+            }
+        }
+        if specs.is_empty() {
+            ids = context.state.jobs.iter().map(|job| job.id).collect();
+        }
+        // A finished job shown here is not reported again before the prompt
+        for id in ids {
+            let marker = context.state.jobs.get_marker(id);
+            let Some(job) = context.state.jobs.get_mut(id) else { continue };
+            let line = match is_pgid_only {
+                true => job.pgid.to_string(),
+                false => job.format(marker, is_long.then_some(job.pgid)),
+            };
+            job.is_changed = false;
+            if job.get_status().is_finished() {
+                context.state.jobs.remove(id);
+            }
+            if !context.write_line(line) {
+                return 1;
+            }
+        }
+        status
+    }
+}
+
+// fg [jobspec]: the job gets the terminal and the shell waits for it as for any foreground job
+struct FgBuiltin;
+
+impl Builtin for FgBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"fg"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        if !check_job_control(context) {
+            return 1;
+        }
+        reap_children(context.state);
+        let Some(id) = find_job(arguments.first().map(Vec::as_slice), context) else { return 1 };
+        let Some(mut job) = context.state.jobs.remove(id) else { return 1 };
+        if job.get_status().is_finished() {
+            context.print_error("job has terminated");
+            return 1;
+        }
+        context.write_line(&job.command);
+        let state = &mut *context.state;
+        state.pipeline_pgid = Some(job.pgid);
+        give_terminal(job.pgid);
+        continue_job(job.pgid);
+        job.set_running();
+        wait_for_job(&mut job);
+        let status = job.processes.last().map(|process| process.status.get_exit_status()).unwrap_or(0);
+        if matches!(job.get_status(), ProcessStatus::Stopped(_)) {
+            keep_stopped_job(job, state);
+        }
+        take_terminal_back(state);
+        status
+    }
+}
+
+// bg [jobspec...]: stopped jobs continue in the background
+struct BgBuiltin;
+
+impl Builtin for BgBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"bg"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        if !check_job_control(context) {
+            return 1;
+        }
+        reap_children(context.state);
+        let specs: Vec<Option<&[u8]>> = match arguments.is_empty() {
+            true => vec![None],
+            false => arguments.iter().map(|argument| Some(argument.as_slice())).collect(),
+        };
+        let mut status = 0;
+        for spec in specs {
+            let Some(id) = find_job(spec, context) else {
+                status = 1;
+                continue;
+            };
+            let marker = context.state.jobs.get_marker(id);
+            let Some(job) = context.state.jobs.get_mut(id) else { continue };
+            match job.get_status() {
+                ProcessStatus::Running => {
+                    context.print_error(format_args!("job {id} already in background"));
+                    continue;
+                },
+                job_status if job_status.is_finished() => {
+                    context.print_error("job has terminated");
+                    status = 1;
+                    continue;
+                },
+                _ => {},
+            }
+            job.set_running();
+            continue_job(job.pgid);
+            let line = format!("[{id}]{marker} {} &", String::from_utf8_lossy(&job.command));
+            context.write_line(line);
+        }
+        status
+    }
+}
@@ -0,0 +1,257 @@
+use std::rc::Rc;
+
+use super::{parse_number, quote_value, Builtin, BuiltinContext};
+
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::variables::VariableValue;
+use crate::shell_state::ShellState;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(SetBuiltin), Rc::new(ShoptBuiltin), Rc::new(ShiftBuiltin)]
+}
+
+// name='value' or name=([0]='a' [1]='b'), as set shows the variables
+pub fn format_variable(name: &[u8], value: &VariableValue, state: &ShellState) -> Vec<u8> {
+    let format_element = |key: Vec<u8>, value: &[u8]| [b"[".as_slice(), &key, b"]=", &quote_value(value)].concat();
+    let value = match value {
+        VariableValue::Scalar(_) => quote_value(&state.get_variable(name).unwrap_or_default()),
+        VariableValue::Indexed(elements) => {
+            let elements = elements.iter().map(|(index, value)| format_element(index.to_string().into_bytes(), value));
+            [b"(".as_slice(), &elements.collect::<Vec<Vec<u8>>>().join(&b' '), b")"].concat()
+        },
+        VariableValue::Associative(elements) => {
+            let elements = elements.iter().map(|(key, value)| format_element(quote_value(key), value));
+            [b"(".as_slice(), &elements.collect::<Vec<Vec<u8>>>().join(&b' '), b")"].concat()
+        },
+    };
+    [name, b"=", &value].concat()
+}
+
+// set -o shows every option with its value, set +o the commands that restore them
+fn print_set_options(is_reusable: bool, context: &mut BuiltinContext) -> bool {
+    let options: Vec<(ShellOption, bool)> = context.state.options.iter_set_options().collect();
+    options.into_iter().all(|(option, is_enabled)| {
+        let line = match (is_reusable, is_enabled) {
+            (true, true) => format!("set -o {}", option.get_name()),
+            (true, false) => format!("set +o {}", option.get_name()),
+            (false, _) => format!("{:<15}\t{}", option.get_name(), if is_enabled { "on" } else { "off" }),
+        };
+        context.write_line(line)
+    })
+}
+
+// set [-+CmO] [-+o option] [--] [argument...], without arguments it shows the variables
+struct SetBuiltin;
+
+impl SetBuiltin {
+    fn print_variables(&self, context: &mut BuiltinContext) -> i32 {
+        let state = &*context.state;
+        let lines: Vec<Vec<u8>> = state
+            .variables
+            .get_visible()
+            .into_iter()
+            .filter_map(|(name, variable)| Some(format_variable(name, variable.value.as_ref()?, state)))
+            .collect();
+        match lines.into_iter().all(|line| context.write_line(line)) {
+            true => 0,
+            false => 1,
+        }
+    }
+}
+
+impl Builtin for SetBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"set"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "set [-Cm] [-o option-name] [--] [arg ...]";
+        if arguments.is_empty() {
+            return self.print_variables(context);
+        }
+        let mut arguments = arguments.iter().peekable();
+        let mut parameters = None;
+        while let Some(argument) = arguments.next() {
+            let (is_enabled, flags) = match argument.split_first() {
+                Some((b'-', flags)) => (true, flags),
+                Some((b'+', flags)) => (false, flags),
+                _ => {
+                    parameters = Some(vec![argument.clone()]);
+                    break;
+                },
+            };
+            match flags {
+                b"-" if is_enabled => {
+                    parameters = Some(vec![]);
+                    break;
+                },
+                b"" => {
+                    if arguments.peek().is_some() {
+                        parameters = Some(vec![]);
+                    }
+                    break;
+                },
+                _ => {},
+            }
+            for &flag in flags {
+                let option = match flag {
+                    b'o' => match arguments.next() {
+                        None => match print_set_options(!is_enabled, context) {
+                            true => continue,
+                            false => return 1,
+                        },
+                        Some(name) => match ShellOption::from_set_name(name) {
+                            Some(option) => option,
+                            None => {
+                                context.print_error(format_args!("{}: invalid option name", String::from_utf8_lossy(name)));
+                                return 2;
+                            },
+                        },
+                    },
+                    _ => match ShellOption::from_flag(flag) {
+                        Some(option) => option,
+                        None => {
+                            let sign = if is_enabled { '-' } else { '+' };
+                            return context.print_invalid_option(format_args!("{sign}{}", flag as char), USAGE);
+                        },
+                    },
+                };
+                context.state.options.set(option, is_enabled);
+            }
+        }
+        if let Some(mut parameters) = parameters {
+            parameters.extend(arguments.cloned());
+            context.state.set_positional_parameters(parameters);
+        }
+        0
+    }
+}
+
+// shopt [-pqsu] [-o] [optname...]
+struct ShoptBuiltin;
+
+impl Builtin for ShoptBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"shopt"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "shopt [-pqsu] [-o] [optname ...]";
+        let mut value = None;
+        let mut is_reusable = false;
+        let mut is_quiet = false;
+        let mut is_set_option = false;
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            if argument == b"--" {
+                break;
+            }
+            for &flag in &argument[1..] {
+                match flag {
+                    b's' | b'u' => {
+                        let is_enabled = flag == b's';
+                        if value == Some(!is_enabled) {
+                            context.print_error("cannot set and unset shell options simultaneously");
+                            return 1;
+                        }
+                        value = Some(is_enabled);
+                    },
+                    b'p' => is_reusable = true,
+                    b'q' => is_quiet = true,
+                    b'o' => is_set_option = true,
+                    _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                }
+            }
+        }
+        let names: Vec<&Vec<u8>> = arguments.collect();
+        let mut options = vec![];
+        let mut status = 0;
+        for name in names.iter() {
+            let option = match is_set_option {
+                true => ShellOption::from_set_name(name),
+                false => ShellOption::from_name(name),
+            };
+            match option {
+                Some(option) => options.push(option),
+                None => {
+                    context.print_error(format_args!("{}: invalid shell option name", String::from_utf8_lossy(name)));
+                    status = 1;
+                },
+            }
+        }
+        if let (Some(is_enabled), false) = (value, names.is_empty()) {
+            for option in options {
+                context.state.options.set(option, is_enabled);
+            }
+            return status;
+        }
+        if names.is_empty() {
+            options = match is_set_option {
+                true => context.state.options.iter_set_options().map(|(option, _)| option).collect(),
+                false => context.state.options.iter().map(|(option, _)| option).collect(),
+            };
+        }
+        for option in options {
+            let is_enabled = context.state.options.is_enabled(option);
+            if value.is_some_and(|value| value != is_enabled) {
+                continue;
+            }
+            if !is_enabled && !names.is_empty() {
+                status = 1;
+            }
+            if is_quiet {
+                continue;
+            }
+            let line = match (is_reusable, is_set_option) {
+                (true, true) => format!("set {}o {}", if is_enabled { '-' } else { '+' }, option.get_name()),
+                (true, false) => format!("shopt {} {}", if is_enabled { "-s" } else { "-u" }, option.get_name()),
+                (false, _) => format!("{:<15}\t{}", option.get_name(), if is_enabled { "on" } else { "off" }),
+            };
+            if !context.write_line(line) {
+                return 1;
+            }
+        }
+        status
+    }
+}
+
+// shift [n], n defaults to 1 and cannot be more than $#
+struct ShiftBuiltin;
+
+impl Builtin for ShiftBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"shift"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let count = match arguments.first() {
+            None => 1,
+            Some(argument) => match parse_number(argument) {
+                Some(value) => value,
+                None => {
+                    context.print_error(format_args!("{}: numeric argument required", String::from_utf8_lossy(argument)));
+                    return 1;
+                },
+            },
+        };
+        let parameters = context.state.get_positional_parameters();
+        if count < 0 {
+            context.print_error(format_args!("{count}: shift count out of range"));
+            return 1;
+        }
+        if count as usize > parameters.len() {
+            return 1;
+        }
+        let parameters = parameters[count as usize..].to_vec();
+        context.state.set_positional_parameters(parameters);
+        0
+    }
+}
@@ -0,0 +1,526 @@
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{quote_word, Builtin, BuiltinContext};
+
+use crate::shell_expansions::parameter_expansion::get_array_key;
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::variables::is_valid_reference;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(EchoBuiltin), Rc::new(PrintfBuiltin)]
+}
+
+fn parse_digits(text: &[u8], radix: u32, max_digits: usize) -> (u32, usize) {
+    let length = text.iter().take(max_digits).take_while(|&&c| (c as char).is_digit(radix)).count();
+    let value = std::str::from_utf8(&text[..length]).ok().and_then(|digits| u32::from_str_radix(digits, radix).ok());
+    (value.unwrap_or(0), length)
+}
+
+// Backslash escapes of echo -e and printf %b, where octal values start with \0 and \c ends all of
+// the output; in printf formats octal values have no 0 before them. The flag tells whether \c was found
+fn expand_escapes(text: &[u8], is_format: bool) -> (Vec<u8>, bool) {
+    let mut result = vec![];
+    let mut index = 0;
+    while index < text.len() {
+        let c = text[index];
+        index += 1;
+        if c != b'\\' || index == text.len() {
+            result.push(c);
+            continue;
+        }
+        let next_char = text[index];
+        index += 1;
+        match next_char {
+            b'a' => result.push(0x07),
+            b'b' => result.push(0x08),
+            b'e' | b'E' => result.push(0x1B),
+            b'f' => result.push(0x0C),
+            b'n' => result.push(b'\n'),
+            b'r' => result.push(b'\r'),
+            b't' => result.push(b'\t'),
+            b'v' => result.push(0x0B),
+            b'\\' => result.push(b'\\'),
+            b'"' if is_format => result.push(b'"'),
+            b'c' if !is_format => return (result, true),
+            b'0'..=b'7' if is_format || next_char == b'0' => {
+                let (start, max_digits) = if is_format { (index - 1, 3) } else { (index, 3) };
+                let (value, length) = parse_digits(&text[start..], 8, max_digits);
+                index = start + length;
+                result.push(value as u8);
+            },
+            b'x' => match parse_digits(&text[index..], 16, 2) {
+                (_, 0) => result.extend(b"\\x"),
+                (value, length) => {
+                    index += length;
+                    result.push(value as u8);
+                },
+            },
+            b'u' | b'U' => {
+                let max_digits = if next_char == b'u' { 4 } else { 8 };
+                match parse_digits(&text[index..], 16, max_digits) {
+                    (value, length) if length > 0 && char::from_u32(value).is_some() => {
+                        index += length;
+                        result.extend(char::from_u32(value).unwrap_or_default().to_string().into_bytes());
+                    },
+                    _ => result.extend([b'\\', next_char]),
+                }
+            },
+            _ => result.extend([b'\\', next_char]),
+        }
+    }
+    (result, false)
+}
+
+// echo [-neE] [argument...]: -e interprets backslash escapes, which xpg_echo does by default
+struct EchoBuiltin;
+
+impl Builtin for EchoBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"echo"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let mut is_newline = true;
+        let mut is_escaping = context.state.options.is_enabled(ShellOption::XpgEcho);
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| {
+            argument.len() > 1 && argument[0] == b'-' && argument[1..].iter().all(|c| b"neE".contains(c))
+        }) {
+            for &flag in &argument[1..] {
+                match flag {
+                    b'n' => is_newline = false,
+                    b'e' => is_escaping = true,
+                    _ => is_escaping = false,
+                }
+            }
+        }
+        let mut output = arguments.cloned().collect::<Vec<Vec<u8>>>().join(&b' ');
+        if is_escaping {
+            let (expanded, is_stopped) = expand_escapes(&output, false);
+            output = expanded;
+            is_newline &= !is_stopped;
+        }
+        if is_newline {
+            output.push(b'\n');
+        }
+        match context.write_output(&output) {
+            true => 0,
+            false => 1,
+        }
+    }
+}
+
+enum FormatError {
+    InvalidCharacter(u8),
+    MissingCharacter,
+}
+
+// Widths and precisions can be * to take them from the arguments
+enum FormatNumber {
+    Fixed(i64),
+    FromArgument,
+}
+
+// A conversion of a printf format: %-10.3s has the flag -, width 10 and precision 3
+struct FormatSpecification {
+    flags: Vec<u8>,
+    width: Option<FormatNumber>,
+    precision: Option<FormatNumber>,
+    conversion: u8,
+    time_format: Vec<u8>, // %(...)T
+}
+
+enum FormatItem {
+    Literal(Vec<u8>),
+    Conversion(FormatSpecification),
+}
+
+fn parse_format_number(format: &[u8], index: &mut usize) -> Option<FormatNumber> {
+    if format.get(*index) == Some(&b'*') {
+        *index += 1;
+        return Some(FormatNumber::FromArgument);
+    }
+    let length = format[*index..].iter().take_while(|c| c.is_ascii_digit()).count();
+    if length == 0 {
+        return None;
+    }
+    let value = String::from_utf8_lossy(&format[*index..*index + length]).parse().unwrap_or(i64::MAX);
+    *index += length;
+    Some(FormatNumber::Fixed(value))
+}
+
+// The format is parsed once, then used as many times as needed for the arguments
+fn parse_format(format: &[u8]) -> Result<Vec<FormatItem>, FormatError> {
+    let mut items = vec![];
+    let mut literal = vec![];
+    let mut index = 0;
+    while index < format.len() {
+        let c = format[index];
+        index += 1;
+        if c == b'\\' {
+            let end = match format.get(index) {
+                Some(b'x') => index + 1 + parse_digits(&format[index + 1..], 16, 2).1,
+                Some(b'u') => index + 1 + parse_digits(&format[index + 1..], 16, 4).1,
+                Some(b'U') => index + 1 + parse_digits(&format[index + 1..], 16, 8).1,
+                Some(b'0'..=b'7') => index + parse_digits(&format[index..], 8, 3).1,
+                Some(_) => index + 1,
+                None => index,
+            };
+            literal.extend(expand_escapes(&format[index - 1..end], true).0);
+            index = end;
+            continue;
+        }
+        if c != b'%' {
+            literal.push(c);
+            continue;
+        }
+        if format.get(index) == Some(&b'%') {
+            literal.push(b'%');
+            index += 1;
+            continue;
+        }
+        let flags_length = format[index..].iter().take_while(|c| b"-+ #0'".contains(c)).count();
+        let flags = format[index..index + flags_length].to_vec();
+        index += flags_length;
+        let width = parse_format_number(format, &mut index);
+        let precision = match format.get(index) {
+            Some(b'.') => {
+                index += 1;
+                Some(parse_format_number(format, &mut index).unwrap_or(FormatNumber::Fixed(0)))
+            },
+            _ => None,
+        };
+        let mut time_format = vec![];
+        if format.get(index) == Some(&b'(') {
+            let end = format[index..].iter().position(|&c| c == b')').ok_or(FormatError::MissingCharacter)?;
+            time_format = format[index + 1..index + end].to_vec();
+            index += end + 1;
+        }
+        index += format[index..].iter().take_while(|c| b"hjlLtz".contains(c)).count();
+        let conversion = *format.get(index).ok_or(FormatError::MissingCharacter)?;
+        index += 1;
+        let is_valid = match time_format.is_empty() && format[index - 2] != b')' {
+            true => b"diouxXfFeEgGaAcsbq".contains(&conversion),
+            false => conversion == b'T',
+        };
+        if !is_valid {
+            return Err(FormatError::InvalidCharacter(conversion));
+        }
+        items.push(FormatItem::Literal(std::mem::take(&mut literal)));
+        items.push(FormatItem::Conversion(FormatSpecification { flags, width, precision, conversion, time_format }));
+    }
+    items.push(FormatItem::Literal(literal));
+    Ok(items)
+}
+
+// Numeric arguments can be decimal, octal with a leading 0, hexadecimal with 0x, or a quote followed by
+// a character, whose code is the value
+fn parse_integer(argument: &[u8]) -> Result<i64, i64> {
+    if let Some(rest) = argument.strip_prefix(b"'").or_else(|| argument.strip_prefix(b"\"")) {
+        let text = String::from_utf8_lossy(rest);
+        return Ok(text.chars().next().map_or(0, |c| c as i64));
+    }
+    let text = String::from_utf8_lossy(argument);
+    let text = text.trim_start();
+    let (is_negative, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let (radix, digits) = match digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        Some(hex_digits) => (16, hex_digits),
+        None if digits.len() > 1 && digits.starts_with('0') => (8, &digits[1..]),
+        None => (10, digits),
+    };
+    let length = digits.chars().take_while(|c| c.is_digit(radix)).count();
+    let value = i64::from_str_radix(&digits[..length], radix).unwrap_or(if length == 0 { 0 } else { i64::MAX });
+    let value = if is_negative { value.wrapping_neg() } else { value };
+    match length == digits.len() && !digits.is_empty() || argument.is_empty() {
+        true => Ok(value),
+        false => Err(value),
+    }
+}
+
+fn parse_float(argument: &[u8]) -> Result<f64, f64> {
+    if argument.starts_with(b"'") || argument.starts_with(b"\"") {
+        return parse_integer(argument).map(|value| value as f64).map_err(|value| value as f64);
+    }
+    let text = String::from_utf8_lossy(argument);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0.0);
+    }
+    if let Ok(value) = text.parse::<f64>() {
+        return Ok(value);
+    }
+    parse_integer(argument).map(|value| value as f64).map_err(|value| value as f64)
+}
+
+enum CValue {
+    Integer(i64),
+    Float(f64),
+}
+
+// Numbers are formatted by the C library, which knows every flag and conversion
+fn format_number(specification: &[u8], value: CValue) -> Vec<u8> {
+    let Ok(format) = std::ffi::CString::new(specification) else { return vec![] };
+    let mut buffer = vec![0u8; 64];
+    loop {
+        let length = unsafe {
+            match value {
+                CValue::Integer(value) => {
+                    libc::snprintf(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len(), format.as_ptr(), value as libc::c_longlong)
+                },
+                CValue::Float(value) => libc::snprintf(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len(), format.as_ptr(), value),
+            }
+        };
+        if length < 0 {
+            return vec![];
+        }
+        if (length as usize) < buffer.len() {
+            buffer.truncate(length as usize);
+            return buffer;
+        }
+        buffer.resize(length as usize + 1, 0);
+    }
+}
+
+// strftime in the local time zone, an empty format is the one of the locale
+fn format_time(format: &[u8], time: i64) -> Vec<u8> {
+    let format = if format.is_empty() { b"%X".as_slice() } else { format };
+    let Ok(format) = std::ffi::CString::new(format) else { return vec![] };
+    let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+    let time = time as libc::time_t;
+    if unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+        return vec![];
+    }
+    let mut buffer = vec![0u8; 256];
+    loop {
+        let length = unsafe { libc::strftime(buffer.as_mut_ptr() as *mut libc::c_char, buffer.len(), format.as_ptr(), &tm) };
+        if length > 0 || buffer.len() > 65536 {
+            buffer.truncate(length);
+            return buffer;
+        }
+        buffer.resize(buffer.len() * 4, 0);
+    }
+}
+
+// Strings are padded to the width, the precision is the most bytes that are kept
+fn pad_string(mut value: Vec<u8>, is_left: bool, width: Option<i64>, precision: Option<i64>) -> Vec<u8> {
+    if let Some(precision) = precision {
+        value.truncate(precision as usize);
+    }
+    let padding = vec![b' '; (width.unwrap_or(0).unsigned_abs() as usize).saturating_sub(value.len())];
+    match is_left || width.is_some_and(|width| width < 0) {
+        true => [value, padding].concat(),
+        false => [padding, value].concat(),
+    }
+}
+
+fn get_current_time() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs() as i64)
+}
+
+// The arguments consumed by the conversions, the format is used again while some are left
+struct Formatter<'a> {
+    arguments: &'a [Vec<u8>],
+    next_argument: usize,
+    invalid_numbers: Vec<Vec<u8>>,
+}
+
+impl<'a> Formatter<'a> {
+    fn take_argument(&mut self) -> &'a [u8] {
+        let Some(argument) = self.arguments.get(self.next_argument) else { return b"" };
+        self.next_argument += 1;
+        argument
+    }
+
+    // Invalid numbers are reported once the output is done, what could be read of them is used
+    fn take_integer(&mut self) -> i64 {
+        let argument = self.take_argument();
+        parse_integer(argument).unwrap_or_else(|value| {
+            self.invalid_numbers.push(argument.to_vec());
+            value
+        })
+    }
+
+    fn take_float(&mut self) -> f64 {
+        let argument = self.take_argument();
+        parse_float(argument).unwrap_or_else(|value| {
+            self.invalid_numbers.push(argument.to_vec());
+            value
+        })
+    }
+
+    fn resolve(&mut self, number: &Option<FormatNumber>) -> Option<i64> {
+        match number {
+            Some(FormatNumber::Fixed(value)) => Some(*value),
+            Some(FormatNumber::FromArgument) => Some(self.take_integer()),
+            None => None,
+        }
+    }
+
+    // The flag tells whether %b found \c, which ends the output
+    fn format(&mut self, specification: &FormatSpecification, start_time: i64) -> (Vec<u8>, bool) {
+        let width = self.resolve(&specification.width);
+        let precision = self.resolve(&specification.precision).filter(|&precision| precision >= 0);
+        let is_left = specification.flags.contains(&b'-');
+        let conversion = specification.conversion;
+        let mut c_specification = b"%".to_vec();
+        c_specification.extend(specification.flags.iter().filter(|&&flag| flag != b'\''));
+        if let Some(width) = width {
+            c_specification.extend(width.to_string().into_bytes());
+        }
+        if let Some(precision) = precision {
+            c_specification.extend(format!(".{precision}").into_bytes());
+        }
+        let text = match conversion {
+            b'd' | b'i' | b'o' | b'u' | b'x' | b'X' => {
+                c_specification.extend([b'l', b'l', if conversion == b'i' { b'd' } else { conversion }]);
+                format_number(&c_specification, CValue::Integer(self.take_integer()))
+            },
+            b'f' | b'F' | b'e' | b'E' | b'g' | b'G' | b'a' | b'A' => {
+                c_specification.push(conversion);
+                format_number(&c_specification, CValue::Float(self.take_float()))
+            },
+            b'c' => pad_string(self.take_argument().iter().take(1).copied().collect(), is_left, width, None),
+            b's' => pad_string(self.take_argument().to_vec(), is_left, width, precision),
+            b'q' => pad_string(quote_word(self.take_argument()), is_left, width, precision),
+            b'b' => {
+                let (value, is_stopped) = expand_escapes(self.take_argument(), false);
+                return (pad_string(value, is_left, width, precision), is_stopped);
+            },
+            _ => {
+                // %(...)T: -1 or nothing is now, -2 is when the shell started
+                let time = match self.take_argument() {
+                    b"" => -1,
+                    _ => {
+                        self.next_argument -= 1;
+                        self.take_integer()
+                    },
+                };
+                let time = match time {
+                    -1 => get_current_time(),
+                    -2 => start_time,
+                    _ => time,
+                };
+                pad_string(format_time(&specification.time_format, time), is_left, width, precision)
+            },
+        };
+        (text, false)
+    }
+}
+
+// printf [-v name] format [argument...]: the format is reused until the arguments run out, missing
+// arguments are empty strings or zeros
+struct PrintfBuiltin;
+
+impl PrintfBuiltin {
+    fn assign(&self, name: &[u8], value: Vec<u8>, context: &mut BuiltinContext) -> bool {
+        let state = &mut *context.state;
+        let result = match name.iter().position(|&c| c == b'[') {
+            Some(index) => {
+                let subscript = &name[index + 1..name.len() - 1];
+                match get_array_key(&name[..index], subscript, state) {
+                    Ok(key) => state.variables.set_element(&name[..index], key, value, false),
+                    Err(err) => {
+                        context.print_error(err.get_message());
+                        return false;
+                    },
+                }
+            },
+            None => state.variables.set_scalar(name, value, false),
+        };
+        if let Err(err) = result {
+            context.print_error(err.get_message());
+            return false;
+        }
+        true
+    }
+}
+
+impl Builtin for PrintfBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"printf"
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "printf [-v var] format [arguments]";
+        let mut variable = None;
+        let mut arguments = arguments;
+        loop {
+            match arguments.first().map(Vec::as_slice) {
+                Some(b"--") => {
+                    arguments = &arguments[1..];
+                    break;
+                },
+                Some(b"-v") => match arguments.get(1) {
+                    Some(name) => {
+                        if !is_valid_reference(name) || name.contains(&b'[') && !name.ends_with(b"]") {
+                            context.print_error(format_args!("`{}': not a valid identifier", String::from_utf8_lossy(name)));
+                            return 2;
+                        }
+                        variable = Some(name.clone());
+                        arguments = &arguments[2..];
+                    },
+                    None => {
+                        context.print_error("-v: option requires an argument");
+                        return context.print_usage(USAGE);
+                    },
+                },
+                Some([b'-', flag, ..]) => {
+                    return context.print_invalid_option(format_args!("-{}", *flag as char), USAGE);
+                },
+                _ => break,
+            }
+        }
+        let Some((format, arguments)) = arguments.split_first() else { return context.print_usage(USAGE) };
+        let items = match parse_format(format) {
+            Ok(value) => value,
+            Err(FormatError::InvalidCharacter(c)) => {
+                context.print_error(format_args!("`{}': invalid format character", c as char));
+                return 1;
+            },
+            Err(FormatError::MissingCharacter) => {
+                context.print_error("`%': missing format character");
+                return 1;
+            },
+        };
+        let mut formatter = Formatter { arguments, next_argument: 0, invalid_numbers: vec![] };
+        let start_time = context.state.start_time;
+        let mut output = vec![];
+        'outer: loop {
+            let start = formatter.next_argument;
+            for item in items.iter() {
+                match item {
+                    FormatItem::Literal(text) => output.extend(text),
+                    FormatItem::Conversion(specification) => {
+                        let (text, is_stopped) = formatter.format(specification, start_time);
+                        output.extend(text);
+                        if is_stopped {
+                            break 'outer;
+                        }
+                    },
+                }
+            }
+            if formatter.next_argument == start || formatter.next_argument >= arguments.len() {
+                break;
+            }
+        }
+        let status = if formatter.invalid_numbers.is_empty() { 0 } else { 1 };
+        for argument in formatter.invalid_numbers {
+            context.print_error(format_args!("{}: invalid number", String::from_utf8_lossy(&argument)));
+        }
+        match variable {
+            Some(name) => match self.assign(&name, output, context) {
+                true => status,
+                false => 1,
+            },
+            None => match context.write_output(&output) {
+                true => status,
+                false => 1,
+            },
+        }
+    }
+}
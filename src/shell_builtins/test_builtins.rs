@@ -0,0 +1,168 @@
+use std::rc::Rc;
+
+use super::{Builtin, BuiltinContext};
+
+use crate::shell_execution::conditional_expression::{
+    evaluate_file_comparison, evaluate_unary_test, FILE_COMPARISON_OPERATORS, UNARY_OPERATORS,
+};
+use crate::shell_state::ShellState;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(TestBuiltin { name: b"test" }), Rc::new(TestBuiltin { name: b"[" })]
+}
+
+const BINARY_OPERATORS: [&[u8]; 11] = [b"=", b"==", b"!=", b"<", b">", b"-eq", b"-ne", b"-lt", b"-le", b"-gt", b"-ge"];
+
+fn is_binary_operator(argument: &[u8]) -> bool {
+    BINARY_OPERATORS.contains(&argument) || FILE_COMPARISON_OPERATORS.contains(&argument)
+}
+
+// Integers of test are decimal, with blanks around them allowed
+fn parse_integer(argument: &[u8]) -> Result<i64, String> {
+    let text = String::from_utf8_lossy(argument);
+    text.trim_matches([' ', '\t', '\n']).parse().map_err(|_| format!("{text}: integer expression expected"))
+}
+
+fn evaluate_binary(operator: &[u8], left: &[u8], right: &[u8]) -> Result<bool, String> {
+    let compare_integers = || Ok::<_, String>(parse_integer(left)?.cmp(&parse_integer(right)?));
+    Ok(match operator {
+        b"=" | b"==" => left == right,
+        b"!=" => left != right,
+        b"<" => left < right,
+        b">" => left > right,
+        b"-eq" => compare_integers()?.is_eq(),
+        b"-ne" => compare_integers()?.is_ne(),
+        b"-lt" => compare_integers()?.is_lt(),
+        b"-le" => compare_integers()?.is_le(),
+        b"-gt" => compare_integers()?.is_gt(),
+        b"-ge" => compare_integers()?.is_ge(),
+        b"-a" => !left.is_empty() && !right.is_empty(),
+        b"-o" => !left.is_empty() || !right.is_empty(),
+        _ => evaluate_file_comparison(operator, left, right),
+    })
+}
+
+// Expressions of more than four arguments, -o binds less than -a, which binds less than !
+struct TestParser<'a> {
+    arguments: &'a [Vec<u8>],
+    position: usize,
+    state: &'a mut ShellState,
+}
+
+impl<'a> TestParser<'a> {
+    fn peek(&self, offset: usize) -> Option<&'a [u8]> {
+        self.arguments.get(self.position + offset).map(Vec::as_slice)
+    }
+
+    fn parse_or(&mut self) -> Result<bool, String> {
+        let mut result = self.parse_and()?;
+        while self.peek(0) == Some(b"-o") {
+            self.position += 1;
+            result |= self.parse_and()?;
+        }
+        Ok(result)
+    }
+
+    fn parse_and(&mut self) -> Result<bool, String> {
+        let mut result = self.parse_term()?;
+        while self.peek(0) == Some(b"-a") {
+            self.position += 1;
+            result &= self.parse_term()?;
+        }
+        Ok(result)
+    }
+
+    // Binary operators are tried first, so that = is a string when it comes before one
+    fn parse_term(&mut self) -> Result<bool, String> {
+        let Some(argument) = self.peek(0) else { return Err("argument expected".to_string()) };
+        if argument == b"!" {
+            self.position += 1;
+            return Ok(!self.parse_term()?);
+        }
+        if argument == b"(" {
+            self.position += 1;
+            let result = self.parse_or()?;
+            if self.peek(0) != Some(b")") {
+                return Err("`)' expected".to_string());
+            }
+            self.position += 1;
+            return Ok(result);
+        }
+        if let (Some(operator), Some(right)) = (self.peek(1), self.peek(2)) {
+            if is_binary_operator(operator) {
+                self.position += 3;
+                return evaluate_binary(operator, argument, right);
+            }
+        }
+        if UNARY_OPERATORS.contains(&argument) {
+            let Some(operand) = self.peek(1) else {
+                return Err(format!("{}: unary operator expected", String::from_utf8_lossy(argument)));
+            };
+            self.position += 2;
+            return Ok(evaluate_unary_test(argument, operand, self.state));
+        }
+        self.position += 1;
+        Ok(!argument.is_empty())
+    }
+}
+
+// POSIX decides by the number of arguments, so that [ "$x" = -n ] compares strings whatever $x is
+fn evaluate(arguments: &[Vec<u8>], state: &mut ShellState) -> Result<bool, String> {
+    let is_not = arguments.first().is_some_and(|argument| argument == b"!");
+    let is_parenthesized = |length| arguments.len() == length && arguments[0] == b"(" && arguments[length - 1] == b")";
+    match arguments.len() {
+        0 => Ok(false),
+        1 => Ok(!arguments[0].is_empty()),
+        2 if is_not => Ok(arguments[1].is_empty()),
+        2 if UNARY_OPERATORS.contains(&arguments[0].as_slice()) => Ok(evaluate_unary_test(&arguments[0], &arguments[1], state)),
+        2 => Err(format!("{}: unary operator expected", String::from_utf8_lossy(&arguments[0]))),
+        3 if is_binary_operator(&arguments[1]) || arguments[1] == b"-a" || arguments[1] == b"-o" => {
+            evaluate_binary(&arguments[1], &arguments[0], &arguments[2])
+        },
+        3 if is_not => evaluate(&arguments[1..], state).map(|result| !result),
+        3 if is_parenthesized(3) => Ok(!arguments[1].is_empty()),
+        3 => Err(format!("{}: binary operator expected", String::from_utf8_lossy(&arguments[1]))),
+        4 if is_not => evaluate(&arguments[1..], state).map(|result| !result),
+        4 if is_parenthesized(4) => evaluate(&arguments[1..3], state),
+        _ => {
+            let mut parser = TestParser { arguments, position: 0, state };
+            let result = parser.parse_or()?;
+            match parser.peek(0) {
+                Some(_) => Err("too many arguments".to_string()),
+                None => Ok(result),
+            }
+        },
+    }
+}
+
+// test expression and [ expression ]: status 0 when it is true, 1 when false and 2 on errors
+struct TestBuiltin {
+    name: &'static [u8],
+}
+
+impl Builtin for TestBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        self.name
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        let arguments = match self.name {
+            b"[" => match arguments.split_last() {
+                Some((last, arguments)) if last == b"]" => arguments,
+                _ => {
+                    context.print_error("missing `]'");
+                    return 2;
+                },
+            },
+            _ => arguments,
+        };
+        match evaluate(arguments, context.state) {
+            Ok(true) => 0,
+            Ok(false) => 1,
+            Err(message) => {
+                context.print_error(message);
+                2
+            },
+        }
+    }
+}
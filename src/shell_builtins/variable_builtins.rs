@@ -0,0 +1,96 @@
+use std::rc::Rc;
+
+use super::{Builtin, BuiltinContext};
+
+use crate::shell_expansions::parameter_expansion::get_array_key;
+use crate::shell_state::variables::is_valid_name;
+
+pub fn get_builtins() -> Vec<Rc<dyn Builtin>> {
+    vec![Rc::new(UnsetBuiltin)]
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum UnsetTarget {
+    Any, // the variable, or the function when there is no such variable
+    Variable,
+    Function,
+    Reference,
+}
+
+// unset [-f|-v|-n] name..., name[subscript] unsets an element of an array
+struct UnsetBuiltin;
+
+impl UnsetBuiltin {
+    fn unset(&self, argument: &[u8], target: UnsetTarget, context: &mut BuiltinContext) -> bool {
+        if target == UnsetTarget::Function {
+            context.state.functions.remove(argument);
+            return true;
+        }
+        let (name, subscript) = match argument.iter().position(|&c| c == b'[') {
+            Some(index) if argument.ends_with(b"]") => (&argument[..index], Some(&argument[index + 1..argument.len() - 1])),
+            _ => (argument, None),
+        };
+        if !is_valid_name(name) {
+            context.print_error(format_args!("`{}': not a valid identifier", String::from_utf8_lossy(argument)));
+            return false;
+        }
+        let state = &mut *context.state;
+        let result = match subscript {
+            Some(b"@" | b"*") | None if target == UnsetTarget::Reference => state.variables.unset_reference(name),
+            Some(b"@" | b"*") => state.variables.unset(name),
+            Some(subscript) => match get_array_key(name, subscript, state) {
+                Ok(key) => state.variables.unset_element(name, &key),
+                Err(err) => {
+                    context.print_error(err.get_message());
+                    return false;
+                },
+            },
+            None if target == UnsetTarget::Any && state.variables.get_unresolved(name).is_none() => {
+                state.functions.remove(name);
+                Ok(())
+            },
+            None => state.variables.unset(name),
+        };
+        if let Err(err) = result {
+            context.print_error(err.get_message());
+            return false;
+        }
+        true
+    }
+}
+
+impl Builtin for UnsetBuiltin {
+    fn get_name(&self) -> &'static [u8] {
+        b"unset"
+    }
+
+    fn is_special(&self) -> bool {
+        true
+    }
+
+    fn run(&self, arguments: &[Vec<u8>], context: &mut BuiltinContext) -> i32 {
+        const USAGE: &str = "unset [-f] [-v] [-n] [name ...]";
+        let mut target = UnsetTarget::Any;
+        let mut arguments = arguments.iter().peekable();
+        while let Some(argument) = arguments.next_if(|argument| argument.starts_with(b"-") && argument.len() > 1) {
+            if argument == b"--" {
+                break;
+            }
+            for &flag in &argument[1..] {
+                target = match flag {
+                    b'f' => UnsetTarget::Function,
+                    b'v' => UnsetTarget::Variable,
+                    b'n' => UnsetTarget::Reference,
+                    _ => return context.print_invalid_option(format_args!("-{}", flag as char), USAGE),
+                };
+            }
+        }
+        let mut status = 0;
+        for argument in arguments {
+            if !self.unset(argument, target, context) {
+                status = 1;
+            }
+        }
+        status
+    }
+}
@@ -0,0 +1,94 @@
+pub mod command_search;
+pub mod process;
+pub mod simple_command;
+pub mod command_substitution;
+pub mod pipeline;
+pub mod job_control;
+pub mod redirection;
+pub mod compound_command;
+pub mod function;
+pub mod conditional_expression;
+
+use crate::shell_input_iterator::ShellInputIterator;
+use crate::shell_parsers::shell_input_parser::ShellInputParser;
+use crate::shell_state::{ControlFlow, ShellState};
+use crate::shell_structures::shell_command::ShellCommand;
+use crate::shell_structures::shell_list::{ShellAndOrList, ShellList, ShellListOperator};
+
+use compound_command::execute_compound_command;
+use function::define_function;
+use job_control::start_background_job;
+use pipeline::execute_pipeline;
+use simple_command::execute_simple_command;
+
+// Runs the command and stores its status in $?
+pub fn execute_command(command: &ShellCommand, state: &mut ShellState) -> i32 {
+    let status = match command {
+        ShellCommand::Simple(command) => execute_simple_command(command, state),
+        ShellCommand::Compound(command, redirections) => execute_compound_command(command, redirections, state),
+        ShellCommand::FunctionDefinition(definition) => {
+            define_function(definition, state);
+            0
+        },
+        ShellCommand::Coproc() => {
+            eprintln!("cosh: coprocesses are not supported");
+            2
+        },
+    };
+    // exit in a compound command or a function ends all of them with its status
+    let status = match state.control_flow {
+        Some(ControlFlow::Exit(exit_status)) => exit_status,
+        _ => status,
+    };
+    state.last_exit_status = status;
+    status
+}
+
+// The pipeline after && runs when the status so far is 0, the one after || when it is not
+pub fn execute_and_or_list(list: &ShellAndOrList, state: &mut ShellState) -> i32 {
+    let mut status = execute_pipeline(&list.first, state);
+    for (operator, pipeline) in list.rest.iter() {
+        let is_run = match operator {
+            ShellListOperator::And => status == 0,
+            ShellListOperator::Or => status != 0,
+        };
+        if state.control_flow.is_some() {
+            break;
+        }
+        if is_run {
+            status = execute_pipeline(pipeline, state);
+        }
+    }
+    status
+}
+
+pub fn execute_list(list: &ShellList, state: &mut ShellState) -> i32 {
+    let mut status = 0;
+    for item in list.items.iter() {
+        status = match item.is_background {
+            true => start_background_job(&item.and_or_list, state),
+            false => execute_and_or_list(&item.and_or_list, state),
+        };
+        state.last_exit_status = status;
+        if state.control_flow.is_some() {
+            break;
+        }
+    }
+    status
+}
+
+// Scripts and the contents of command substitutions, everything is parsed before anything runs
+pub fn execute_input(input: &[u8], state: &mut ShellState) -> i32 {
+    let parsing_rules = state.get_parsing_rules();
+    let mut parser = ShellInputParser::new(ShellInputIterator::new(input.iter().copied().peekable()), &parsing_rules);
+    let status = match parser.parse() {
+        Ok(Some(list)) => return execute_list(&list, state),
+        Ok(None) => 0,
+        Err(_) => {
+            eprintln!("cosh: syntax error");
+            2
+        },
+    };
+    state.last_exit_status = status;
+    status
+}
@@ -0,0 +1,84 @@
+use std::ffi::{CString, OsStr};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use crate::shell_state::ShellState;
+
+// Searched when PATH is unset
+const DEFAULT_PATH: &[u8] = b"/usr/local/sbin:/usr/local/bin:/usr/sbin:/usr/bin:/sbin:/bin:.";
+
+fn is_file(path: &[u8]) -> bool {
+    Path::new(OsStr::from_bytes(path)).is_file()
+}
+
+fn is_executable_file(path: &[u8]) -> bool {
+    let c_path = match CString::new(path) {
+        Ok(value) => value,
+        Err(_) => return false,
+    };
+    is_file(path) && unsafe { libc::access(c_path.as_ptr(), libc::X_OK) } == 0
+}
+
+// The first executable file in the directories of PATH, an empty directory is the current one;
+// without an executable one the first plain file is returned, so that running it reports the error
+fn search_path(name: &[u8], search_path: &[u8]) -> Option<Vec<u8>> {
+    let mut first_file = None;
+    for directory in search_path.split(|&c| c == b':') {
+        let directory = if directory.is_empty() { b".".as_slice() } else { directory };
+        let path = [directory, b"/", name].concat();
+        if is_executable_file(&path) {
+            return Some(path);
+        }
+        if first_file.is_none() && is_file(&path) {
+            first_file = Some(path);
+        }
+    }
+    first_file
+}
+
+// Names with a slash are used as they are, the others are looked up in PATH, remembering the result;
+// a remembered file that has been removed since is searched for again
+pub fn find_command(name: &[u8], state: &mut ShellState) -> Option<Vec<u8>> {
+    if name.contains(&b'/') {
+        return Some(name.to_vec());
+    }
+    let path_variable = state.get_variable(b"PATH").unwrap_or_else(|| DEFAULT_PATH.to_vec());
+    if let Some(command) = state.command_hash.get(name, &path_variable) {
+        if is_file(&command.path) {
+            command.hits += 1;
+            return Some(command.path.clone());
+        }
+        state.command_hash.remove(name);
+    }
+    let path = search_path(name, &path_variable)?;
+    if is_executable_file(&path) {
+        state.command_hash.insert(name, path.clone());
+        if let Some(command) = state.command_hash.get(name, &path_variable) {
+            command.hits += 1;
+        }
+    }
+    Some(path)
+}
+
+// command -p: the default directories, whatever PATH is, without remembering the result
+pub fn find_command_in_default_path(name: &[u8]) -> Option<Vec<u8>> {
+    if name.contains(&b'/') {
+        return Some(name.to_vec());
+    }
+    search_path(name, DEFAULT_PATH)
+}
+
+// The executable file a name would run, for command -v and hash, without remembering it; the flag
+// tells whether the location is already remembered
+pub fn locate_command(name: &[u8], state: &mut ShellState) -> Option<(Vec<u8>, bool)> {
+    if name.contains(&b'/') {
+        return is_executable_file(name).then(|| (name.to_vec(), false));
+    }
+    let path_variable = state.get_variable(b"PATH").unwrap_or_else(|| DEFAULT_PATH.to_vec());
+    if let Some(command) = state.command_hash.get(name, &path_variable) {
+        if is_file(&command.path) {
+            return Some((command.path.clone(), true));
+        }
+    }
+    search_path(name, &path_variable).filter(|path| is_executable_file(path)).map(|path| (path, false))
+}
@@ -0,0 +1,56 @@
+use std::fs::File;
+use std::io::Read;
+use std::os::fd::FromRawFd;
+
+use errno::errno;
+
+use super::execute_input;
+use super::process::{exit_child, flush_output, reset_signals, wait_for_process};
+
+use crate::shell_expansions::word_expansion::ExpansionError;
+use crate::shell_state::jobs::JobTable;
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ShellState;
+
+// $(command) runs in a forked copy of the shell, the output loses its trailing newlines and NUL bytes
+pub fn run_command_substitution(command: &[u8], state: &mut ShellState) -> Result<Vec<u8>, ExpansionError> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+        return Err(ExpansionError::CommandSubstitution(errno()));
+    }
+    let [read_fd, write_fd] = fds;
+    flush_output();
+    let pid = unsafe { libc::fork() };
+    if pid == -1 {
+        let err = errno();
+        unsafe {
+            libc::close(read_fd);
+            libc::close(write_fd);
+        }
+        return Err(ExpansionError::CommandSubstitution(err));
+    }
+    if pid == 0 {
+        reset_signals();
+        state.options.set(ShellOption::Monitor, false);
+        state.can_exec_directly = false;
+        state.jobs = JobTable::default();
+        state.variables.dynamic.reseed_random();
+        unsafe {
+            libc::close(read_fd);
+            libc::dup2(write_fd, libc::STDOUT_FILENO);
+            libc::close(write_fd);
+        }
+        exit_child(execute_input(command, state));
+    }
+    unsafe { libc::close(write_fd) };
+    let mut output = vec![];
+    let mut reader = unsafe { File::from_raw_fd(read_fd) };
+    let _ = reader.read_to_end(&mut output);
+    drop(reader);
+    state.last_substitution_status = Some(wait_for_process(pid));
+    output.retain(|&c| c != 0);
+    while output.last() == Some(&b'\n') {
+        output.pop();
+    }
+    Ok(output)
+}
@@ -0,0 +1,133 @@
+use super::execute_list;
+use super::job_control::wait_for_foreground;
+use super::process::{exit_child, fork_process};
+use super::redirection::{perform_redirections, restore_descriptors};
+
+use crate::shell_expansions::word_expansion::expand_word;
+use crate::shell_state::{ControlFlow, ShellState};
+use crate::shell_structures::shell_command::shell_compound_command::{ShellCompoundCommand, ShellIfBranch};
+use crate::shell_structures::shell_list::ShellList;
+use crate::shell_structures::shell_redirection::ShellRedirection;
+use crate::shell_structures::shell_word::ShellWord;
+
+fn execute_subshell(list: &ShellList, state: &mut ShellState) -> i32 {
+    match fork_process(state, true) {
+        Ok(0) => exit_child(execute_list(list, state)),
+        Ok(pid) => wait_for_foreground(&[pid], state)[0],
+        Err(err) => {
+            eprintln!("cosh: fork: {err}");
+            1
+        },
+    }
+}
+
+// Without a branch taken the status is 0
+fn execute_if(branches: &[ShellIfBranch], else_body: Option<&ShellList>, state: &mut ShellState) -> i32 {
+    for branch in branches {
+        let condition_status = execute_list(&branch.condition, state);
+        if state.control_flow.is_some() {
+            return condition_status;
+        }
+        if condition_status == 0 {
+            return execute_list(&branch.body, state);
+        }
+    }
+    else_body.map_or(0, |body| execute_list(body, state))
+}
+
+// True when the loop has to stop. break n and continue n take one level off on the way out, the
+// loop they are meant for carries on with them
+fn is_loop_ended(state: &mut ShellState) -> bool {
+    match state.control_flow {
+        Some(ControlFlow::Break(1)) => state.control_flow = None,
+        Some(ControlFlow::Break(n)) => state.control_flow = Some(ControlFlow::Break(n - 1)),
+        Some(ControlFlow::Continue(1)) => {
+            state.control_flow = None;
+            return false;
+        },
+        Some(ControlFlow::Continue(n)) => state.control_flow = Some(ControlFlow::Continue(n - 1)),
+        Some(_) => {},
+        None => return false,
+    }
+    true
+}
+
+// The status is the one of the last run of the body, 0 when it never ran
+fn execute_while(condition: &ShellList, body: &ShellList, is_until: bool, state: &mut ShellState) -> i32 {
+    state.loop_depth += 1;
+    let mut status = 0;
+    loop {
+        let condition_status = execute_list(condition, state);
+        if state.control_flow.is_some() {
+            if is_loop_ended(state) {
+                status = condition_status;
+                break;
+            }
+            continue;
+        }
+        if (condition_status == 0) == is_until {
+            break;
+        }
+        status = execute_list(body, state);
+        if is_loop_ended(state) {
+            break;
+        }
+    }
+    state.loop_depth -= 1;
+    status
+}
+
+// Without "in" the loop goes over the positional parameters
+fn execute_for(variable: &[u8], words: Option<&[ShellWord]>, body: &ShellList, state: &mut ShellState) -> i32 {
+    let values = match words {
+        Some(words) => {
+            let mut values = vec![];
+            for word in words {
+                match expand_word(word, state) {
+                    Ok(fields) => values.extend(fields),
+                    Err(err) => {
+                        eprintln!("cosh: {}", err.get_message());
+                        return 1;
+                    },
+                }
+            }
+            values
+        },
+        None => state.get_positional_parameters().to_vec(),
+    };
+    state.loop_depth += 1;
+    let mut status = 0;
+    for value in values {
+        if let Err(err) = state.variables.set_loop_variable(variable, value) {
+            eprintln!("cosh: {}", err.get_message());
+            status = 1;
+            break;
+        }
+        status = execute_list(body, state);
+        if is_loop_ended(state) {
+            break;
+        }
+    }
+    state.loop_depth -= 1;
+    status
+}
+
+// The redirections apply to the whole command and are undone afterwards
+pub fn execute_compound_command(command: &ShellCompoundCommand, redirections: &[ShellRedirection], state: &mut ShellState) -> i32 {
+    let saved = match perform_redirections(redirections, state, true) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("cosh: {}", err.get_message());
+            return 1;
+        },
+    };
+    let status = match command {
+        ShellCompoundCommand::BraceGroup(list) => execute_list(list, state),
+        ShellCompoundCommand::Subshell(list) => execute_subshell(list, state),
+        ShellCompoundCommand::If { branches, else_body } => execute_if(branches, else_body.as_ref(), state),
+        ShellCompoundCommand::While { condition, body, is_until } => execute_while(condition, body, *is_until, state),
+        ShellCompoundCommand::For { variable, words, body } => execute_for(variable, words.as_deref(), body, state),
+    };
+    restore_descriptors(saved);
+    status
+}
@@ -0,0 +1,99 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt};
+
+use super::process::to_c_string;
+
+use crate::shell_expansions::parameter_expansion::get_array_key;
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ShellState;
+
+// The operators test and [[ ]] have in common; string and integer comparisons differ between them,
+// [[ ]] matches patterns and evaluates arithmetic
+pub const UNARY_OPERATORS: [&[u8]; 26] = [
+    b"-a", b"-b", b"-c", b"-d", b"-e", b"-f", b"-g", b"-h", b"-k", b"-p", b"-r", b"-s", b"-t", b"-u", b"-w", b"-x",
+    b"-G", b"-L", b"-N", b"-O", b"-S", b"-z", b"-n", b"-o", b"-v", b"-R",
+];
+
+pub const FILE_COMPARISON_OPERATORS: [&[u8]; 3] = [b"-ef", b"-nt", b"-ot"];
+
+fn get_metadata(path: &[u8]) -> Option<std::fs::Metadata> {
+    if path.is_empty() {
+        return None;
+    }
+    std::fs::metadata(OsStr::from_bytes(path)).ok()
+}
+
+// -r, -w and -x use the effective ids, root can read anything and execute what has an execute bit
+fn is_accessible(path: &[u8], mode: i32) -> bool {
+    !path.is_empty() && unsafe { libc::faccessat(libc::AT_FDCWD, to_c_string(path).as_ptr(), mode, libc::AT_EACCESS) } == 0
+}
+
+// -v name or -v name[subscript]
+fn is_variable_set(operand: &[u8], state: &mut ShellState) -> bool {
+    match operand.iter().position(|&c| c == b'[') {
+        Some(index) if operand.ends_with(b"]") => {
+            let name = &operand[..index];
+            match get_array_key(name, &operand[index + 1..operand.len() - 1], state) {
+                Ok(key) => state.variables.get_element(name, &key).is_some(),
+                Err(_) => false,
+            }
+        },
+        _ => state.get_variable(operand).is_some(),
+    }
+}
+
+// Symbolic links are followed except by -h and -L
+pub fn evaluate_unary_test(operator: &[u8], operand: &[u8], state: &mut ShellState) -> bool {
+    let metadata = || get_metadata(operand);
+    let mode = || metadata().map_or(0, |metadata| metadata.permissions().mode());
+    match operator {
+        b"-a" | b"-e" => metadata().is_some(),
+        b"-b" => metadata().is_some_and(|metadata| metadata.file_type().is_block_device()),
+        b"-c" => metadata().is_some_and(|metadata| metadata.file_type().is_char_device()),
+        b"-d" => metadata().is_some_and(|metadata| metadata.is_dir()),
+        b"-f" => metadata().is_some_and(|metadata| metadata.is_file()),
+        b"-g" => mode() & libc::S_ISGID != 0,
+        b"-h" | b"-L" => std::fs::symlink_metadata(OsStr::from_bytes(operand)).is_ok_and(|metadata| metadata.is_symlink()),
+        b"-k" => mode() & libc::S_ISVTX != 0,
+        b"-p" => metadata().is_some_and(|metadata| metadata.file_type().is_fifo()),
+        b"-r" => is_accessible(operand, libc::R_OK),
+        b"-s" => metadata().is_some_and(|metadata| metadata.len() > 0),
+        b"-S" => metadata().is_some_and(|metadata| metadata.file_type().is_socket()),
+        b"-t" => String::from_utf8_lossy(operand).trim().parse().is_ok_and(|fd: i32| unsafe { libc::isatty(fd) } == 1),
+        b"-u" => mode() & libc::S_ISUID != 0,
+        b"-w" => is_accessible(operand, libc::W_OK),
+        b"-x" => is_accessible(operand, libc::X_OK),
+        b"-G" => metadata().is_some_and(|metadata| metadata.gid() == unsafe { libc::getegid() }),
+        b"-O" => metadata().is_some_and(|metadata| metadata.uid() == unsafe { libc::geteuid() }),
+        b"-N" => metadata().is_some_and(|metadata| {
+            (metadata.mtime(), metadata.mtime_nsec()) > (metadata.atime(), metadata.atime_nsec())
+        }),
+        b"-z" => operand.is_empty(),
+        b"-n" => !operand.is_empty(),
+        b"-o" => ShellOption::from_set_name(operand).is_some_and(|option| state.options.is_enabled(option)),
+        b"-v" => is_variable_set(operand, state),
+        b"-R" => state.variables.is_reference(operand),
+        _ => false,
+    }
+}
+
+// -nt and -ot: a file that exists is newer than one that does not
+pub fn evaluate_file_comparison(operator: &[u8], left: &[u8], right: &[u8]) -> bool {
+    let get_time = |path| get_metadata(path).map(|metadata| (metadata.mtime(), metadata.mtime_nsec()));
+    match operator {
+        b"-ef" => match (get_metadata(left), get_metadata(right)) {
+            (Some(left), Some(right)) => left.dev() == right.dev() && left.ino() == right.ino(),
+            _ => false,
+        },
+        b"-nt" => match (get_time(left), get_time(right)) {
+            (Some(left), Some(right)) => left > right,
+            (left, _) => left.is_some(),
+        },
+        b"-ot" => match (get_time(left), get_time(right)) {
+            (Some(left), Some(right)) => left < right,
+            (_, right) => right.is_some(),
+        },
+        _ => false,
+    }
+}
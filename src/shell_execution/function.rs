@@ -0,0 +1,103 @@
+use std::rc::Rc;
+
+use super::compound_command::execute_compound_command;
+
+use crate::shell_expansions::assignment_expansion::{expand_assignment, perform_assignment};
+use crate::shell_expansions::word_expansion::ExpansionError;
+use crate::shell_state::functions::{CallFrame, ShellFunction};
+use crate::shell_state::variables::ArrayKey;
+use crate::shell_state::{ControlFlow, ShellState};
+use crate::shell_structures::shell_command::shell_function_definition::ShellFunctionDefinition;
+use crate::shell_structures::shell_command::shell_simple_command::ShellSimpleCommand;
+
+// Functions defined outside of a script are shown as coming from "main"
+pub fn define_function(definition: &Rc<ShellFunctionDefinition>, state: &mut ShellState) {
+    let source = match state.is_interactive {
+        true => b"main".to_vec(),
+        false => state.shell_name.clone(),
+    };
+    state.functions.define(definition.clone(), source);
+}
+
+// FUNCNAME, BASH_SOURCE and BASH_LINENO list the calls from the innermost one, a script adds "main"
+// at the bottom; they only exist while a function runs
+fn update_call_stack_variables(state: &mut ShellState) {
+    const NAMES: [&[u8]; 3] = [b"FUNCNAME", b"BASH_SOURCE", b"BASH_LINENO"];
+    if state.functions.call_stack.is_empty() {
+        for name in NAMES {
+            let _ = state.variables.unset(name);
+        }
+        return;
+    }
+    let mut frames: Vec<CallFrame> = state.functions.call_stack.iter().rev().cloned().collect();
+    if !state.is_interactive {
+        frames.push(CallFrame { name: b"main".to_vec(), source: state.shell_name.clone(), line: 0 });
+    }
+    let columns: [Vec<Vec<u8>>; 3] = [
+        frames.iter().map(|frame| frame.name.clone()).collect(),
+        frames.iter().map(|frame| frame.source.clone()).collect(),
+        frames.iter().map(|frame| frame.line.to_string().into_bytes()).collect(),
+    ];
+    for (name, values) in NAMES.into_iter().zip(columns) {
+        let elements = values.into_iter().enumerate().map(|(index, value)| (Some(ArrayKey::Index(index as i64)), value)).collect();
+        let _ = state.variables.set_array(name, elements, false);
+    }
+}
+
+// A positive FUNCNEST limits how deep the calls can go
+fn check_nesting_level(name: &[u8], state: &ShellState) -> bool {
+    let limit: usize = match state.get_variable(b"FUNCNEST").and_then(|value| String::from_utf8_lossy(&value).trim().parse().ok()) {
+        Some(value) if value > 0 => value,
+        _ => return true,
+    };
+    if state.functions.call_stack.len() < limit {
+        return true;
+    }
+    eprintln!("cosh: {}: maximum function nesting level exceeded ({limit})", String::from_utf8_lossy(name));
+    false
+}
+
+// Prefix assignments become exported locals of the call: LANG=C f
+pub fn perform_local_assignments(command: &ShellSimpleCommand, state: &mut ShellState) -> Result<(), ExpansionError> {
+    for assignment in command.assignments.iter() {
+        let assignment = expand_assignment(assignment, state)?;
+        state.variables.declare_innermost(&assignment.identifier).attributes.is_exported = true;
+        perform_assignment(assignment, state)?;
+    }
+    Ok(())
+}
+
+// The arguments replace the positional parameters for the duration of the call
+pub fn call_function(
+    function: &ShellFunction,
+    arguments: Vec<Vec<u8>>,
+    command: &ShellSimpleCommand,
+    state: &mut ShellState,
+) -> Result<i32, ExpansionError> {
+    let definition = function.definition.as_ref();
+    if !check_nesting_level(&definition.name, state) {
+        return Ok(1);
+    }
+    let saved_parameters = state.get_positional_parameters().to_vec();
+    state.variables.push_scope();
+    if let Err(err) = perform_local_assignments(command, state) {
+        state.variables.pop_scope();
+        return Err(err);
+    }
+    state.set_positional_parameters(arguments.into_iter().skip(1).collect());
+    let line = state.variables.dynamic.line_number;
+    state.functions.call_stack.push(CallFrame { name: definition.name.clone(), source: function.source.clone(), line });
+    update_call_stack_variables(state);
+    // The loops around the call are out of reach of break and continue in the body
+    let saved_loop_depth = std::mem::take(&mut state.loop_depth);
+    let status = execute_compound_command(&definition.body, &definition.redirections, state);
+    state.loop_depth = saved_loop_depth;
+    if state.control_flow == Some(ControlFlow::Return) {
+        state.control_flow = None;
+    }
+    state.functions.call_stack.pop();
+    update_call_stack_variables(state);
+    state.variables.pop_scope();
+    state.set_positional_parameters(saved_parameters);
+    Ok(status)
+}
@@ -0,0 +1,148 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use errno::errno;
+
+use super::execute_and_or_list;
+use super::process::{exit_child, fork_process, wait_for_process};
+
+use crate::shell_state::jobs::{Job, JobProcess, ProcessStatus};
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ShellState;
+use crate::shell_structures::shell_command::ShellCommand;
+use crate::shell_structures::shell_list::ShellAndOrList;
+use crate::shell_structures::ShellToken;
+
+// Set by the SIGCHLD handler, the children are only reaped before the next prompt
+static IS_CHILD_STATUS_CHANGED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn handle_child_signal(_signal: libc::c_int) {
+    IS_CHILD_STATUS_CHANGED.store(true, Ordering::SeqCst);
+}
+
+pub fn install_child_signal_handler() {
+    unsafe {
+        let mut action: libc::sigaction = std::mem::zeroed();
+        action.sa_sigaction = handle_child_signal as extern "C" fn(libc::c_int) as usize;
+        action.sa_flags = libc::SA_RESTART;
+        libc::sigemptyset(&mut action.sa_mask);
+        libc::sigaction(libc::SIGCHLD, &action, std::ptr::null_mut());
+    }
+}
+
+// Returns when the process is finished or stopped
+fn wait_for_change(pid: libc::pid_t) -> ProcessStatus {
+    let mut wait_status = 0;
+    loop {
+        if unsafe { libc::waitpid(pid, &mut wait_status, libc::WUNTRACED) } != -1 {
+            return ProcessStatus::from_wait_status(wait_status);
+        }
+        if errno().0 != libc::EINTR {
+            return ProcessStatus::Exited(127);
+        }
+    }
+}
+
+// Stopped processes are all in the process group of the job, Ctrl-Z stops every one of them
+pub fn wait_for_job(job: &mut Job) {
+    for process in job.processes.iter_mut().filter(|process| process.status == ProcessStatus::Running) {
+        process.status = wait_for_change(process.pid);
+    }
+}
+
+// A stopped foreground job joins the job table and becomes the current job
+pub fn keep_stopped_job(job: Job, state: &mut ShellState) {
+    let id = state.jobs.insert(Job { is_changed: false, ..job });
+    if let Some(job) = state.jobs.get(id) {
+        eprintln!("\n{}", job.format(state.jobs.get_marker(id), None));
+    }
+}
+
+// Without job control nothing can stop, the statuses are in the order of the processes
+pub fn wait_for_foreground(pids: &[libc::pid_t], state: &mut ShellState) -> Vec<i32> {
+    if !state.options.is_enabled(ShellOption::Monitor) {
+        return pids.iter().map(|&pid| wait_for_process(pid)).collect();
+    }
+    let processes = pids.iter().map(|&pid| JobProcess { pid, status: ProcessStatus::Running }).collect();
+    let pgid = state.pipeline_pgid.or(pids.first().copied()).unwrap_or(0);
+    let mut job = Job { id: 0, pgid, command: state.foreground_command.clone(), processes, is_changed: false };
+    wait_for_job(&mut job);
+    let statuses = job.processes.iter().map(|process| process.status.get_exit_status()).collect();
+    if matches!(job.get_status(), ProcessStatus::Stopped(_)) {
+        keep_stopped_job(job, state);
+    }
+    statuses
+}
+
+// The and-or list runs in a forked shell with its own process group, without job control its
+// standard input is /dev/null
+pub fn start_background_job(list: &ShellAndOrList, state: &mut ShellState) -> i32 {
+    state.pipeline_pgid = None;
+    let is_job_control = state.options.is_enabled(ShellOption::Monitor);
+    let pid = match fork_process(state, false) {
+        Ok(0) => {
+            if !is_job_control {
+                let fd = unsafe { libc::open(c"/dev/null".as_ptr(), libc::O_RDONLY) };
+                if fd != -1 {
+                    unsafe {
+                        libc::dup2(fd, libc::STDIN_FILENO);
+                        libc::close(fd);
+                    }
+                }
+            }
+            state.can_exec_directly = list.rest.is_empty()
+                && matches!(list.first.stages.as_slice(), [stage] if matches!(stage.command, ShellCommand::Simple(_)));
+            exit_child(execute_and_or_list(list, state));
+        },
+        Ok(pid) => pid,
+        Err(err) => {
+            eprintln!("cosh: fork: {err}");
+            return 1;
+        },
+    };
+    let pgid = state.pipeline_pgid.take().unwrap_or(pid);
+    let processes = vec![JobProcess { pid, status: ProcessStatus::Running }];
+    let id = state.jobs.add(pgid, processes, list.restore_original());
+    state.last_background_pid = Some(pid);
+    if state.is_interactive {
+        eprintln!("[{id}] {pid}");
+    }
+    0
+}
+
+pub fn reap_children(state: &mut ShellState) {
+    let mut wait_status = 0;
+    loop {
+        let options = libc::WNOHANG | libc::WUNTRACED | libc::WCONTINUED;
+        let pid = unsafe { libc::waitpid(-1, &mut wait_status, options) };
+        if pid <= 0 {
+            return;
+        }
+        state.jobs.update_process(pid, ProcessStatus::from_wait_status(wait_status));
+    }
+}
+
+// Finished jobs leave the table once they have been reported
+fn update_jobs(state: &mut ShellState) {
+    IS_CHILD_STATUS_CHANGED.store(false, Ordering::SeqCst);
+    reap_children(state);
+    let changed: Vec<usize> = state.jobs.iter().filter(|job| job.is_changed).map(|job| job.id).collect();
+    for id in changed {
+        let marker = state.jobs.get_marker(id);
+        let Some(job) = state.jobs.get_mut(id) else { continue };
+        job.is_changed = false;
+        let status = job.get_status();
+        if status != ProcessStatus::Running {
+            println!("{}", job.format(marker, None));
+        }
+        if status.is_finished() {
+            state.jobs.remove(id);
+        }
+    }
+}
+
+// Called before every prompt, a shell that is not interactive keeps finished jobs until jobs shows them
+pub fn notify_job_changes(state: &mut ShellState) {
+    if state.is_interactive && IS_CHILD_STATUS_CHANGED.load(Ordering::SeqCst) {
+        update_jobs(state);
+    }
+}
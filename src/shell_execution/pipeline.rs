@@ -0,0 +1,132 @@
+use errno::{errno, Errno};
+
+use super::execute_command;
+use super::job_control::wait_for_foreground;
+use super::process::{exit_child, fork_process, take_terminal_back};
+
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ShellState;
+use crate::shell_structures::shell_command::ShellCommand;
+use crate::shell_structures::shell_pipeline::{ShellPipeline, ShellPipelineStage};
+use crate::shell_structures::ShellToken;
+
+fn create_pipe() -> Result<(i32, i32), Errno> {
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+        return Err(errno());
+    }
+    Ok((fds[0], fds[1]))
+}
+
+// shopt -s lastpipe: the last stage runs in the shell itself with its standard input from the pipe
+fn execute_in_shell_with_input(command: &ShellCommand, input_fd: i32, state: &mut ShellState) -> i32 {
+    let saved_fd = unsafe { libc::fcntl(libc::STDIN_FILENO, libc::F_DUPFD_CLOEXEC, 10) };
+    unsafe {
+        libc::dup2(input_fd, libc::STDIN_FILENO);
+        libc::close(input_fd);
+    }
+    let status = execute_command(command, state);
+    unsafe {
+        if saved_fd == -1 {
+            libc::close(libc::STDIN_FILENO);
+        } else {
+            libc::dup2(saved_fd, libc::STDIN_FILENO);
+            libc::close(saved_fd);
+        }
+    }
+    status
+}
+
+fn execute_stage_in_child(stage: &ShellPipelineStage, input_fd: Option<i32>, output_fds: Option<(i32, i32)>, state: &mut ShellState) -> ! {
+    unsafe {
+        if let Some(fd) = input_fd {
+            libc::dup2(fd, libc::STDIN_FILENO);
+            libc::close(fd);
+        }
+        if let Some((read_fd, write_fd)) = output_fds {
+            libc::close(read_fd);
+            libc::dup2(write_fd, libc::STDOUT_FILENO);
+            libc::close(write_fd);
+            if stage.is_stderr_piped {
+                libc::dup2(libc::STDOUT_FILENO, libc::STDERR_FILENO);
+            }
+        }
+    }
+    state.can_exec_directly = matches!(stage.command, ShellCommand::Simple(_));
+    exit_child(execute_command(&stage.command, state));
+}
+
+// Every stage runs in a forked child, builtins included, the statuses are in the order of the stages
+fn execute_stages(stages: &[ShellPipelineStage], state: &mut ShellState) -> Vec<i32> {
+    let is_last_in_shell = state.options.is_enabled(ShellOption::LastPipe) && !state.options.is_enabled(ShellOption::Monitor);
+    let mut input_fd: Option<i32> = None;
+    let mut pids = vec![];
+    let mut last_status = None;
+    for (index, stage) in stages.iter().enumerate() {
+        let is_last = index + 1 == stages.len();
+        if let (true, true, Some(fd)) = (is_last, is_last_in_shell, input_fd) {
+            input_fd = None;
+            last_status = Some(execute_in_shell_with_input(&stage.command, fd, state));
+            break;
+        }
+        let output_fds = match is_last {
+            true => None,
+            false => match create_pipe() {
+                Ok(value) => Some(value),
+                Err(err) => {
+                    eprintln!("cosh: pipe: {err}");
+                    last_status = Some(1);
+                    break;
+                },
+            },
+        };
+        match fork_process(state, true) {
+            Ok(0) => execute_stage_in_child(stage, input_fd, output_fds, state),
+            Ok(pid) => pids.push(pid),
+            Err(err) => {
+                eprintln!("cosh: fork: {err}");
+                last_status = Some(1);
+            },
+        }
+        if let Some(fd) = input_fd.take() {
+            unsafe { libc::close(fd) };
+        }
+        if let Some((read_fd, write_fd)) = output_fds {
+            unsafe { libc::close(write_fd) };
+            input_fd = Some(read_fd);
+        }
+        if last_status.is_some() {
+            break;
+        }
+    }
+    if let Some(fd) = input_fd {
+        unsafe { libc::close(fd) };
+    }
+    let mut statuses = wait_for_foreground(&pids, state);
+    statuses.extend(last_status);
+    statuses
+}
+
+// The statuses of all the stages are kept in PIPESTATUS; with set -o pipefail the status is the one
+// of the last stage that failed
+pub fn execute_pipeline(pipeline: &ShellPipeline, state: &mut ShellState) -> i32 {
+    state.pipeline_pgid = None;
+    state.foreground_command = pipeline.restore_original();
+    let statuses = match pipeline.stages.as_slice() {
+        [stage] => vec![execute_command(&stage.command, state)],
+        stages => execute_stages(stages, state),
+    };
+    take_terminal_back(state);
+    let elements = statuses.iter().map(|status| (None, status.to_string().into_bytes())).collect();
+    let _ = state.variables.set_array(b"PIPESTATUS", elements, false);
+    let status = match state.options.is_enabled(ShellOption::PipeFail) {
+        true => statuses.iter().rev().find(|&&status| status != 0).copied().unwrap_or(0),
+        false => statuses.last().copied().unwrap_or(0),
+    };
+    let status = match pipeline.is_negated {
+        true => (status == 0) as i32,
+        false => status,
+    };
+    state.last_exit_status = status;
+    status
+}
@@ -0,0 +1,133 @@
+use std::ffi::CString;
+use std::io::Write;
+
+use errno::{errno, Errno};
+
+use crate::shell_state::jobs::JobTable;
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ShellState;
+
+// Dispositions the shell changes for itself, children start with the defaults; Rust ignores SIGPIPE
+const RESET_SIGNALS: [libc::c_int; 7] = [
+    libc::SIGPIPE, libc::SIGINT, libc::SIGQUIT, libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU, libc::SIGCHLD,
+];
+
+// Sent by the terminal to its foreground process group, the shell only hands the terminal over
+const JOB_CONTROL_SIGNALS: [libc::c_int; 4] = [libc::SIGQUIT, libc::SIGTSTP, libc::SIGTTIN, libc::SIGTTOU];
+
+// Arguments and environment entries end at the first NUL byte, as in C
+pub fn to_c_string(value: &[u8]) -> CString {
+    let end = value.iter().position(|&c| c == 0).unwrap_or(value.len());
+    CString::new(&value[..end]).unwrap()
+}
+
+// Anything written before a fork would otherwise be written twice
+pub fn flush_output() {
+    let _ = std::io::stdout().flush();
+    let _ = std::io::stderr().flush();
+}
+
+pub fn reset_signals() {
+    for signal in RESET_SIGNALS {
+        unsafe { libc::signal(signal, libc::SIG_DFL) };
+    }
+}
+
+pub fn ignore_job_control_signals() {
+    for signal in JOB_CONTROL_SIGNALS {
+        unsafe { libc::signal(signal, libc::SIG_IGN) };
+    }
+}
+
+pub fn give_terminal(pgid: libc::pid_t) {
+    if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 {
+        unsafe { libc::tcsetpgrp(libc::STDIN_FILENO, pgid) };
+    }
+}
+
+// Called when a foreground pipeline is over
+pub fn take_terminal_back(state: &mut ShellState) {
+    if state.options.is_enabled(ShellOption::Monitor) && state.pipeline_pgid.is_some() {
+        give_terminal(unsafe { libc::getpgrp() });
+    }
+    state.pipeline_pgid = None;
+}
+
+// With job control the processes of a pipeline form a process group, created by the first one and
+// given the terminal unless the job runs in the background; children start with default signal
+// dispositions, without job control and without the jobs of the shell
+pub fn fork_process(state: &mut ShellState, is_foreground: bool) -> Result<libc::pid_t, Errno> {
+    flush_output();
+    let pid = unsafe { libc::fork() };
+    if pid == -1 {
+        return Err(errno());
+    }
+    let is_job_control = state.options.is_enabled(ShellOption::Monitor);
+    if pid == 0 {
+        reset_signals();
+        if is_job_control {
+            let pgid = state.pipeline_pgid.unwrap_or(0);
+            unsafe { libc::setpgid(0, pgid) };
+            if pgid == 0 && is_foreground {
+                give_terminal(unsafe { libc::getpid() });
+            }
+            state.options.set(ShellOption::Monitor, false);
+        }
+        state.pipeline_pgid = None;
+        state.jobs = JobTable::default();
+        // break and continue in a subshell do not reach the loops of the parent
+        state.loop_depth = 0;
+        state.variables.dynamic.reseed_random();
+        return Ok(0);
+    }
+    if is_job_control {
+        let pgid = *state.pipeline_pgid.get_or_insert(pid);
+        unsafe { libc::setpgid(pid, pgid) };
+        if pgid == pid && is_foreground {
+            give_terminal(pgid);
+        }
+    }
+    Ok(pid)
+}
+
+// Statuses of processes killed by a signal are 128 plus the signal number
+pub fn get_exit_status(wait_status: i32) -> i32 {
+    if libc::WIFEXITED(wait_status) {
+        libc::WEXITSTATUS(wait_status)
+    } else if libc::WIFSIGNALED(wait_status) {
+        128 + libc::WTERMSIG(wait_status)
+    } else {
+        128 + libc::WSTOPSIG(wait_status)
+    }
+}
+
+pub fn wait_for_process(pid: libc::pid_t) -> i32 {
+    let mut wait_status = 0;
+    loop {
+        if unsafe { libc::waitpid(pid, &mut wait_status, 0) } != -1 {
+            return get_exit_status(wait_status);
+        }
+        if errno().0 != libc::EINTR {
+            return 127;
+        }
+    }
+}
+
+// Forked children leave without running the destructors of the shell
+pub fn exit_child(status: i32) -> ! {
+    flush_output();
+    unsafe { libc::_exit(status) }
+}
+
+// Only returns when the execution fails
+pub fn execute_file(path: &[u8], arguments: &[Vec<u8>], environment: &[(Vec<u8>, Vec<u8>)]) -> Errno {
+    let path = to_c_string(path);
+    let arguments: Vec<CString> = arguments.iter().map(|argument| to_c_string(argument)).collect();
+    let environment: Vec<CString> = environment.iter().map(|(name, value)| to_c_string(&[name, b"=".as_slice(), value].concat())).collect();
+    let mut argv: Vec<*const libc::c_char> = arguments.iter().map(|argument| argument.as_ptr()).collect();
+    argv.push(std::ptr::null());
+    let mut envp: Vec<*const libc::c_char> = environment.iter().map(|entry| entry.as_ptr()).collect();
+    envp.push(std::ptr::null());
+    unsafe { libc::execve(path.as_ptr(), argv.as_ptr(), envp.as_ptr()) };
+    errno()
+}
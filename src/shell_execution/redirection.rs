@@ -0,0 +1,353 @@
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+
+use errno::{errno, Errno};
+
+use super::process::{flush_output, to_c_string};
+
+use crate::shell_expansions::word_expansion::{expand_word, expand_word_without_splitting, ExpansionError};
+use crate::shell_input_iterator::ShellInputIterator;
+use crate::shell_parsers::shell_word_parser::quoted_expression_parser::QuotedExpressionParser;
+use crate::shell_state::shell_options::ShellOption;
+use crate::shell_state::ShellState;
+use crate::shell_structures::shell_redirection::{HereDocument, RedirectionDescriptor, RedirectionOperator, ShellRedirection};
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+use crate::shell_structures::ShellToken;
+
+// Copies of the descriptors replaced by redirections of commands run in the shell itself are kept
+// from here up, out of the way of the descriptors scripts usually pick
+const SAVED_DESCRIPTOR_BASE: i32 = 100;
+
+// Descriptors allocated for {name} start at 10, as in bash
+const VARIABLE_DESCRIPTOR_BASE: i32 = 10;
+
+pub enum RedirectionError {
+    Expansion(ExpansionError),
+    Ambiguous(Vec<u8>), // the target is not a single field, or not a descriptor after <&
+    Open(Vec<u8>, Errno),
+    NoClobber(Vec<u8>),
+    BadDescriptor(Vec<u8>),
+    Duplicate(Errno), // no descriptor left
+    HereDocument(Errno), // neither a pipe nor a temporary file could hold the contents
+}
+
+impl RedirectionError {
+    pub fn get_message(&self) -> String {
+        match self {
+            RedirectionError::Expansion(err) => err.get_message(),
+            RedirectionError::Ambiguous(word) => format!("{}: ambiguous redirect", String::from_utf8_lossy(word)),
+            RedirectionError::Open(path, err) => format!("{}: {err}", String::from_utf8_lossy(path)),
+            RedirectionError::NoClobber(path) => format!("{}: cannot overwrite existing file", String::from_utf8_lossy(path)),
+            RedirectionError::BadDescriptor(fd) => format!("{}: Bad file descriptor", String::from_utf8_lossy(fd)),
+            RedirectionError::Duplicate(err) => format!("redirection error: cannot duplicate fd: {err}"),
+            RedirectionError::HereDocument(err) => format!("cannot create temp file for here-document: {err}"),
+        }
+    }
+}
+
+// The descriptor as it was before the redirections, None when it was closed
+pub struct SavedDescriptor {
+    fd: i32,
+    copy: Option<i32>,
+}
+
+fn parse_descriptor_number(text: &[u8]) -> Option<i32> {
+    if text.is_empty() || !text.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    String::from_utf8_lossy(text).parse().ok()
+}
+
+// -, n or n- after <& and >&
+fn is_descriptor_target(target: &[u8]) -> bool {
+    target == b"-" || parse_descriptor_number(target.strip_suffix(b"-").unwrap_or(target)).is_some()
+}
+
+fn is_open(fd: i32) -> bool {
+    let flags = unsafe { libc::fcntl(fd, libc::F_GETFD) };
+    flags != -1
+}
+
+fn open_file(path: &[u8], flags: i32) -> Result<i32, RedirectionError> {
+    let fd = unsafe { libc::open(to_c_string(path).as_ptr(), flags | libc::O_CLOEXEC, 0o666) };
+    if fd == -1 {
+        return Err(RedirectionError::Open(path.to_vec(), errno()));
+    }
+    Ok(fd)
+}
+
+// set -C: > does not truncate an existing regular file, /dev/null and the like are still fine
+fn open_without_clobbering(path: &[u8]) -> Result<i32, RedirectionError> {
+    match std::fs::metadata(OsStr::from_bytes(path)) {
+        Ok(metadata) if metadata.is_file() => Err(RedirectionError::NoClobber(path.to_vec())),
+        Ok(_) => open_file(path, libc::O_WRONLY),
+        Err(_) => open_file(path, libc::O_WRONLY | libc::O_CREAT | libc::O_EXCL),
+    }
+}
+
+// The target is a single field: a file name or a descriptor
+fn expand_target(redirection: &ShellRedirection, state: &mut ShellState) -> Result<Vec<u8>, RedirectionError> {
+    let fields = expand_word(&redirection.target, state).map_err(RedirectionError::Expansion)?;
+    match <[Vec<u8>; 1]>::try_from(fields) {
+        Ok([field]) => Ok(field),
+        Err(_) => Err(RedirectionError::Ambiguous(redirection.target.restore_original())),
+    }
+}
+
+// The contents were checked by the parser, expansions are performed as in double quotes
+fn expand_here_document(here_document: &HereDocument, state: &mut ShellState) -> Result<Vec<u8>, RedirectionError> {
+    if here_document.is_quoted {
+        return Ok(here_document.contents.clone());
+    }
+    let iter = ShellInputIterator::new(here_document.contents.iter().copied().peekable());
+    let expression = match QuotedExpressionParser::new(iter).parse_here_document() {
+        Ok(Some(value)) => value,
+        _ => return Ok(here_document.contents.clone()),
+    };
+    let word = ShellWord { parts: vec![ShellExpression::QuoteExpression(expression)] };
+    expand_word_without_splitting(&word, state).map_err(RedirectionError::Expansion)
+}
+
+fn write_all(fd: i32, mut contents: &[u8]) -> Result<(), Errno> {
+    while !contents.is_empty() {
+        match unsafe { libc::write(fd, contents.as_ptr() as *const libc::c_void, contents.len()) } {
+            -1 if errno().0 == libc::EINTR => continue,
+            -1 => return Err(errno()),
+            written => contents = &contents[written as usize..],
+        }
+    }
+    Ok(())
+}
+
+// Contents that fit in a pipe are written to one, longer ones to a temporary file that is deleted
+// right away, so that writing them never waits for the reader
+fn open_here_document(contents: &[u8]) -> Result<i32, Errno> {
+    if contents.len() <= libc::PIPE_BUF {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } == -1 {
+            return Err(errno());
+        }
+        let result = write_all(fds[1], contents);
+        unsafe { libc::close(fds[1]) };
+        return result.map(|_| fds[0]);
+    }
+    let directory = std::env::var_os("TMPDIR").filter(|directory| !directory.is_empty()).unwrap_or("/tmp".into());
+    let mut template = to_c_string(&[directory.as_bytes(), b"/cosh-XXXXXX"].concat()).into_bytes_with_nul();
+    let fd = unsafe { libc::mkostemp(template.as_mut_ptr() as *mut libc::c_char, libc::O_CLOEXEC) };
+    if fd == -1 {
+        return Err(errno());
+    }
+    unsafe { libc::unlink(template.as_ptr() as *const libc::c_char) };
+    let result = write_all(fd, contents);
+    if result.is_err() || unsafe { libc::lseek(fd, 0, libc::SEEK_SET) } == -1 {
+        let err = result.err().unwrap_or_else(errno);
+        unsafe { libc::close(fd) };
+        return Err(err);
+    }
+    Ok(fd)
+}
+
+struct Redirector<'a> {
+    state: &'a mut ShellState,
+    saved: Vec<SavedDescriptor>,
+    is_saving: bool,
+}
+
+impl Redirector<'_> {
+    // Only the first redirection of a descriptor saves it
+    fn save(&mut self, fd: i32) {
+        if !self.is_saving || self.saved.iter().any(|saved| saved.fd == fd) {
+            return;
+        }
+        let copy = unsafe { libc::fcntl(fd, libc::F_DUPFD_CLOEXEC, SAVED_DESCRIPTOR_BASE) };
+        self.saved.push(SavedDescriptor { fd, copy: (copy != -1).then_some(copy) });
+    }
+
+    fn close(&mut self, fd: i32) {
+        self.save(fd);
+        unsafe { libc::close(fd) };
+    }
+
+    // fd becomes a copy of source, inherited by the commands the shell runs
+    fn duplicate(&mut self, source: i32, fd: i32) -> Result<(), RedirectionError> {
+        self.save(fd);
+        if source == fd {
+            unsafe { libc::fcntl(fd, libc::F_SETFD, 0) };
+            return Ok(());
+        }
+        if unsafe { libc::dup2(source, fd) } == -1 {
+            return Err(RedirectionError::BadDescriptor(source.to_string().into_bytes()));
+        }
+        Ok(())
+    }
+
+    // {name} gets a new descriptor, which stays open after the command
+    fn assign(&mut self, descriptor: &RedirectionDescriptor, default: i32, source: i32) -> Result<(), RedirectionError> {
+        let name = match descriptor {
+            RedirectionDescriptor::Default => return self.duplicate(source, default),
+            RedirectionDescriptor::Number(fd) => return self.duplicate(source, *fd),
+            RedirectionDescriptor::Variable(name) => name,
+        };
+        let fd = unsafe { libc::fcntl(source, libc::F_DUPFD, VARIABLE_DESCRIPTOR_BASE) };
+        if fd == -1 {
+            return Err(RedirectionError::Duplicate(errno()));
+        }
+        let result = self.state.variables.set_scalar(name, fd.to_string().into_bytes(), false);
+        result.map_err(|err| RedirectionError::Expansion(ExpansionError::Variable(err)))
+    }
+
+    // n>&m copies m, n>&m- moves it and n>&- closes n; {name}>&- closes the descriptor in the variable
+    fn perform_duplication(&mut self, descriptor: &RedirectionDescriptor, default: i32, target: &[u8]) -> Result<(), RedirectionError> {
+        if target == b"-" {
+            let fd = match descriptor {
+                RedirectionDescriptor::Default => default,
+                RedirectionDescriptor::Number(fd) => *fd,
+                RedirectionDescriptor::Variable(name) => {
+                    let value = self.state.get_variable(name).unwrap_or_default();
+                    let fd = parse_descriptor_number(&value).ok_or_else(|| RedirectionError::Ambiguous(name.clone()))?;
+                    unsafe { libc::close(fd) };
+                    return Ok(());
+                },
+            };
+            self.close(fd);
+            return Ok(());
+        }
+        let (source_text, is_move) = match target.strip_suffix(b"-") {
+            Some(value) => (value, true),
+            None => (target, false),
+        };
+        let source = parse_descriptor_number(source_text).ok_or_else(|| RedirectionError::Ambiguous(target.to_vec()))?;
+        if !is_open(source) {
+            return Err(RedirectionError::BadDescriptor(source_text.to_vec()));
+        }
+        self.assign(descriptor, default, source)?;
+        let fd = match descriptor {
+            RedirectionDescriptor::Default => Some(default),
+            RedirectionDescriptor::Number(fd) => Some(*fd),
+            RedirectionDescriptor::Variable(_) => None,
+        };
+        if is_move && fd != Some(source) {
+            self.close(source);
+        }
+        Ok(())
+    }
+
+    fn perform_file_redirection(&mut self, descriptor: &RedirectionDescriptor, operator: RedirectionOperator, path: &[u8]) -> Result<(), RedirectionError> {
+        let default = operator.get_default_descriptor();
+        let is_both_outputs = matches!(operator, RedirectionOperator::OutputAndError | RedirectionOperator::AppendOutputAndError);
+        let targets = match descriptor {
+            _ if is_both_outputs => vec![1, 2],
+            RedirectionDescriptor::Default => vec![default],
+            RedirectionDescriptor::Number(fd) => vec![*fd],
+            RedirectionDescriptor::Variable(_) => vec![],
+        };
+        // Saved before opening, the file can get the number of a closed target descriptor
+        for &fd in targets.iter() {
+            self.save(fd);
+        }
+        let is_noclobber = self.state.options.is_enabled(ShellOption::NoClobber);
+        let file_fd = match operator {
+            RedirectionOperator::Input => open_file(path, libc::O_RDONLY),
+            RedirectionOperator::ReadWrite => open_file(path, libc::O_RDWR | libc::O_CREAT),
+            RedirectionOperator::Output if is_noclobber => open_without_clobbering(path),
+            RedirectionOperator::Append | RedirectionOperator::AppendOutputAndError => {
+                open_file(path, libc::O_WRONLY | libc::O_CREAT | libc::O_APPEND)
+            },
+            _ => open_file(path, libc::O_WRONLY | libc::O_CREAT | libc::O_TRUNC),
+        }?;
+        let result = match is_both_outputs {
+            true => self.duplicate(file_fd, 1).and_then(|_| self.duplicate(file_fd, 2)),
+            false => self.assign(descriptor, default, file_fd),
+        };
+        if !targets.contains(&file_fd) {
+            unsafe { libc::close(file_fd) };
+        }
+        result
+    }
+
+    fn perform_here_document(&mut self, descriptor: &RedirectionDescriptor, contents: &[u8]) -> Result<(), RedirectionError> {
+        let target = match descriptor {
+            RedirectionDescriptor::Default => Some(0),
+            RedirectionDescriptor::Number(fd) => Some(*fd),
+            RedirectionDescriptor::Variable(_) => None,
+        };
+        if let Some(fd) = target {
+            self.save(fd);
+        }
+        let fd = open_here_document(contents).map_err(RedirectionError::HereDocument)?;
+        let result = self.assign(descriptor, 0, fd);
+        if target != Some(fd) {
+            unsafe { libc::close(fd) };
+        }
+        result
+    }
+
+    fn perform(&mut self, redirection: &ShellRedirection) -> Result<(), RedirectionError> {
+        if let Some(here_document) = &redirection.here_document {
+            let contents = expand_here_document(here_document, self.state)?;
+            return self.perform_here_document(&redirection.descriptor, &contents);
+        }
+        if redirection.operator == RedirectionOperator::HereString {
+            let mut contents = expand_word_without_splitting(&redirection.target, self.state).map_err(RedirectionError::Expansion)?;
+            contents.push(b'\n');
+            return self.perform_here_document(&redirection.descriptor, &contents);
+        }
+        let target = expand_target(redirection, self.state)?;
+        let descriptor = &redirection.descriptor;
+        let default = redirection.operator.get_default_descriptor();
+        match redirection.operator {
+            // >&file without a descriptor is &>file
+            RedirectionOperator::DuplicateOutput if *descriptor == RedirectionDescriptor::Default && !is_descriptor_target(&target) => {
+                self.perform_file_redirection(descriptor, RedirectionOperator::OutputAndError, &target)
+            },
+            RedirectionOperator::DuplicateInput | RedirectionOperator::DuplicateOutput => {
+                self.perform_duplication(descriptor, default, &target)
+            },
+            operator => self.perform_file_redirection(descriptor, operator, &target),
+        }
+    }
+}
+
+pub fn restore_descriptors(saved: Vec<SavedDescriptor>) {
+    flush_output();
+    for SavedDescriptor { fd, copy } in saved.into_iter().rev() {
+        unsafe {
+            match copy {
+                Some(copy) => {
+                    libc::dup2(copy, fd);
+                    libc::close(copy);
+                },
+                None => {
+                    libc::close(fd);
+                },
+            }
+        }
+    }
+}
+
+// exec without a command keeps its redirections, the copies are not needed anymore
+pub fn close_saved_descriptors(saved: Vec<SavedDescriptor>) {
+    flush_output();
+    for copy in saved.into_iter().filter_map(|saved| saved.copy) {
+        unsafe { libc::close(copy) };
+    }
+}
+
+// Redirections are performed left to right: 2>&1 >file leaves the errors where the output was.
+// With is_saving the replaced descriptors are kept to be restored once the command is over, a
+// failure undoes the redirections already performed
+pub fn perform_redirections(
+    redirections: &[ShellRedirection],
+    state: &mut ShellState,
+    is_saving: bool,
+) -> Result<Vec<SavedDescriptor>, RedirectionError> {
+    flush_output();
+    let mut redirector = Redirector { state, saved: vec![], is_saving };
+    for redirection in redirections.iter() {
+        if let Err(err) = redirector.perform(redirection) {
+            restore_descriptors(redirector.saved);
+            return Err(err);
+        }
+    }
+    Ok(redirector.saved)
+}
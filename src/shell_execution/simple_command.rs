@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+use std::ffi::OsStr;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::rc::Rc;
+
+use super::command_search::{find_command, find_command_in_default_path};
+use super::execute_input;
+use super::function::{call_function, perform_local_assignments};
+use super::job_control::wait_for_foreground;
+use super::process::{execute_file, exit_child, fork_process};
+use super::redirection::{close_saved_descriptors, perform_redirections, restore_descriptors};
+
+use crate::shell_builtins::{Builtin, BuiltinContext};
+use crate::shell_expansions::assignment_expansion::{expand_assignment, perform_assignment, ExpandedAssignment};
+use crate::shell_expansions::word_expansion::{expand_word, ExpansionError};
+use crate::shell_state::{ControlFlow, ShellState};
+use crate::shell_structures::shell_command::shell_simple_command::{ShellArgument, ShellSimpleCommand};
+
+// Files that fail with ENOEXEC are run as scripts unless there is a NUL byte in the first line
+fn is_binary_file(contents: &[u8]) -> bool {
+    contents.iter().take(80).take_while(|&&c| c != b'\n').any(|&c| c == 0)
+}
+
+// The script runs in a new shell made of the environment of the command, only the remembered
+// command locations of the parent are kept
+fn run_script(path: &[u8], arguments: Vec<Vec<u8>>, environment: &[(Vec<u8>, Vec<u8>)], state: &ShellState) -> ! {
+    let name = String::from_utf8_lossy(path);
+    let script = match std::fs::read(OsStr::from_bytes(path)) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("cosh: {name}: {err}");
+            exit_child(126);
+        },
+    };
+    if is_binary_file(&script) {
+        eprintln!("cosh: {name}: cannot execute binary file: Exec format error");
+        exit_child(126);
+    }
+    for (name, _) in std::env::vars_os() {
+        std::env::remove_var(name);
+    }
+    for (name, value) in environment {
+        std::env::set_var(OsStr::from_bytes(name), OsStr::from_bytes(value));
+    }
+    // $0 is the path the script was found at rather than the command name
+    let mut script_state = ShellState::new(path.to_vec(), arguments.into_iter().skip(1).collect(), false);
+    script_state.command_hash = state.command_hash.clone();
+    exit_child(execute_input(&script, &mut script_state));
+}
+
+// Redirections and prefix assignments are performed in the child only, the assignments are
+// exported to the command
+fn execute_in_child(
+    path: &[u8],
+    arguments: Vec<Vec<u8>>,
+    command: &ShellSimpleCommand,
+    assignments: Vec<ExpandedAssignment>,
+    state: &mut ShellState,
+) -> ! {
+    if let Err(err) = perform_redirections(&command.redirections, state, false) {
+        eprintln!("cosh: {}", err.get_message());
+        exit_child(1);
+    }
+    let mut environment = state.variables.get_exported();
+    for assignment in assignments {
+        let name = assignment.identifier.clone();
+        if let Err(err) = perform_assignment(assignment, state) {
+            eprintln!("cosh: {}", err.get_message());
+            exit_child(1);
+        }
+        if let Some(value) = state.get_variable(&name) {
+            environment.retain(|(exported_name, _)| *exported_name != name);
+            environment.push((name, value));
+        }
+    }
+    exit_child(execute_file_or_script(path, arguments, &environment, state));
+}
+
+// Only returns when the file cannot be run, with the status of the failure
+pub fn execute_file_or_script(path: &[u8], arguments: Vec<Vec<u8>>, environment: &[(Vec<u8>, Vec<u8>)], state: &ShellState) -> i32 {
+    let err = execute_file(path, &arguments, environment);
+    if err.0 == libc::ENOEXEC {
+        run_script(path, arguments, environment, state);
+    }
+    let name = String::from_utf8_lossy(path);
+    match err.0 {
+        libc::EACCES if Path::new(OsStr::from_bytes(path)).is_dir() => eprintln!("cosh: {name}: Is a directory"),
+        _ => eprintln!("cosh: {name}: {err}"),
+    }
+    if err.0 == libc::ENOENT { 127 } else { 126 }
+}
+
+// A forked child that only runs this command becomes the command instead of forking again
+fn execute_external(
+    arguments: Vec<Vec<u8>>,
+    path: Option<Vec<u8>>,
+    command: &ShellSimpleCommand,
+    can_exec_directly: bool,
+    state: &mut ShellState,
+) -> Result<i32, ExpansionError> {
+    let path = match path {
+        Some(value) => value,
+        None => {
+            return execute_with_redirections(command, state, |_| {
+                eprintln!("cosh: {}: command not found", String::from_utf8_lossy(&arguments[0]));
+                Ok(127)
+            });
+        },
+    };
+    let mut assignments = vec![];
+    for assignment in command.assignments.iter() {
+        assignments.push(expand_assignment(assignment, state)?);
+    }
+    if can_exec_directly {
+        execute_in_child(&path, arguments, command, assignments, state);
+    }
+    match fork_process(state, true) {
+        Err(err) => {
+            eprintln!("cosh: fork: {err}");
+            Ok(1)
+        },
+        Ok(0) => execute_in_child(&path, arguments, command, assignments, state),
+        Ok(pid) => Ok(wait_for_foreground(&[pid], state)[0]),
+    }
+}
+
+// Commands run by the shell itself get their redirections undone afterwards, nothing runs when one
+// of them fails
+fn execute_with_redirections(
+    command: &ShellSimpleCommand,
+    state: &mut ShellState,
+    run: impl FnOnce(&mut ShellState) -> Result<i32, ExpansionError>,
+) -> Result<i32, ExpansionError> {
+    let saved = match perform_redirections(&command.redirections, state, true) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("cosh: {}", err.get_message());
+            return Ok(1);
+        },
+    };
+    let result = run(state);
+    restore_descriptors(saved);
+    result
+}
+
+// Prefix assignments of special builtins stay in the shell, the other builtins see them as exported
+// variables for their duration only. An error of a special builtin, or of its redirections, ends a
+// shell that is not interactive
+fn execute_builtin(
+    builtin: &Rc<dyn Builtin>,
+    arguments: &[Vec<u8>],
+    argument_assignments: HashMap<usize, ExpandedAssignment>,
+    command: &ShellSimpleCommand,
+    state: &mut ShellState,
+) -> Result<i32, ExpansionError> {
+    let is_special = builtin.is_special();
+    let saved = match perform_redirections(&command.redirections, state, true) {
+        Ok(value) => value,
+        Err(err) => {
+            eprintln!("cosh: {}", err.get_message());
+            if is_special && !state.is_interactive {
+                state.control_flow = Some(ControlFlow::Exit(1));
+            }
+            return Ok(1);
+        },
+    };
+    let is_scoped = !is_special && !command.assignments.is_empty();
+    if is_scoped {
+        state.variables.push_temporary_scope();
+    }
+    let assignment_result = match is_special {
+        true => execute_assignments(command, state).map(|_| ()),
+        false => perform_local_assignments(command, state),
+    };
+    let mut context = BuiltinContext::new(builtin.get_name(), state);
+    context.assignments = argument_assignments;
+    let result = assignment_result.map(|_| builtin.run(&arguments[1..], &mut context));
+    let (is_failed, is_keeping_redirections) = (context.is_failed, context.is_keeping_redirections);
+    if is_scoped {
+        state.variables.pop_scope();
+    }
+    match is_keeping_redirections {
+        true => close_saved_descriptors(saved),
+        false => restore_descriptors(saved),
+    }
+    if let Ok(status) = result {
+        if is_special && is_failed && !state.is_interactive && state.control_flow.is_none() {
+            state.control_flow = Some(ControlFlow::Exit(status));
+        }
+    }
+    result
+}
+
+// Without a command name the assignments change the shell itself, the status is the one of the
+// last command substitution then
+fn execute_assignments(command: &ShellSimpleCommand, state: &mut ShellState) -> Result<i32, ExpansionError> {
+    for assignment in command.assignments.iter() {
+        let assignment = expand_assignment(assignment, state)?;
+        perform_assignment(assignment, state)?;
+    }
+    Ok(state.last_substitution_status.unwrap_or(0))
+}
+
+fn execute(command: &ShellSimpleCommand, state: &mut ShellState) -> Result<i32, ExpansionError> {
+    state.last_substitution_status = None;
+    let can_exec_directly = std::mem::take(&mut state.can_exec_directly);
+    let mut arguments = vec![];
+    // Assignment arguments are kept expanded for the declaration builtins, by their index after the name
+    let mut argument_assignments = HashMap::new();
+    for word in command.words.iter() {
+        match word {
+            ShellArgument::Word(word) => arguments.extend(expand_word(word, state)?),
+            ShellArgument::Assignment(assignment) => {
+                let assignment = expand_assignment(assignment, state)?;
+                arguments.push(assignment.to_argument());
+                argument_assignments.insert(arguments.len() - 2, assignment);
+            },
+        }
+    }
+    // $_ is the last argument of the previous command, set once the command is done
+    let last_argument = arguments.last().cloned().unwrap_or_default();
+    let result = execute_expanded(arguments, argument_assignments, command, can_exec_directly, state);
+    let _ = state.set_variable(b"_", last_argument);
+    result
+}
+
+fn execute_expanded(
+    arguments: Vec<Vec<u8>>,
+    argument_assignments: HashMap<usize, ExpandedAssignment>,
+    command: &ShellSimpleCommand,
+    can_exec_directly: bool,
+    state: &mut ShellState,
+) -> Result<i32, ExpansionError> {
+    if arguments.is_empty() {
+        return execute_with_redirections(command, state, |state| execute_assignments(command, state));
+    }
+    let builtin = state.builtins.get(&arguments[0]);
+    if let Some(builtin) = builtin.as_ref().filter(|builtin| builtin.is_special()) {
+        return execute_builtin(builtin, &arguments, argument_assignments, command, state);
+    }
+    if let Some(function) = state.functions.get(&arguments[0]).cloned() {
+        return execute_with_redirections(command, state, |state| call_function(&function, arguments, command, state));
+    }
+    if let Some(builtin) = builtin {
+        return execute_builtin(&builtin, &arguments, argument_assignments, command, state);
+    }
+    let path = find_command(&arguments[0], state);
+    execute_external(arguments, path, command, can_exec_directly, state)
+}
+
+// command and builtin run commands from PATH with the redirections and assignments already in place
+pub fn run_external_command(arguments: Vec<Vec<u8>>, is_default_path: bool, state: &mut ShellState) -> i32 {
+    let path = match is_default_path {
+        true => find_command_in_default_path(&arguments[0]),
+        false => find_command(&arguments[0], state),
+    };
+    execute_external(arguments, path, &ShellSimpleCommand::default(), false, state).unwrap_or(1)
+}
+
+pub fn execute_simple_command(command: &ShellSimpleCommand, state: &mut ShellState) -> i32 {
+    match execute(command, state) {
+        Ok(status) => status,
+        Err(err) => {
+            eprintln!("cosh: {}", err.get_message());
+            1
+        },
+    }
+}
@@ -0,0 +1,11 @@
+pub mod filename_expansion;
+pub mod history_expansion;
+pub mod tilde_expansion;
+pub mod parameter_expansion;
+pub mod expanded_word;
+pub mod word_splitting;
+pub mod quote_removal;
+pub mod brace_expansion;
+pub mod arithmetic_expansion;
+pub mod word_expansion;
+pub mod assignment_expansion;
@@ -0,0 +1,362 @@
+use crate::shell_state::ShellState;
+
+const MAX_RECURSION_DEPTH: usize = 1024;
+
+const OPERATORS: [&str; 39] = [
+    "<<=", ">>=", "**", "++", "--", "<<", ">>", "<=", ">=", "==", "!=", "&&", "||", "*=", "/=", "%=", "+=", "-=",
+    "&=", "^=", "|=", "+", "-", "*", "/", "%", "<", ">", "=", "!", "~", "&", "^", "|", "?", ":", "(", ")", ",",
+];
+
+const ASSIGNMENT_OPERATORS: [&str; 11] = ["=", "*=", "/=", "%=", "+=", "-=", "<<=", ">>=", "&=", "^=", "|="];
+
+#[derive(Debug)]
+pub struct ArithmeticError {
+    pub expression: Vec<u8>,
+    pub message: String,
+}
+
+impl ArithmeticError {
+    pub fn get_message(&self) -> String {
+        format!("{}: {}", String::from_utf8_lossy(&self.expression), self.message)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(i64),
+    Name(Vec<u8>),
+    Operator(&'static str),
+}
+
+fn get_digit_value(c: u8, base: u32) -> Option<u32> {
+    let value = match c {
+        b'0'..=b'9' => c - b'0',
+        b'a'..=b'z' => c - b'a' + 10,
+        b'A'..=b'Z' if base <= 36 => c - b'A' + 10,
+        b'A'..=b'Z' => c - b'A' + 36,
+        b'@' => 62,
+        b'_' => 63,
+        _ => return None,
+    } as u32;
+    (value < base).then_some(value)
+}
+
+// 10, 0x1f, 017 and base#digits with bases from 2 to 64
+fn parse_number(text: &[u8]) -> Result<i64, String> {
+    let (base, digits) = if let Some(position) = text.iter().position(|&c| c == b'#') {
+        let base = std::str::from_utf8(&text[..position])
+            .ok()
+            .and_then(|value| value.parse::<u32>().ok())
+            .filter(|base| (2..=64).contains(base))
+            .ok_or("invalid arithmetic base")?;
+        (base, &text[position + 1..])
+    } else if text.len() > 2 && (text.starts_with(b"0x") || text.starts_with(b"0X")) {
+        (16, &text[2..])
+    } else if text.len() > 1 && text[0] == b'0' {
+        (8, &text[1..])
+    } else {
+        (10, text)
+    };
+    if digits.is_empty() {
+        return Err("invalid number".to_string());
+    }
+    let mut result: i64 = 0;
+    for &c in digits {
+        let digit = get_digit_value(c, base).ok_or("value too great for base")?;
+        result = result.wrapping_mul(base as i64).wrapping_add(digit as i64);
+    }
+    Ok(result)
+}
+
+fn tokenize(expression: &[u8]) -> Result<Vec<Token>, String> {
+    let mut result = vec![];
+    let mut position = 0;
+    while position < expression.len() {
+        let c = expression[position];
+        if c.is_ascii_whitespace() {
+            position += 1;
+        } else if c.is_ascii_digit() {
+            let start = position;
+            while position < expression.len()
+                && (expression[position].is_ascii_alphanumeric() || matches!(expression[position], b'#' | b'@' | b'_'))
+            {
+                position += 1;
+            }
+            result.push(Token::Number(parse_number(&expression[start..position])?));
+        } else if c.is_ascii_alphabetic() || c == b'_' {
+            let start = position;
+            while position < expression.len() && (expression[position].is_ascii_alphanumeric() || expression[position] == b'_') {
+                position += 1;
+            }
+            result.push(Token::Name(expression[start..position].to_vec()));
+        } else {
+            let operator = OPERATORS
+                .iter()
+                .find(|operator| expression[position..].starts_with(operator.as_bytes()))
+                .ok_or_else(|| format!("syntax error: operand expected (error token is \"{}\")",
+                    String::from_utf8_lossy(&expression[position..])))?;
+            result.push(Token::Operator(operator));
+            position += operator.len();
+        }
+    }
+    Ok(result)
+}
+
+struct ArithmeticEvaluator<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    state: &'a mut ShellState,
+    depth: usize,
+    is_evaluating: bool, // false in the branches skipped by &&, || and ?:
+}
+
+impl<'a> ArithmeticEvaluator<'a> {
+    fn peek_operator(&self) -> Option<&'static str> {
+        match self.tokens.get(self.position) {
+            Some(Token::Operator(operator)) => Some(operator),
+            _ => None,
+        }
+    }
+
+    fn next_operator_if(&mut self, operators: &[&str]) -> Option<&'static str> {
+        let operator = self.peek_operator().filter(|operator| operators.contains(operator))?;
+        self.position += 1;
+        Some(operator)
+    }
+
+    fn expect_operator(&mut self, operator: &str) -> Result<(), String> {
+        match self.next_operator_if(&[operator]) {
+            Some(_) => Ok(()),
+            None => Err(format!("syntax error: `{operator}' expected")),
+        }
+    }
+
+    // Values of variables are expressions themselves, unset and empty ones are 0
+    fn get_variable_value(&mut self, name: &[u8]) -> Result<i64, String> {
+        let value = self.state.get_variable(name).unwrap_or_default();
+        if value.iter().all(|c| c.is_ascii_whitespace()) {
+            return Ok(0);
+        }
+        if self.depth >= MAX_RECURSION_DEPTH {
+            return Err("expression recursion level exceeded".to_string());
+        }
+        let tokens = tokenize(&value)?;
+        let mut evaluator = ArithmeticEvaluator {
+            tokens,
+            position: 0,
+            state: &mut *self.state,
+            depth: self.depth + 1,
+            is_evaluating: self.is_evaluating,
+        };
+        evaluator.evaluate()
+    }
+
+    fn set_variable_value(&mut self, name: &[u8], value: i64) -> Result<(), String> {
+        if self.is_evaluating {
+            self.state.set_variable(name, value.to_string().into_bytes()).map_err(|err| err.get_message())?;
+        }
+        Ok(())
+    }
+
+    fn apply_binary(&self, operator: &str, left: i64, right: i64) -> Result<i64, String> {
+        let result = match operator {
+            "*" => left.wrapping_mul(right),
+            "/" | "%" if right == 0 && self.is_evaluating => return Err("division by 0".to_string()),
+            "/" | "%" if right == 0 => 0,
+            "/" => left.wrapping_div(right),
+            "%" => left.wrapping_rem(right),
+            "+" => left.wrapping_add(right),
+            "-" => left.wrapping_sub(right),
+            "<<" => left.wrapping_shl(right as u32),
+            ">>" => left.wrapping_shr(right as u32),
+            "<" => (left < right) as i64,
+            ">" => (left > right) as i64,
+            "<=" => (left <= right) as i64,
+            ">=" => (left >= right) as i64,
+            "==" => (left == right) as i64,
+            "!=" => (left != right) as i64,
+            "&" => left & right,
+            "^" => left ^ right,
+            "|" => left | right,
+            "**" if right < 0 => return Err("exponent less than 0".to_string()),
+            "**" => left.wrapping_pow(right.min(u32::MAX as i64) as u32),
+            _ => unreachable!(),
+        };
+        Ok(result)
+    }
+
+    fn parse_primary(&mut self) -> Result<i64, String> {
+        match self.tokens.get(self.position).cloned() {
+            Some(Token::Number(value)) => {
+                self.position += 1;
+                Ok(value)
+            },
+            Some(Token::Name(name)) => {
+                self.position += 1;
+                let value = self.get_variable_value(&name)?;
+                match self.next_operator_if(&["++", "--"]) {
+                    Some(operator) => {
+                        let new_value = if operator == "++" { value.wrapping_add(1) } else { value.wrapping_sub(1) };
+                        self.set_variable_value(&name, new_value)?;
+                        Ok(value)
+                    },
+                    None => Ok(value),
+                }
+            },
+            Some(Token::Operator("(")) => {
+                self.position += 1;
+                let value = self.parse_comma()?;
+                self.expect_operator(")")?;
+                Ok(value)
+            },
+            _ => Err("syntax error: operand expected".to_string()),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<i64, String> {
+        if let Some(operator) = self.next_operator_if(&["++", "--"]) {
+            if let Some(Token::Name(name)) = self.tokens.get(self.position).cloned() {
+                self.position += 1;
+                let value = self.get_variable_value(&name)?;
+                let new_value = if operator == "++" { value.wrapping_add(1) } else { value.wrapping_sub(1) };
+                self.set_variable_value(&name, new_value)?;
+                return Ok(new_value);
+            }
+            // ++5 is +(+5) and --5 is -(-5)
+            return self.parse_unary();
+        }
+        match self.next_operator_if(&["!", "~", "-", "+"]) {
+            Some("!") => Ok((self.parse_unary()? == 0) as i64),
+            Some("~") => Ok(!self.parse_unary()?),
+            Some("-") => Ok(self.parse_unary()?.wrapping_neg()),
+            Some(_) => self.parse_unary(),
+            None => self.parse_primary(),
+        }
+    }
+
+    // ** is right-associative
+    fn parse_power(&mut self) -> Result<i64, String> {
+        let left = self.parse_unary()?;
+        if self.next_operator_if(&["**"]).is_some() {
+            let right = self.parse_power()?;
+            return self.apply_binary("**", left, right);
+        }
+        Ok(left)
+    }
+
+    // Left-associative binary operators, levels from the loosest to the tightest binding
+    fn parse_binary(&mut self, level: usize) -> Result<i64, String> {
+        const LEVELS: [&[&str]; 8] = [
+            &["|"],
+            &["^"],
+            &["&"],
+            &["==", "!="],
+            &["<=", ">=", "<", ">"],
+            &["<<", ">>"],
+            &["+", "-"],
+            &["*", "/", "%"],
+        ];
+        if level == LEVELS.len() {
+            return self.parse_power();
+        }
+        let mut result = self.parse_binary(level + 1)?;
+        while let Some(operator) = self.next_operator_if(LEVELS[level]) {
+            let right = self.parse_binary(level + 1)?;
+            result = self.apply_binary(operator, result, right)?;
+        }
+        Ok(result)
+    }
+
+    // Evaluates the operand only when should_evaluate is set, so skipped branches have no side effects
+    fn parse_skippable(&mut self, should_evaluate: bool, parse: fn(&mut Self) -> Result<i64, String>) -> Result<i64, String> {
+        let is_evaluating = self.is_evaluating;
+        self.is_evaluating = is_evaluating && should_evaluate;
+        let result = parse(self);
+        self.is_evaluating = is_evaluating;
+        result
+    }
+
+    fn parse_logical_and(&mut self) -> Result<i64, String> {
+        let mut result = self.parse_binary(0)?;
+        while self.next_operator_if(&["&&"]).is_some() {
+            let right = self.parse_skippable(result != 0, |evaluator| evaluator.parse_binary(0))?;
+            result = (result != 0 && right != 0) as i64;
+        }
+        Ok(result)
+    }
+
+    fn parse_logical_or(&mut self) -> Result<i64, String> {
+        let mut result = self.parse_logical_and()?;
+        while self.next_operator_if(&["||"]).is_some() {
+            let right = self.parse_skippable(result == 0, Self::parse_logical_and)?;
+            result = (result != 0 || right != 0) as i64;
+        }
+        Ok(result)
+    }
+
+    fn parse_conditional(&mut self) -> Result<i64, String> {
+        let condition = self.parse_logical_or()?;
+        if self.next_operator_if(&["?"]).is_none() {
+            return Ok(condition);
+        }
+        let if_true = self.parse_skippable(condition != 0, Self::parse_comma)?;
+        self.expect_operator(":")?;
+        let if_false = self.parse_skippable(condition == 0, Self::parse_conditional)?;
+        Ok(if condition != 0 { if_true } else { if_false })
+    }
+
+    // Assignments are right-associative: a = b += 2
+    fn parse_assignment(&mut self) -> Result<i64, String> {
+        let name = match (self.tokens.get(self.position), self.tokens.get(self.position + 1)) {
+            (Some(Token::Name(name)), Some(Token::Operator(operator))) if ASSIGNMENT_OPERATORS.contains(operator) => {
+                name.clone()
+            },
+            _ => return self.parse_conditional(),
+        };
+        self.position += 1;
+        let operator = self.next_operator_if(&ASSIGNMENT_OPERATORS).unwrap();
+        let right = self.parse_assignment()?;
+        let value = match operator.strip_suffix('=').filter(|binary| !binary.is_empty()) {
+            Some(binary) => {
+                let left = self.get_variable_value(&name)?;
+                self.apply_binary(binary, left, right)?
+            },
+            None => right,
+        };
+        self.set_variable_value(&name, value)?;
+        Ok(value)
+    }
+
+    fn parse_comma(&mut self) -> Result<i64, String> {
+        let mut result = self.parse_assignment()?;
+        while self.next_operator_if(&[","]).is_some() {
+            result = self.parse_assignment()?;
+        }
+        Ok(result)
+    }
+
+    fn evaluate(&mut self) -> Result<i64, String> {
+        if self.tokens.is_empty() {
+            return Ok(0);
+        }
+        let result = self.parse_comma()?;
+        match self.tokens.get(self.position) {
+            None => Ok(result),
+            Some(_) => Err("syntax error in expression".to_string()),
+        }
+    }
+}
+
+// The expression is already expanded, an empty one is 0
+pub fn evaluate_arithmetic(expression: &[u8], state: &mut ShellState) -> Result<i64, ArithmeticError> {
+    let to_error = |message| ArithmeticError { expression: expression.to_vec(), message };
+    let tokens = tokenize(expression).map_err(to_error)?;
+    let mut evaluator = ArithmeticEvaluator {
+        tokens,
+        position: 0,
+        state,
+        depth: 0,
+        is_evaluating: true,
+    };
+    evaluator.evaluate().map_err(to_error)
+}
@@ -0,0 +1,125 @@
+use super::arithmetic_expansion::evaluate_arithmetic;
+use super::parameter_expansion::get_array_key;
+use super::word_expansion::{expand_word, expand_word_without_splitting, ExpansionError};
+
+use crate::shell_state::ShellState;
+use crate::shell_structures::shell_variable_assignment::{ShellVariableAssignment, VariableAssignmentType};
+
+#[derive(Debug)]
+pub enum ExpandedAssignmentValue {
+    Scalar(Vec<u8>),
+    List(Vec<(Option<Vec<u8>>, Vec<u8>)>), // (subscript, value), subscripts are evaluated by the variable store
+}
+
+// Subscripts are only expanded here: whether they are arithmetic expressions or keys depends on the variable
+#[derive(Debug)]
+pub struct ExpandedAssignment {
+    pub identifier: Vec<u8>,
+    pub subscript: Option<Vec<u8>>,
+    pub is_append: bool,
+    pub value: ExpandedAssignmentValue,
+}
+
+impl ExpandedAssignment {
+    // A single argument for the commands that take it as a word: name[subscript]+=value, lists as name=(a b)
+    pub fn to_argument(&self) -> Vec<u8> {
+        let mut result = self.identifier.clone();
+        if let Some(subscript) = &self.subscript {
+            result.extend([b"[".as_slice(), subscript, b"]"].concat());
+        }
+        result.extend(if self.is_append { b"+=".as_slice() } else { b"=" });
+        match &self.value {
+            ExpandedAssignmentValue::Scalar(value) => result.extend(value),
+            ExpandedAssignmentValue::List(elements) => {
+                let elements: Vec<Vec<u8>> = elements
+                    .iter()
+                    .map(|(subscript, value)| match subscript {
+                        Some(subscript) => [b"[".as_slice(), subscript, b"]=", value].concat(),
+                        None => value.clone(),
+                    })
+                    .collect();
+                result.extend([b"(".as_slice(), &elements.join(&b' '), b")"].concat());
+            },
+        }
+        result
+    }
+}
+
+pub fn expand_assignment(assignment: &ShellVariableAssignment, state: &mut ShellState) -> Result<ExpandedAssignment, ExpansionError> {
+    let subscript = match &assignment.subscript {
+        Some(subscript) => Some(expand_word_without_splitting(subscript, state)?),
+        None => None,
+    };
+    let value = match &assignment.value {
+        VariableAssignmentType::Simple(word) => ExpandedAssignmentValue::Scalar(expand_word_without_splitting(word, state)?),
+        VariableAssignmentType::List(elements) => {
+            let mut result = vec![];
+            for element in elements.iter() {
+                match &element.subscript {
+                    Some(subscript) => {
+                        let subscript = expand_word_without_splitting(subscript, state)?;
+                        result.push((Some(subscript), expand_word_without_splitting(&element.value, state)?));
+                    },
+                    None => result.extend(expand_word(&element.value, state)?.into_iter().map(|value| (None, value))),
+                }
+            }
+            ExpandedAssignmentValue::List(result)
+        },
+    };
+    Ok(ExpandedAssignment { identifier: assignment.identifier.clone(), subscript, is_append: assignment.is_append, value })
+}
+
+// Values of integer variables are arithmetic expressions, += adds to the old value then
+fn evaluate_integer(value: &[u8], old_value: Option<Vec<u8>>, state: &mut ShellState) -> Result<Vec<u8>, ExpansionError> {
+    let mut result = evaluate_arithmetic(value, state).map_err(ExpansionError::Arithmetic)?;
+    if let Some(old_value) = old_value {
+        result = result.wrapping_add(evaluate_arithmetic(&old_value, state).map_err(ExpansionError::Arithmetic)?);
+    }
+    Ok(result.to_string().into_bytes())
+}
+
+pub fn perform_assignment(assignment: ExpandedAssignment, state: &mut ShellState) -> Result<(), ExpansionError> {
+    let ExpandedAssignment { identifier, subscript, is_append, value } = assignment;
+    let (name, reference_subscript) = state.variables.resolve_reference(&identifier).map_err(ExpansionError::Variable)?;
+    let subscript = subscript.or(reference_subscript);
+    let variable = state.variables.get(&name);
+    let is_integer = variable.is_some_and(|variable| variable.attributes.is_integer);
+    let is_associative = variable.is_some_and(|variable| variable.is_associative());
+    let key = match subscript {
+        Some(subscript) => Some(get_array_key(&name, &subscript, state)?),
+        None => None,
+    };
+    let result = match value {
+        ExpandedAssignmentValue::Scalar(value) if is_integer => {
+            let old_value = match &key {
+                Some(key) => state.variables.get_element(&name, key),
+                None => state.variables.get_scalar(&name),
+            };
+            let value = evaluate_integer(&value, old_value.filter(|_| is_append), state)?;
+            match key {
+                Some(key) => state.variables.set_element(&name, key, value, false),
+                None => state.variables.set_scalar(&name, value, false),
+            }
+        },
+        ExpandedAssignmentValue::Scalar(value) => match key {
+            Some(key) => state.variables.set_element(&name, key, value, is_append),
+            None => state.variables.set_scalar(&name, value, is_append),
+        },
+        ExpandedAssignmentValue::List(elements) => {
+            let mut values = vec![];
+            for (subscript, value) in elements {
+                let key = match subscript {
+                    Some(subscript) => Some(get_array_key(&name, &subscript, state)?),
+                    None => None,
+                };
+                let value = match is_integer && !is_associative {
+                    true => evaluate_integer(&value, None, state)?,
+                    false => value,
+                };
+                values.push((key, value));
+            }
+            state.variables.set_array(&name, values, is_append)
+        },
+    };
+    result.map_err(ExpansionError::Variable)
+}
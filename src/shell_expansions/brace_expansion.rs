@@ -0,0 +1,40 @@
+use crate::shell_structures::shell_word::shell_brace_expansion::BraceExpansion;
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+
+fn get_alternatives(expansion: &BraceExpansion) -> Vec<ShellWord> {
+    match expansion {
+        BraceExpansion::Range(range) => range
+            .expand()
+            .into_iter()
+            .map(|value| ShellWord { parts: vec![ShellExpression::Literal(value)] })
+            .collect(),
+        BraceExpansion::List(list) => list.words.iter().flat_map(expand_braces).collect(),
+    }
+}
+
+// First stage: a{b,c}d becomes abd acd, the words are left in order and not expanded otherwise
+pub fn expand_braces(word: &ShellWord) -> Vec<ShellWord> {
+    let mut result = vec![ShellWord::default()];
+    for part in word.parts.iter() {
+        match part {
+            ShellExpression::BraceExpansion(expansion) => {
+                let alternatives = get_alternatives(expansion);
+                let mut combined = Vec::with_capacity(result.len() * alternatives.len());
+                for prefix in result.iter() {
+                    for alternative in alternatives.iter() {
+                        let mut word = prefix.clone();
+                        word.parts.extend(alternative.parts.iter().cloned());
+                        combined.push(word);
+                    }
+                }
+                result = combined;
+            },
+            _ => {
+                for word in result.iter_mut() {
+                    word.parts.push(part.clone());
+                }
+            },
+        }
+    }
+    result
+}
@@ -0,0 +1,35 @@
+use crate::shell_structures::shell_word::globbing_pattern::GlobbingPattern;
+use crate::shell_structures::ShellToken;
+
+// Word after the expansions, the origin of every piece decides whether it is split and globbed
+#[derive(Clone, Debug)]
+pub enum ExpandedFragment {
+    Literal(Vec<u8>), // unquoted text written in the word itself
+    Quoted(Vec<u8>), // quoted or escaped text, also results of expansions inside quotes
+    Expanded(Vec<u8>), // unquoted result of an expansion, subject to field splitting
+    Glob(GlobbingPattern),
+    FieldBreak, // between the fields of "$@"
+}
+
+pub type ExpandedField = Vec<ExpandedFragment>;
+
+impl ExpandedFragment {
+    // Quoted empty strings still make a field: "" is an argument, $empty is not
+    pub fn has_content(&self) -> bool {
+        match self {
+            ExpandedFragment::Literal(text) | ExpandedFragment::Expanded(text) => !text.is_empty(),
+            ExpandedFragment::Quoted(_) | ExpandedFragment::Glob(_) => true,
+            ExpandedFragment::FieldBreak => false,
+        }
+    }
+
+    pub fn get_text(&self) -> Vec<u8> {
+        match self {
+            ExpandedFragment::Literal(text) | ExpandedFragment::Quoted(text) | ExpandedFragment::Expanded(text) => {
+                text.clone()
+            },
+            ExpandedFragment::Glob(glob) => glob.restore_original(),
+            ExpandedFragment::FieldBreak => vec![],
+        }
+    }
+}
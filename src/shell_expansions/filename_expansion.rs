@@ -0,0 +1,262 @@
+use std::fs;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+use super::expanded_word::ExpandedFragment;
+
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{get_default_word_parsing_rules, ShellParsingRules};
+use crate::shell_parsers::shell_word_parser::globbing_pattern_parser::GlobbingPatternParser;
+use crate::shell_parsers::shell_word_parser::ShellWordParser;
+use crate::shell_state::shell_options::{ShellOption, ShellOptions};
+use crate::shell_structures::shell_word::globbing_pattern::{GlobbingPattern, Pattern};
+
+#[derive(Debug)]
+pub enum FilenameExpansionError {
+    NoMatch(Vec<u8>), // failglob
+}
+
+impl FilenameExpansionError {
+    pub fn get_message(&self) -> String {
+        match self {
+            FilenameExpansionError::NoMatch(pattern) => format!("no match: {}", String::from_utf8_lossy(pattern)),
+        }
+    }
+}
+
+struct FilenameExpander {
+    ignore_patterns: Vec<Pattern>,
+    match_dotfiles: bool,
+    ignore_case: bool,
+    is_globstar_enabled: bool,
+}
+
+// GLOBIGNORE is a colon-separated list of patterns matched against the whole resulting path
+fn parse_glob_ignore(value: &[u8], is_extglob_enabled: bool) -> Vec<Pattern> {
+    let parsing_rules = ShellParsingRules { is_interactive: false, is_extglob_enabled };
+    let word_rules = get_default_word_parsing_rules(&parsing_rules);
+    let mut result = vec![];
+    for entry in value.split(|&c| c == b':').filter(|entry| !entry.is_empty()) {
+        let iter = ShellInputIterator::new(entry.iter().copied().peekable());
+        let mut word_parser = ShellWordParser::new(iter, &word_rules);
+        match word_parser.parse() {
+            Ok(Some(word)) => result.push(Pattern::from_word(&word)),
+            _ => {
+                let mut pattern = Pattern::default();
+                pattern.push_literal(entry);
+                result.push(pattern);
+            },
+        }
+    }
+    result
+}
+
+fn join_path(prefix: &[u8], name: &[u8]) -> Vec<u8> {
+    let mut result = prefix.to_vec();
+    result.extend(name);
+    result
+}
+
+fn get_directory(prefix: &[u8]) -> &Path {
+    if prefix.is_empty() {
+        Path::new(".")
+    } else {
+        Path::new(std::ffi::OsStr::from_bytes(prefix))
+    }
+}
+
+fn is_directory(path: &[u8]) -> bool {
+    fs::metadata(std::ffi::OsStr::from_bytes(path)).is_ok_and(|metadata| metadata.is_dir())
+}
+
+fn exists(path: &[u8]) -> bool {
+    fs::symlink_metadata(std::ffi::OsStr::from_bytes(path)).is_ok()
+}
+
+impl FilenameExpander {
+    fn read_directory(&self, prefix: &[u8]) -> Vec<Vec<u8>> {
+        let entries = match fs::read_dir(get_directory(prefix)) {
+            Ok(value) => value,
+            Err(_) => return vec![],
+        };
+        entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.file_name().as_bytes().to_vec())
+            .collect()
+    }
+
+    fn is_hidden(&self, name: &[u8], component: Option<&Pattern>) -> bool {
+        name.first() == Some(&b'.')
+            && !self.match_dotfiles
+            && !component.is_some_and(|pattern| pattern.starts_with('.'))
+    }
+
+    // Every file and directory below prefix, symbolic links to directories are not followed
+    fn list_recursive(&self, prefix: &[u8], only_directories: bool, result: &mut Vec<Vec<u8>>) {
+        for name in self.read_directory(prefix) {
+            if self.is_hidden(&name, None) {
+                continue;
+            }
+            let path = join_path(prefix, &name);
+            let is_real_directory = fs::symlink_metadata(std::ffi::OsStr::from_bytes(&path))
+                .is_ok_and(|metadata| metadata.is_dir());
+            if is_real_directory || !only_directories {
+                result.push(path.clone());
+            }
+            if is_real_directory {
+                self.list_recursive(&join_path(&path, b"/"), only_directories, result);
+            }
+        }
+    }
+
+    fn expand_recursive_component(&self, prefixes: Vec<Vec<u8>>, is_last: bool) -> Vec<Vec<u8>> {
+        let mut result = vec![];
+        for prefix in prefixes {
+            if is_last {
+                self.list_recursive(&prefix, false, &mut result);
+            } else {
+                let mut directories = vec![];
+                self.list_recursive(&prefix, true, &mut directories);
+                result.push(prefix);
+                result.extend(directories.into_iter().map(|directory| join_path(&directory, b"/")));
+            }
+        }
+        result
+    }
+
+    fn expand_component(&self, prefixes: Vec<Vec<u8>>, component: &Pattern, is_last: bool) -> Vec<Vec<u8>> {
+        if component.elements.is_empty() {
+            return if is_last {
+                prefixes.into_iter().filter(|prefix| is_directory(prefix)).collect()
+            } else {
+                prefixes
+            };
+        }
+        if component.is_recursive() && self.is_globstar_enabled {
+            return self.expand_recursive_component(prefixes, is_last);
+        }
+        let mut result = vec![];
+        for prefix in prefixes {
+            let names = if component.has_globs() {
+                self.read_directory(&prefix)
+                    .into_iter()
+                    .filter(|name| !self.is_hidden(name, Some(component)))
+                    .filter(|name| component.matches(name, self.ignore_case))
+                    .collect()
+            } else {
+                vec![component.to_literal()]
+            };
+            for name in names {
+                let path = join_path(&prefix, &name);
+                if is_last && exists(&path) {
+                    result.push(path);
+                } else if !is_last && is_directory(&path) {
+                    result.push(join_path(&path, b"/"));
+                }
+            }
+        }
+        result
+    }
+
+    fn expand(&self, pattern: &Pattern) -> Vec<Vec<u8>> {
+        let components = pattern.split_path_components();
+        let (mut prefixes, components) = match components.split_first() {
+            Some((first, rest)) if first.elements.is_empty() && !rest.is_empty() => (vec![b"/".to_vec()], rest),
+            _ => (vec![vec![]], components.as_slice()),
+        };
+        for (i, component) in components.iter().enumerate() {
+            prefixes = self.expand_component(prefixes, component, i + 1 == components.len());
+        }
+        prefixes.retain(|path| {
+            !self.ignore_patterns.iter().any(|ignore_pattern| ignore_pattern.matches(path, self.ignore_case))
+        });
+        prefixes.sort();
+        prefixes
+    }
+}
+
+// Unquoted expansion results are patterns as well: x='*.txt'; ls $x
+fn push_expanded_pattern(pattern: &mut Pattern, text: &[u8]) {
+    let mut iter = ShellInputIterator::new(text.iter().copied().peekable());
+    let mut literal = vec![];
+    while let Some(c) = iter.next() {
+        let glob = match c {
+            b'*' => {
+                let mut count = 1;
+                while iter.next_if(|&c| c == b'*').is_some() {
+                    count += 1;
+                }
+                GlobbingPattern::AnyString(count)
+            },
+            b'?' => GlobbingPattern::AnySingleCharacter,
+            b'[' => {
+                let mut globbing_pattern_parser = GlobbingPatternParser::new(iter.clone(), b"");
+                match globbing_pattern_parser.parse_bracket_expression() {
+                    Ok(Some(glob)) => {
+                        iter = globbing_pattern_parser.iter;
+                        glob
+                    },
+                    _ => {
+                        literal.push(c);
+                        continue;
+                    },
+                }
+            },
+            b'\\' => {
+                literal.push(iter.next().unwrap_or(c));
+                continue;
+            },
+            _ => {
+                literal.push(c);
+                continue;
+            },
+        };
+        pattern.push_literal(&std::mem::take(&mut literal));
+        pattern.push_glob(glob);
+    }
+    pattern.push_literal(&literal);
+}
+
+// Pattern of a field after word splitting, quoted parts only match themselves
+pub fn get_field_pattern(field: &[ExpandedFragment]) -> Pattern {
+    let mut result = Pattern::default();
+    for fragment in field.iter() {
+        match fragment {
+            ExpandedFragment::Literal(text) | ExpandedFragment::Quoted(text) => result.push_literal(text),
+            ExpandedFragment::Expanded(text) => push_expanded_pattern(&mut result, text),
+            ExpandedFragment::Glob(glob) => result.push_glob(glob.clone()),
+            ExpandedFragment::FieldBreak => {},
+        }
+    }
+    result
+}
+
+// None means the word is left as is
+pub fn expand_filenames(
+    pattern: &Pattern,
+    options: &ShellOptions,
+    glob_ignore: Option<&[u8]>,
+) -> Result<Option<Vec<Vec<u8>>>, FilenameExpansionError> {
+    if !pattern.has_globs() {
+        return Ok(None);
+    }
+    let glob_ignore = glob_ignore.filter(|value| !value.is_empty());
+    let expander = FilenameExpander {
+        ignore_patterns: glob_ignore
+            .map(|value| parse_glob_ignore(value, options.is_enabled(ShellOption::ExtGlob)))
+            .unwrap_or_default(),
+        match_dotfiles: options.is_enabled(ShellOption::DotGlob) || glob_ignore.is_some(),
+        ignore_case: options.is_enabled(ShellOption::NoCaseGlob),
+        is_globstar_enabled: options.is_enabled(ShellOption::GlobStar),
+    };
+    let result = expander.expand(pattern);
+    if !result.is_empty() {
+        Ok(Some(result))
+    } else if options.is_enabled(ShellOption::FailGlob) {
+        Err(FilenameExpansionError::NoMatch(pattern.to_literal()))
+    } else if options.is_enabled(ShellOption::NullGlob) {
+        Ok(Some(vec![]))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,309 @@
+use crate::shell_input_iterator::ShellInputIterator;
+use crate::shell_parser_base::ParseError;
+use crate::shell_parsers::history_expansion_parser::HistoryExpansionParser;
+use crate::shell_structures::history_expansion::*;
+use crate::shell_structures::ShellToken;
+
+#[derive(Debug)]
+pub enum HistoryExpansionError {
+    EventNotFound(Vec<u8>),
+    BadWordSpecifier,
+    SubstitutionFailed,
+    NoPreviousSubstitution,
+}
+
+pub struct ExpandedHistoryLine {
+    pub line: Vec<u8>,
+    pub is_expanded: bool,
+    pub is_print_only: bool, // :p
+}
+
+impl HistoryExpansionError {
+    pub fn get_message(&self) -> String {
+        match self {
+            HistoryExpansionError::EventNotFound(event) => {
+                format!("{}: event not found", String::from_utf8_lossy(event))
+            },
+            HistoryExpansionError::BadWordSpecifier => "bad word specifier".to_string(),
+            HistoryExpansionError::SubstitutionFailed => "substitution failed".to_string(),
+            HistoryExpansionError::NoPreviousSubstitution => "no previous substitution".to_string(),
+        }
+    }
+}
+
+// History lines are split into words the same way as bash does: blanks separate words unless quoted,
+// and control operators are words of their own
+fn split_history_words(line: &[u8]) -> Vec<Vec<u8>> {
+    let mut result = vec![];
+    let mut current_word = vec![];
+    let mut quote: Option<u8> = None;
+    let mut iter = line.iter().copied().peekable();
+    while let Some(c) = iter.next() {
+        match quote {
+            Some(quote_char) => {
+                if c == quote_char {
+                    quote = None;
+                } else if c == b'\\' && quote_char == b'"' {
+                    current_word.push(c);
+                    if let Some(escaped_char) = iter.next() {
+                        current_word.push(escaped_char);
+                    }
+                    continue;
+                }
+                current_word.push(c);
+            },
+            None if c.is_ascii_whitespace() => {
+                if !current_word.is_empty() {
+                    result.push(std::mem::take(&mut current_word));
+                }
+            },
+            None if matches!(c, b'|' | b'&' | b';' | b'<' | b'>' | b'(' | b')') => {
+                if !current_word.is_empty() {
+                    result.push(std::mem::take(&mut current_word));
+                }
+                let mut operator = vec![c];
+                if let Some(next_char) = iter.next_if(|&next_char| next_char == c && c != b'(' && c != b')') {
+                    operator.push(next_char);
+                }
+                result.push(operator);
+            },
+            None => {
+                if c == b'\'' || c == b'"' {
+                    quote = Some(c);
+                } else if c == b'\\' {
+                    current_word.push(c);
+                    if let Some(escaped_char) = iter.next() {
+                        current_word.push(escaped_char);
+                    }
+                    continue;
+                }
+                current_word.push(c);
+            },
+        }
+    }
+    if !current_word.is_empty() {
+        result.push(current_word);
+    }
+    result
+}
+
+fn find_subslice(text: &[u8], pattern: &[u8]) -> Option<usize> {
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    text.windows(pattern.len()).position(|window| window == pattern)
+}
+
+fn quote_word(word: &[u8]) -> Vec<u8> {
+    let mut result = vec![b'\''];
+    for &c in word {
+        if c == b'\'' {
+            result.extend(b"'\\''");
+        } else {
+            result.push(c);
+        }
+    }
+    result.push(b'\'');
+    result
+}
+
+struct HistoryExpander<'a> {
+    history: &'a [Vec<u8>],
+    last_search: Option<Vec<u8>>,
+    last_substitution: Option<(Vec<u8>, Vec<u8>)>,
+    is_print_only: bool,
+}
+
+impl<'a> HistoryExpander<'a> {
+    fn find_event(&mut self, event: &EventDesignator, current_line: &[u8]) -> Result<Vec<u8>, HistoryExpansionError> {
+        let not_found = || HistoryExpansionError::EventNotFound(event.restore_original());
+        let result = match event {
+            EventDesignator::PreviousCommand => self.history.last(),
+            EventDesignator::Absolute(n) => n.checked_sub(1).and_then(|index| self.history.get(index)),
+            EventDesignator::Relative(n) => {
+                self.history.len().checked_sub(*n).and_then(|index| self.history.get(index))
+            },
+            EventDesignator::StartsWith(prefix) => {
+                self.history.iter().rev().find(|line| line.starts_with(prefix))
+            },
+            EventDesignator::Contains(text) => {
+                let text = if text.is_empty() {
+                    self.last_search.clone().ok_or_else(not_found)?
+                } else {
+                    text.clone()
+                };
+                let result = self.history.iter().rev().find(|line| find_subslice(line, &text).is_some());
+                self.last_search = Some(text);
+                result
+            },
+            EventDesignator::CurrentLine => return Ok(current_line.to_vec()),
+        };
+        result.cloned().ok_or_else(not_found)
+    }
+
+    fn get_word_index(&self, index: WordIndex, words: &[Vec<u8>]) -> Result<usize, HistoryExpansionError> {
+        match index {
+            WordIndex::Number(n) => Ok(n),
+            WordIndex::Last => Ok(words.len().saturating_sub(1)),
+            WordIndex::Matched => {
+                let search = self.last_search.as_ref().ok_or(HistoryExpansionError::BadWordSpecifier)?;
+                words
+                    .iter()
+                    .position(|word| find_subslice(word, search).is_some())
+                    .ok_or(HistoryExpansionError::BadWordSpecifier)
+            },
+        }
+    }
+
+    fn select_words(&self, designator: &WordDesignator, line: &[u8]) -> Result<Vec<u8>, HistoryExpansionError> {
+        let words = split_history_words(line);
+        let (start, end) = match designator {
+            WordDesignator::AllArguments if words.len() < 2 => return Ok(vec![]),
+            WordDesignator::AllArguments => (1, words.len() - 1),
+            WordDesignator::Single(index) => {
+                let index = self.get_word_index(*index, &words)?;
+                (index, index)
+            },
+            WordDesignator::Range(start, end) => {
+                (self.get_word_index(*start, &words)?, self.get_word_index(*end, &words)?)
+            },
+            WordDesignator::RangeWithoutLast(start) => {
+                let end = words.len().checked_sub(2).ok_or(HistoryExpansionError::BadWordSpecifier)?;
+                (self.get_word_index(*start, &words)?, end)
+            },
+        };
+        if start > end || end >= words.len() {
+            return Err(HistoryExpansionError::BadWordSpecifier);
+        }
+        Ok(words[start..=end].join(&b' '))
+    }
+
+    fn substitute(&mut self, text: Vec<u8>, old: &[u8], new: &[u8], is_global: bool) -> Result<Vec<u8>, HistoryExpansionError> {
+        let old = if old.is_empty() {
+            match self.last_substitution.as_ref().map(|(old, _)| old.clone()).or(self.last_search.clone()) {
+                Some(value) => value,
+                None => return Err(HistoryExpansionError::NoPreviousSubstitution),
+            }
+        } else {
+            old.to_vec()
+        };
+        // & in the replacement stands for the matched text
+        let mut replacement = vec![];
+        let mut iter = new.iter().copied().peekable();
+        while let Some(c) = iter.next() {
+            match c {
+                b'&' => replacement.extend(&old),
+                b'\\' if iter.peek() == Some(&b'&') => replacement.push(iter.next().unwrap()),
+                _ => replacement.push(c),
+            }
+        }
+        self.last_substitution = Some((old.clone(), new.to_vec()));
+        let mut result = vec![];
+        let mut rest = text.as_slice();
+        let mut is_substituted = false;
+        while let Some(position) = find_subslice(rest, &old) {
+            result.extend(&rest[..position]);
+            result.extend(&replacement);
+            rest = &rest[position + old.len()..];
+            is_substituted = true;
+            if !is_global || old.is_empty() {
+                break;
+            }
+        }
+        if !is_substituted {
+            return Err(HistoryExpansionError::SubstitutionFailed);
+        }
+        result.extend(rest);
+        Ok(result)
+    }
+
+    fn apply_modifier(&mut self, modifier: &HistoryModifier, text: Vec<u8>) -> Result<Vec<u8>, HistoryExpansionError> {
+        let last_slash = text.iter().rposition(|&c| c == b'/');
+        let suffix_start = text
+            .iter()
+            .rposition(|&c| c == b'.')
+            .filter(|&dot| last_slash.is_none_or(|slash| dot > slash));
+        let result = match modifier {
+            HistoryModifier::Head => match last_slash {
+                Some(0) => b"/".to_vec(),
+                Some(slash) => text[..slash].to_vec(),
+                None => text,
+            },
+            HistoryModifier::Tail => match last_slash {
+                Some(slash) => text[slash + 1..].to_vec(),
+                None => text,
+            },
+            HistoryModifier::RemoveSuffix => match suffix_start {
+                Some(dot) => text[..dot].to_vec(),
+                None => text,
+            },
+            HistoryModifier::Suffix => match suffix_start {
+                Some(dot) => text[dot..].to_vec(),
+                None => vec![],
+            },
+            HistoryModifier::PrintOnly => {
+                self.is_print_only = true;
+                text
+            },
+            HistoryModifier::Quote => quote_word(&text),
+            HistoryModifier::QuoteWords => {
+                let words: Vec<Vec<u8>> = text
+                    .split(|c| c.is_ascii_whitespace())
+                    .filter(|word| !word.is_empty())
+                    .map(quote_word)
+                    .collect();
+                words.join(&b' ')
+            },
+            HistoryModifier::Substitute { old, new, is_global } => self.substitute(text, old, new, *is_global)?,
+            HistoryModifier::RepeatSubstitution { is_global } => {
+                let (old, new) = self.last_substitution.clone().ok_or(HistoryExpansionError::NoPreviousSubstitution)?;
+                self.substitute(text, &old, &new, *is_global)?
+            },
+        };
+        Ok(result)
+    }
+
+    fn expand(&mut self, expansion: &HistoryExpansion, current_line: &[u8]) -> Result<Vec<u8>, HistoryExpansionError> {
+        let event_line = self.find_event(&expansion.event, current_line)?;
+        let mut result = match &expansion.word {
+            Some(designator) => self.select_words(designator, &event_line)?,
+            None => event_line,
+        };
+        for modifier in expansion.modifiers.iter() {
+            result = self.apply_modifier(modifier, result)?;
+        }
+        Ok(result)
+    }
+}
+
+// History holds the previous lines, oldest first, !1 being the first one
+pub fn expand_history(line: &[u8], history: &[Vec<u8>]) -> Result<ExpandedHistoryLine, HistoryExpansionError> {
+    let iter = ShellInputIterator::new(line.iter().copied().peekable());
+    let mut parser = HistoryExpansionParser::new(iter);
+    let parts = match parser.parse() {
+        Ok(Some(value)) => value,
+        Ok(None) => vec![],
+        Err(ParseError::IncorrectSyntax | ParseError::RequiresNextLine) => {
+            return Err(HistoryExpansionError::BadWordSpecifier)
+        },
+    };
+    let mut expander = HistoryExpander {
+        history,
+        last_search: None,
+        last_substitution: None,
+        is_print_only: false,
+    };
+    let mut result = ExpandedHistoryLine { line: vec![], is_expanded: false, is_print_only: false };
+    for part in parts.iter() {
+        match part {
+            HistoryLinePart::Literal(literal) => result.line.extend(literal),
+            HistoryLinePart::Expansion(expansion) => {
+                let expanded = expander.expand(expansion, &result.line)?;
+                result.line.extend(expanded);
+                result.is_expanded = true;
+            },
+        }
+    }
+    result.is_print_only = expander.is_print_only;
+    Ok(result)
+}
@@ -0,0 +1,108 @@
+use super::arithmetic_expansion::evaluate_arithmetic;
+use super::word_expansion::{expand_word_without_splitting, ExpansionError};
+
+use crate::shell_state::variables::ArrayKey;
+use crate::shell_state::ShellState;
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::parameter_expansion::*;
+use crate::shell_structures::shell_word::ShellWord;
+
+// "$*" joins the positional parameters with the first character of IFS, space when IFS is unset
+fn get_join_separator(state: &ShellState) -> Vec<u8> {
+    match state.get_variable(b"IFS") {
+        Some(ifs) => String::from_utf8_lossy(&ifs)
+            .chars()
+            .next()
+            .map(|c| c.to_string().into_bytes())
+            .unwrap_or_default(),
+        None => b" ".to_vec(),
+    }
+}
+
+// Subscripts of associative arrays are keys, the rest are arithmetic expressions
+pub fn get_array_key(name: &[u8], subscript: &[u8], state: &mut ShellState) -> Result<ArrayKey, ExpansionError> {
+    if state.variables.get(name).is_some_and(|variable| variable.is_associative()) {
+        return Ok(ArrayKey::Key(subscript.to_vec()));
+    }
+    let index = evaluate_arithmetic(subscript, state).map_err(ExpansionError::Arithmetic)?;
+    Ok(ArrayKey::Index(index))
+}
+
+// A nameref to an array element evaluates its subscript on every use: ref='arr[i+1]'
+fn get_named_value(name: &[u8], state: &mut ShellState) -> Result<Option<Vec<u8>>, ExpansionError> {
+    match state.variables.resolve_reference(name).map_err(ExpansionError::Variable)? {
+        (name, Some(subscript)) => {
+            let key = get_array_key(&name, &subscript, state)?;
+            Ok(state.variables.get_element(&name, &key))
+        },
+        (name, None) => Ok(state.get_variable(&name)),
+    }
+}
+
+fn get_element_value(name: &[u8], subscript: &ShellWord, state: &mut ShellState) -> Result<Option<Vec<u8>>, ExpansionError> {
+    let subscript = expand_word_without_splitting(subscript, state)?;
+    let key = get_array_key(name, &subscript, state)?;
+    Ok(state.variables.get_element(name, &key))
+}
+
+// $@, $*, ${name[@]} and ${name[*]} expand to all of their values, the flag is set for the joined ones
+fn get_all_values(parameter: &Parameter, state: &ShellState) -> Option<(Vec<Vec<u8>>, bool)> {
+    match parameter {
+        Parameter::Special(SpecialParameter::AllPositionalFields) => Some((state.get_positional_parameters().to_vec(), false)),
+        Parameter::Special(SpecialParameter::AllPositionalJoined) => Some((state.get_positional_parameters().to_vec(), true)),
+        Parameter::Element(name, subscript @ (ArraySubscript::AllFields | ArraySubscript::AllJoined)) => {
+            let values = state.variables.get(name).map(|variable| variable.get_values()).unwrap_or_default();
+            Some((values, matches!(subscript, ArraySubscript::AllJoined)))
+        },
+        _ => None,
+    }
+}
+
+pub fn get_parameter_value(parameter: &Parameter, state: &mut ShellState) -> Result<Option<Vec<u8>>, ExpansionError> {
+    if let Some((values, _)) = get_all_values(parameter, state) {
+        return Ok(Some(values.join(get_join_separator(state).as_slice())));
+    }
+    let value = match parameter {
+        Parameter::Named(name) => get_named_value(name, state)?,
+        Parameter::Positional(n) => n.checked_sub(1).and_then(|index| state.get_positional_parameters().get(index).cloned()),
+        Parameter::Element(name, ArraySubscript::Expression(subscript)) => get_element_value(name, subscript, state)?,
+        Parameter::Element(..) => unreachable!(),
+        Parameter::Special(special) => match special {
+            SpecialParameter::ExitStatus => Some(state.last_exit_status.to_string().into_bytes()),
+            SpecialParameter::ShellPid => Some(state.shell_pid.to_string().into_bytes()),
+            SpecialParameter::LastBackgroundPid => state.last_background_pid.map(|pid| pid.to_string().into_bytes()),
+            SpecialParameter::PositionalCount => Some(state.get_positional_parameters().len().to_string().into_bytes()),
+            SpecialParameter::ShellName => Some(state.shell_name.clone()),
+            SpecialParameter::Flags => Some(state.get_flags()),
+            SpecialParameter::AllPositionalFields | SpecialParameter::AllPositionalJoined => unreachable!(),
+        },
+    };
+    Ok(value)
+}
+
+// ${#@} and ${#name[@]} count the values, the rest count characters
+fn get_length(parameter: &Parameter, state: &mut ShellState) -> Result<usize, ExpansionError> {
+    if let Some((values, _)) = get_all_values(parameter, state) {
+        return Ok(values.len());
+    }
+    let value = get_parameter_value(parameter, state)?.unwrap_or_default();
+    Ok(String::from_utf8_lossy(&value).chars().count())
+}
+
+// Every returned value is a separate field: "$@" and unquoted $@ and $* give one field per
+// positional parameter (none at all when there are no parameters), "$*" joins them into one;
+// arrays behave the same with [@] and [*]
+pub fn expand_parameter(expansion: &ParameterExpansion, state: &mut ShellState, is_quoted: bool) -> Result<Vec<Vec<u8>>, ExpansionError> {
+    let (parameter, keys) = match expansion {
+        ParameterExpansion::Simple(parameter) | ParameterExpansion::Braced(parameter) => (parameter, None),
+        ParameterExpansion::Length(parameter) => return Ok(vec![get_length(parameter, state)?.to_string().into_bytes()]),
+        ParameterExpansion::Keys(name, is_joined) => {
+            let keys = state.variables.get(name).map(|variable| variable.get_keys()).unwrap_or_default();
+            (&Parameter::Named(name.clone()), Some((keys, *is_joined)))
+        },
+    };
+    match keys.or_else(|| get_all_values(parameter, state)) {
+        Some((values, true)) if is_quoted => Ok(vec![values.join(get_join_separator(state).as_slice())]),
+        Some((values, _)) => Ok(values),
+        None => Ok(vec![get_parameter_value(parameter, state)?.unwrap_or_default()]),
+    }
+}
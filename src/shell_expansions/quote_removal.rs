@@ -0,0 +1,44 @@
+use super::expanded_word::ExpandedFragment;
+
+use crate::shell_structures::shell_word::quoted_expressions::{QuoteExpression, WeakQuoteExpression, WeakQuoteExpressionPart};
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+use crate::shell_structures::ShellToken;
+
+pub fn remove_weak_quotes(expr: &WeakQuoteExpression) -> Vec<u8> {
+    let mut result = vec![];
+    for part in expr.parts.iter() {
+        match part {
+            WeakQuoteExpressionPart::Literal(literal) => result.extend(literal),
+            WeakQuoteExpressionPart::DollarSignExpansion(expansion) => result.extend(expansion.restore_original()),
+        }
+    }
+    result
+}
+
+// Text of a word that is not expanded, such as a here-document delimiter:
+// quotes and escapes are dropped, expansions are kept as written
+pub fn remove_quotes(word: &ShellWord) -> Vec<u8> {
+    let mut result = vec![];
+    for part in word.parts.iter() {
+        match part {
+            ShellExpression::Literal(literal) => result.extend(literal),
+            ShellExpression::EscapedLiteral(c) => result.push(*c),
+            ShellExpression::QuoteExpression(QuoteExpression::Strong(expr)) => result.extend(&expr.contents),
+            ShellExpression::QuoteExpression(QuoteExpression::AnsiC(expr)) => result.extend(&expr.contents),
+            ShellExpression::QuoteExpression(QuoteExpression::Weak(expr) | QuoteExpression::Translatable(expr)) => {
+                result.extend(remove_weak_quotes(expr))
+            },
+            _ => result.extend(part.restore_original()),
+        }
+    }
+    result
+}
+
+// Final stage of the expansion: quoting was already recorded in the fragments, so only the text is left
+pub fn join_field(field: &[ExpandedFragment]) -> Vec<u8> {
+    let mut result = vec![];
+    for fragment in field.iter() {
+        result.extend(fragment.get_text());
+    }
+    result
+}
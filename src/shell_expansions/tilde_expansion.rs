@@ -0,0 +1,33 @@
+use std::ffi::{CStr, CString};
+
+// Home directory from the password database, current user when login_name is None
+fn get_home_directory(login_name: Option<&[u8]>) -> Option<Vec<u8>> {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut passwd_ptr: *mut libc::passwd = std::ptr::null_mut();
+    let mut buffer = vec![0 as libc::c_char; 16384];
+    let status = match login_name {
+        Some(name) => {
+            let name = CString::new(name).ok()?;
+            unsafe {
+                libc::getpwnam_r(name.as_ptr(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut passwd_ptr)
+            }
+        },
+        None => unsafe {
+            libc::getpwuid_r(libc::getuid(), &mut passwd, buffer.as_mut_ptr(), buffer.len(), &mut passwd_ptr)
+        },
+    };
+    if status != 0 || passwd_ptr.is_null() || passwd.pw_dir.is_null() {
+        return None;
+    }
+    Some(unsafe { CStr::from_ptr(passwd.pw_dir) }.to_bytes().to_vec())
+}
+
+// None means the tilde prefix is left unchanged
+pub fn expand_tilde_prefix(login_name: &[u8], get_variable: &dyn Fn(&[u8]) -> Option<Vec<u8>>) -> Option<Vec<u8>> {
+    match login_name {
+        b"" => get_variable(b"HOME").or_else(|| get_home_directory(None)),
+        b"+" => get_variable(b"PWD"),
+        b"-" => get_variable(b"OLDPWD"),
+        _ => get_home_directory(Some(login_name)),
+    }
+}
@@ -0,0 +1,212 @@
+use errno::Errno;
+
+use super::arithmetic_expansion::{evaluate_arithmetic, ArithmeticError};
+use super::brace_expansion::expand_braces;
+use super::expanded_word::ExpandedFragment;
+use super::filename_expansion::{expand_filenames, get_field_pattern, FilenameExpansionError};
+use super::parameter_expansion::expand_parameter;
+use super::quote_removal::join_field;
+use super::tilde_expansion::expand_tilde_prefix;
+use super::word_splitting::split_fields;
+
+use crate::shell_execution::command_substitution::run_command_substitution;
+use crate::shell_state::variables::VariableError;
+use crate::shell_state::ShellState;
+use crate::shell_structures::shell_word::quoted_expressions::{QuoteExpression, WeakQuoteExpression, WeakQuoteExpressionPart};
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::parameter_expansion::*;
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::DollarSignExpansion;
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+use crate::shell_structures::ShellToken;
+use crate::shell_translation::translate_quote_expression;
+
+#[derive(Debug)]
+pub enum ExpansionError {
+    Arithmetic(ArithmeticError),
+    Filename(FilenameExpansionError),
+    Variable(VariableError),
+    CommandSubstitution(Errno), // the pipe or the process could not be created
+}
+
+impl ExpansionError {
+    pub fn get_message(&self) -> String {
+        match self {
+            ExpansionError::Arithmetic(err) => err.get_message(),
+            ExpansionError::Filename(err) => err.get_message(),
+            ExpansionError::Variable(err) => err.get_message(),
+            ExpansionError::CommandSubstitution(err) => format!("command substitution: {err}"),
+        }
+    }
+}
+
+// "$@", "${name[@]}" and "${!name[@]}" are the only expansions that can make a double-quoted string
+// disappear entirely
+fn contains_all_fields(expression: &WeakQuoteExpression) -> bool {
+    expression.parts.iter().any(|part| match part {
+        WeakQuoteExpressionPart::DollarSignExpansion(expansion) => match expansion.as_ref() {
+            DollarSignExpansion::Parameter(ParameterExpansion::Simple(parameter) | ParameterExpansion::Braced(parameter)) => {
+                matches!(
+                    parameter,
+                    Parameter::Special(SpecialParameter::AllPositionalFields) | Parameter::Element(_, ArraySubscript::AllFields)
+                )
+            },
+            DollarSignExpansion::Parameter(ParameterExpansion::Keys(_, is_joined)) => !is_joined,
+            _ => false,
+        },
+        WeakQuoteExpressionPart::Literal(_) => false,
+    })
+}
+
+// Tilde, parameter, arithmetic and command expansions, performed left to right on a single word
+struct WordExpander<'a> {
+    state: &'a mut ShellState,
+    is_splitting: bool, // false where the word stays a single field, fields of $@ are joined with spaces then
+    fragments: Vec<ExpandedFragment>,
+}
+
+impl<'a> WordExpander<'a> {
+    fn new(state: &'a mut ShellState, is_splitting: bool) -> Self {
+        Self { state, is_splitting, fragments: vec![] }
+    }
+
+    fn push_fields(&mut self, fields: Vec<Vec<u8>>, is_quoted: bool) {
+        for (i, field) in fields.into_iter().enumerate() {
+            if i > 0 {
+                self.fragments.push(match self.is_splitting {
+                    true => ExpandedFragment::FieldBreak,
+                    false => ExpandedFragment::Quoted(b" ".to_vec()),
+                });
+            }
+            self.fragments.push(match is_quoted {
+                true => ExpandedFragment::Quoted(field),
+                false => ExpandedFragment::Expanded(field),
+            });
+        }
+    }
+
+    // Contents of $((...)) are expanded as in double quotes before the evaluation
+    fn expand_arithmetic_text(&mut self, parts: &[WeakQuoteExpressionPart]) -> Result<Vec<u8>, ExpansionError> {
+        let mut result = vec![];
+        for part in parts.iter() {
+            match part {
+                WeakQuoteExpressionPart::Literal(literal) => result.extend(literal),
+                WeakQuoteExpressionPart::DollarSignExpansion(expansion) => {
+                    result.extend(self.expand_dollar_sign(expansion, true)?.join(&b' '))
+                },
+            }
+        }
+        Ok(result)
+    }
+
+    fn expand_dollar_sign(&mut self, expansion: &DollarSignExpansion, is_quoted: bool) -> Result<Vec<Vec<u8>>, ExpansionError> {
+        match expansion {
+            DollarSignExpansion::Parameter(expansion) => {
+                expand_parameter(expansion, self.state, is_quoted || !self.is_splitting)
+            },
+            DollarSignExpansion::Arithmetic(expansion) => {
+                let expression = self.expand_arithmetic_text(&expansion.parts)?;
+                let value = evaluate_arithmetic(&expression, self.state).map_err(ExpansionError::Arithmetic)?;
+                Ok(vec![value.to_string().into_bytes()])
+            },
+            DollarSignExpansion::Command(expansion) => Ok(vec![run_command_substitution(&expansion.command, self.state)?]),
+        }
+    }
+
+    fn expand_weak_quote(&mut self, expression: &WeakQuoteExpression) -> Result<(), ExpansionError> {
+        if !contains_all_fields(expression) {
+            self.fragments.push(ExpandedFragment::Quoted(vec![]));
+        }
+        for part in expression.parts.iter() {
+            match part {
+                WeakQuoteExpressionPart::Literal(literal) if literal.is_empty() => {},
+                WeakQuoteExpressionPart::Literal(literal) => self.fragments.push(ExpandedFragment::Quoted(literal.clone())),
+                WeakQuoteExpressionPart::DollarSignExpansion(expansion) => {
+                    let fields = self.expand_dollar_sign(expansion, true)?;
+                    self.push_fields(fields, true);
+                },
+            }
+        }
+        Ok(())
+    }
+
+    // A tilde prefix only expands at the start of the word or after a colon of an assignment,
+    // brace expansion can move it elsewhere: a{~,b}
+    fn expand_tilde(&mut self, login_name: &[u8]) {
+        let is_expandable = match self.fragments.last() {
+            None => true,
+            Some(ExpandedFragment::Literal(literal)) => literal.last() == Some(&b':'),
+            Some(_) => false,
+        };
+        let state = &*self.state;
+        let expanded = is_expandable.then(|| expand_tilde_prefix(login_name, &|name| state.get_variable(name))).flatten();
+        self.fragments.push(match expanded {
+            Some(directory) => ExpandedFragment::Quoted(directory),
+            None => ExpandedFragment::Literal([b"~", login_name].concat()),
+        });
+    }
+
+    fn expand_part(&mut self, part: &ShellExpression) -> Result<(), ExpansionError> {
+        match part {
+            ShellExpression::Literal(literal) if literal.is_empty() => {},
+            ShellExpression::Literal(literal) => self.fragments.push(ExpandedFragment::Literal(literal.clone())),
+            ShellExpression::EscapedLiteral(c) => self.fragments.push(ExpandedFragment::Quoted(vec![*c])),
+            ShellExpression::BraceExpansion(expansion) => {
+                self.fragments.push(ExpandedFragment::Literal(expansion.restore_original()))
+            },
+            ShellExpression::DollarSignExpansion(expansion) => {
+                let fields = self.expand_dollar_sign(expansion, false)?;
+                self.push_fields(fields, false);
+            },
+            ShellExpression::QuoteExpression(expression) => match expression {
+                QuoteExpression::Strong(expression) => {
+                    self.fragments.push(ExpandedFragment::Quoted(expression.contents.clone()))
+                },
+                QuoteExpression::AnsiC(expression) => {
+                    self.fragments.push(ExpandedFragment::Quoted(expression.contents.clone()))
+                },
+                QuoteExpression::Weak(expression) => self.expand_weak_quote(expression)?,
+                QuoteExpression::Translatable(expression) => {
+                    let variables = &self.state.variables;
+                    let catalog = self.state.message_catalogs.get(&|name| variables.get_scalar(name));
+                    let translated = translate_quote_expression(expression, catalog);
+                    self.expand_weak_quote(&translated)?;
+                },
+            },
+            ShellExpression::GlobbingPattern(glob) => self.fragments.push(ExpandedFragment::Glob(glob.clone())),
+            ShellExpression::TildePrefix(login_name) => self.expand_tilde(login_name),
+        }
+        Ok(())
+    }
+
+    fn expand(mut self, word: &ShellWord) -> Result<Vec<ExpandedFragment>, ExpansionError> {
+        for part in word.parts.iter() {
+            self.expand_part(part)?;
+        }
+        Ok(self.fragments)
+    }
+}
+
+// Brace expansion, then tilde, parameter, arithmetic and command expansions, word splitting,
+// filename expansion and quote removal, in the order bash performs them
+pub fn expand_word(word: &ShellWord, state: &mut ShellState) -> Result<Vec<Vec<u8>>, ExpansionError> {
+    let mut result = vec![];
+    for word in expand_braces(word) {
+        let fragments = WordExpander::new(state, true).expand(&word)?;
+        let ifs = state.get_variable(b"IFS");
+        for field in split_fields(fragments, ifs.as_deref()) {
+            let pattern = get_field_pattern(&field);
+            let glob_ignore = state.get_variable(b"GLOBIGNORE");
+            match expand_filenames(&pattern, &state.options, glob_ignore.as_deref()).map_err(ExpansionError::Filename)? {
+                Some(paths) => result.extend(paths),
+                None => result.push(join_field(&field)),
+            }
+        }
+    }
+    Ok(result)
+}
+
+// For assignment values and array subscripts, here-strings and here-documents: the word stays a
+// single field, so there is no brace expansion, word splitting or filename expansion
+pub fn expand_word_without_splitting(word: &ShellWord, state: &mut ShellState) -> Result<Vec<u8>, ExpansionError> {
+    let fragments = WordExpander::new(state, false).expand(word)?;
+    Ok(join_field(&fragments))
+}
@@ -0,0 +1,102 @@
+use super::expanded_word::{ExpandedField, ExpandedFragment};
+
+const DEFAULT_IFS: &[u8] = b" \t\n";
+
+struct FieldSplitter {
+    whitespace: Vec<Vec<u8>>, // IFS whitespace: runs of it are a single delimiter, ignored at the edges
+    delimiters: Vec<Vec<u8>>, // other IFS characters: every one of them ends a field
+    fields: Vec<ExpandedField>,
+    current_field: ExpandedField,
+    has_content: bool,
+    is_after_whitespace: bool,
+}
+
+// IFS is taken by characters, so multibyte delimiters work in UTF-8 locales
+fn split_ifs_characters(ifs: &[u8]) -> Vec<Vec<u8>> {
+    match std::str::from_utf8(ifs) {
+        Ok(value) => value.chars().map(|c| c.to_string().into_bytes()).collect(),
+        Err(_) => ifs.iter().map(|&c| vec![c]).collect(),
+    }
+}
+
+impl FieldSplitter {
+    fn new(ifs: &[u8]) -> Self {
+        let (whitespace, delimiters) = split_ifs_characters(ifs)
+            .into_iter()
+            .partition(|c| matches!(c.as_slice(), b" " | b"\t" | b"\n"));
+        Self {
+            whitespace,
+            delimiters,
+            fields: vec![],
+            current_field: vec![],
+            has_content: false,
+            is_after_whitespace: false,
+        }
+    }
+
+    fn finish_field(&mut self, keep_empty: bool) {
+        if self.has_content || keep_empty {
+            self.fields.push(std::mem::take(&mut self.current_field));
+        }
+        self.current_field.clear();
+        self.has_content = false;
+        self.is_after_whitespace = false;
+    }
+
+    fn push(&mut self, fragment: ExpandedFragment) {
+        if !fragment.has_content() {
+            return;
+        }
+        if self.is_after_whitespace {
+            self.finish_field(false);
+        }
+        self.current_field.push(fragment);
+        self.has_content = true;
+    }
+
+    fn match_delimiter(candidates: &[Vec<u8>], text: &[u8]) -> Option<usize> {
+        candidates.iter().find(|c| text.starts_with(c)).map(|c| c.len())
+    }
+
+    fn split_expanded(&mut self, text: &[u8]) {
+        let mut position = 0;
+        let mut current_text = vec![];
+        while position < text.len() {
+            let rest = &text[position..];
+            if let Some(length) = Self::match_delimiter(&self.whitespace, rest) {
+                self.push(ExpandedFragment::Expanded(std::mem::take(&mut current_text)));
+                self.is_after_whitespace = true;
+                position += length;
+            } else if let Some(length) = Self::match_delimiter(&self.delimiters, rest) {
+                self.push(ExpandedFragment::Expanded(std::mem::take(&mut current_text)));
+                self.finish_field(true);
+                position += length;
+            } else {
+                if self.is_after_whitespace && current_text.is_empty() {
+                    self.finish_field(false);
+                }
+                current_text.push(text[position]);
+                position += 1;
+            }
+        }
+        self.push(ExpandedFragment::Expanded(current_text));
+    }
+
+    fn split(mut self, fragments: Vec<ExpandedFragment>) -> Vec<ExpandedField> {
+        for fragment in fragments {
+            match fragment {
+                ExpandedFragment::Expanded(text) => self.split_expanded(&text),
+                ExpandedFragment::FieldBreak => self.finish_field(false),
+                _ => self.push(fragment),
+            }
+        }
+        self.finish_field(false);
+        self.fields
+    }
+}
+
+// Only unquoted expansion results are split, fields that end up empty and unquoted are dropped;
+// IFS is None when it is unset
+pub fn split_fields(fragments: Vec<ExpandedFragment>, ifs: Option<&[u8]>) -> Vec<ExpandedField> {
+    FieldSplitter::new(ifs.unwrap_or(DEFAULT_IFS)).split(fragments)
+}
@@ -9,6 +9,7 @@ where
 {
     iter: Peekable<I>,
     current_index: usize,
+    here_document_ranges: Vec<(usize, usize)>, // jumped over once the line before them is read
 }
 
 pub trait ShellInputIteratorExt: Iterator {
@@ -28,12 +29,57 @@ where
         Self {
             iter: input_iter,
             current_index: 0,
+            here_document_ranges: vec![],
         }
     }
 
     pub fn get_current_index(&self) -> usize {
         self.current_index
     }
+
+    fn skip_here_documents(&mut self) {
+        while let Some(&(start, end)) = self.here_document_ranges.first() {
+            if start != self.current_index {
+                return;
+            }
+            while self.current_index < end && self.iter.next().is_some() {
+                self.current_index += 1;
+            }
+            self.here_document_ranges.remove(0);
+        }
+    }
+
+    // The lines after the current one, up to the delimiter line: they are taken out of the input and
+    // the parsing goes on after them once the current line ends. Several here-documents on a line
+    // follow each other. None when the input ends before the delimiter
+    pub fn take_here_document(&mut self, delimiter: &[u8], is_stripping_tabs: bool) -> Option<Vec<u8>> {
+        let mut iter = self.clone();
+        while iter.next()? != b'\n' {}
+        iter.skip_here_documents();
+        let start = iter.current_index;
+        let mut result = vec![];
+        loop {
+            let mut line = vec![];
+            let is_terminated = loop {
+                match iter.next() {
+                    Some(b'\n') => break true,
+                    Some(c) => line.push(c),
+                    None => break false,
+                }
+            };
+            let tabs = if is_stripping_tabs { line.iter().take_while(|&&c| c == b'\t').count() } else { 0 };
+            if line[tabs..] == *delimiter {
+                break;
+            }
+            if !is_terminated {
+                return None;
+            }
+            result.extend(&line[tabs..]);
+            result.push(b'\n');
+        }
+        self.here_document_ranges.push((start, iter.current_index));
+        Some(result)
+    }
 }
 
 impl<I> Iterator for ShellInputIterator<I>
@@ -43,6 +89,7 @@ where
     type Item = u8;
 
     fn next(&mut self) -> Option<Self::Item> {
+        self.skip_here_documents();
         self.current_index += 1;
         self.iter.next()
     }
@@ -73,10 +120,12 @@ where
     }
 
     fn peek(&mut self) -> Option<&u8> {
+        self.skip_here_documents();
         self.iter.peek()
     }
 
     fn next_if(&mut self, func: impl FnOnce(&u8) -> bool) -> Option<u8> {
+        self.skip_here_documents();
         let result = self.iter.next_if(func);
         if result.is_some() {
             self.current_index += 1;
@@ -104,7 +153,7 @@ where
     }
 
     fn skip_whitespace(&mut self) {
-        while let Some(_) = self.iter.next_if(|&c| { c == b' ' || c == b'\t' }) {
+        while let Some(_) = self.next_if(|&c| { c == b' ' || c == b'\t' }) {
 
         }
     }
@@ -6,13 +6,18 @@ pub type ParseResult<T> = Result<Option<T>, ParseError>;
 
 pub struct ShellParsingRules {
     pub is_interactive: bool,
+    pub is_extglob_enabled: bool,
 }
 
+#[derive(Clone)]
 pub struct ShellWordParsingRules {
     pub parse_history_expansions: bool, // !34:^
     pub parse_brace_expansions: bool, // {1..6..2}, {a,b,h}
     pub parse_dollar_sign_expansions: bool, // parameter, command, arithmetic
     pub parse_filename_expansions: bool, // *, ?
+    pub parse_extended_globs: bool, // ?(a|b), !(*.o)
+    pub parse_tilde_expansions: bool, // ~, ~user, ~+, ~- at the start of the word
+    pub parse_tilde_expansions_after_colons: bool, // PATH=~/bin:~user/bin
     pub additional_word_stop_characters: Vec<u8>,
 }
 
@@ -23,6 +28,9 @@ pub fn get_brace_list_word_parsing_rules(base_rules: &ShellParsingRules, is_firs
         parse_brace_expansions: true,
         parse_dollar_sign_expansions: true,
         parse_filename_expansions: true,
+        parse_extended_globs: base_rules.is_extglob_enabled,
+        parse_tilde_expansions: true,
+        parse_tilde_expansions_after_colons: false,
         additional_word_stop_characters: if is_first {
             vec![b',']
         } else {
@@ -37,6 +45,24 @@ pub fn get_variable_value_word_parsing_rules(base_rules: &ShellParsingRules) ->
         parse_brace_expansions: false,
         parse_dollar_sign_expansions: true,
         parse_filename_expansions: true,
+        parse_extended_globs: base_rules.is_extglob_enabled,
+        parse_tilde_expansions: true,
+        parse_tilde_expansions_after_colons: true,
+        additional_word_stop_characters: vec![],
+    }
+}
+
+// Inside name[...]: only expansions and quoting, the subscript is an arithmetic expression or a key;
+// history expansion has already been done on the whole line
+pub fn get_subscript_word_parsing_rules() -> ShellWordParsingRules {
+    ShellWordParsingRules {
+        parse_history_expansions: false,
+        parse_brace_expansions: false,
+        parse_dollar_sign_expansions: true,
+        parse_filename_expansions: false,
+        parse_extended_globs: false,
+        parse_tilde_expansions: false,
+        parse_tilde_expansions_after_colons: false,
         additional_word_stop_characters: vec![],
     }
 }
@@ -47,6 +73,9 @@ pub fn get_default_word_parsing_rules(base_rules: &ShellParsingRules) -> ShellWo
         parse_brace_expansions: true,
         parse_dollar_sign_expansions: true,
         parse_filename_expansions: true,
+        parse_extended_globs: base_rules.is_extglob_enabled,
+        parse_tilde_expansions: true,
+        parse_tilde_expansions_after_colons: false,
         additional_word_stop_characters: vec![],
     }
 }
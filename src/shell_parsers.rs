@@ -1,5 +1,8 @@
 pub mod shell_word_parser;
 pub mod shell_input_parser;
 pub mod shell_command_parser;
+pub mod shell_pipeline_parser;
+pub mod shell_list_parser;
 pub mod shell_variable_assignment_parser;
-
+pub mod shell_redirection_parser;
+pub mod history_expansion_parser;
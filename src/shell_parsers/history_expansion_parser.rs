@@ -0,0 +1,282 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{ParseError, ParseResult};
+use crate::shell_structures::history_expansion::*;
+
+pub struct HistoryExpansionParser<I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+}
+
+impl<I> HistoryExpansionParser<I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>) -> Self {
+        Self { iter }
+    }
+
+    fn parse_number(&mut self) -> Option<usize> {
+        let mut result = vec![];
+        while let Some(next_char) = self.iter.next_if(|c| c.is_ascii_digit()) {
+            result.push(next_char);
+        }
+        String::from_utf8(result).ok()?.parse().ok()
+    }
+
+    fn check_event_end(c: u8) -> bool {
+        c.is_ascii_whitespace() || matches!(c, b':' | b';' | b'|' | b'&' | b'<' | b'>' | b'(' | b')' | b'"' | b'\'')
+    }
+
+    // Called right after '!'
+    fn parse_event_designator(&mut self) -> ParseResult<EventDesignator> {
+        let next_char = match self.iter.peek() {
+            Some(value) => *value,
+            None => return Ok(None),
+        };
+        let event = match next_char {
+            b'!' => {
+                self.iter.next();
+                EventDesignator::PreviousCommand
+            },
+            b'#' => {
+                self.iter.next();
+                EventDesignator::CurrentLine
+            },
+            b'^' | b'$' | b'*' | b'%' | b':' => EventDesignator::PreviousCommand,
+            b'-' => {
+                self.iter.next();
+                match self.parse_number() {
+                    Some(value) => EventDesignator::Relative(value),
+                    None => return Err(ParseError::IncorrectSyntax),
+                }
+            },
+            b'0'..=b'9' => EventDesignator::Absolute(self.parse_number().ok_or(ParseError::IncorrectSyntax)?),
+            b'?' => {
+                self.iter.next();
+                let mut text = vec![];
+                while let Some(next_char) = self.iter.next_if(|&c| c != b'?' && c != b'\n') {
+                    text.push(next_char);
+                }
+                self.iter.next_if(|&c| c == b'?');
+                EventDesignator::Contains(text)
+            },
+            _ => {
+                let mut prefix = vec![];
+                while let Some(next_char) = self.iter.next_if(|&c| !Self::check_event_end(c)) {
+                    prefix.push(next_char);
+                }
+                if prefix.is_empty() {
+                    return Ok(None);
+                }
+                EventDesignator::StartsWith(prefix)
+            },
+        };
+        Ok(Some(event))
+    }
+
+    fn parse_word_index(&mut self) -> Option<WordIndex> {
+        match self.iter.peek()? {
+            b'^' => {
+                self.iter.next();
+                Some(WordIndex::Number(1))
+            },
+            b'$' => {
+                self.iter.next();
+                Some(WordIndex::Last)
+            },
+            b'%' => {
+                self.iter.next();
+                Some(WordIndex::Matched)
+            },
+            c if c.is_ascii_digit() => self.parse_number().map(WordIndex::Number),
+            _ => None,
+        }
+    }
+
+    fn parse_word_designator(&mut self) -> ParseResult<WordDesignator> {
+        let iter_state = self.iter.clone();
+        let had_colon = self.iter.next_if(|&c| c == b':').is_some();
+        if self.iter.next_if(|&c| c == b'*').is_some() {
+            return Ok(Some(WordDesignator::AllArguments));
+        }
+        if self.iter.next_if(|&c| c == b'-').is_some() {
+            let end = self.parse_word_index().ok_or(ParseError::IncorrectSyntax)?;
+            return Ok(Some(WordDesignator::Range(WordIndex::Number(0), end)));
+        }
+        let is_number = self.iter.peek().is_some_and(u8::is_ascii_digit);
+        let start = match self.parse_word_index() {
+            Some(value) => value,
+            None => {
+                self.iter = iter_state;
+                return Ok(None);
+            },
+        };
+        if self.iter.next_if(|&c| c == b'*').is_some() {
+            return Ok(Some(WordDesignator::Range(start, WordIndex::Last)));
+        }
+        if self.iter.next_if(|&c| c == b'-').is_some() {
+            return match self.parse_word_index() {
+                Some(end) => Ok(Some(WordDesignator::Range(start, end))),
+                None => Ok(Some(WordDesignator::RangeWithoutLast(start))),
+            };
+        }
+        if !had_colon && is_number {
+            // !n is an event number, digits after it are not a word designator; ^ is a word designator
+            self.iter = iter_state;
+            return Ok(None);
+        }
+        Ok(Some(WordDesignator::Single(start)))
+    }
+
+    // Bytes up to the delimiter, a backslash escapes the delimiter
+    fn parse_substitution_part(&mut self, delimiter: u8) -> (Vec<u8>, bool) {
+        let mut result = vec![];
+        while let Some(next_char) = self.iter.next_if(|&c| c != b'\n') {
+            if next_char == delimiter {
+                return (result, true);
+            }
+            if next_char == b'\\' && self.iter.peek() == Some(&delimiter) {
+                result.push(self.iter.next().unwrap());
+            } else {
+                result.push(next_char);
+            }
+        }
+        (result, false)
+    }
+
+    fn parse_substitution(&mut self, is_global: bool) -> ParseResult<HistoryModifier> {
+        let delimiter = match self.iter.next() {
+            Some(value) => value,
+            None => return Err(ParseError::IncorrectSyntax),
+        };
+        let (old, is_terminated) = self.parse_substitution_part(delimiter);
+        let new = if is_terminated {
+            self.parse_substitution_part(delimiter).0
+        } else {
+            vec![]
+        };
+        Ok(Some(HistoryModifier::Substitute { old, new, is_global }))
+    }
+
+    fn parse_modifier(&mut self) -> ParseResult<HistoryModifier> {
+        let iter_state = self.iter.clone();
+        if self.iter.next_if(|&c| c == b':').is_none() {
+            return Ok(None);
+        }
+        let is_global = self.iter.next_if(|&c| matches!(c, b'g' | b'a' | b'G')).is_some();
+        let modifier = match self.iter.peek() {
+            Some(b's') => {
+                self.iter.next();
+                return self.parse_substitution(is_global);
+            },
+            Some(b'&') => HistoryModifier::RepeatSubstitution { is_global },
+            _ if is_global => return Err(ParseError::IncorrectSyntax),
+            Some(b'h') => HistoryModifier::Head,
+            Some(b't') => HistoryModifier::Tail,
+            Some(b'r') => HistoryModifier::RemoveSuffix,
+            Some(b'e') => HistoryModifier::Suffix,
+            Some(b'p') => HistoryModifier::PrintOnly,
+            Some(b'q') => HistoryModifier::Quote,
+            Some(b'x') => HistoryModifier::QuoteWords,
+            _ => {
+                self.iter = iter_state;
+                return Ok(None);
+            },
+        };
+        self.iter.next();
+        Ok(Some(modifier))
+    }
+
+    // Called right after '!'
+    fn parse_expansion(&mut self) -> ParseResult<HistoryExpansion> {
+        let event = match self.parse_event_designator()? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let word = self.parse_word_designator()?;
+        let mut modifiers = vec![];
+        while let Some(modifier) = self.parse_modifier()? {
+            modifiers.push(modifier);
+        }
+        Ok(Some(HistoryExpansion { event, word, modifiers }))
+    }
+
+    // ^old^new^ at the start of the line is the same as !!:s^old^new^
+    fn parse_quick_substitution(&mut self) -> ParseResult<HistoryExpansion> {
+        let substitution = match self.parse_substitution(false)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let mut modifiers = vec![substitution];
+        while let Some(modifier) = self.parse_modifier()? {
+            modifiers.push(modifier);
+        }
+        Ok(Some(HistoryExpansion { event: EventDesignator::PreviousCommand, word: None, modifiers }))
+    }
+
+    fn check_expansion_start(&mut self, is_double_quoted: bool) -> bool {
+        match self.iter.peek() {
+            None | Some(b' ' | b'\t' | b'\n' | b'\r' | b'=' | b'(') => false,
+            Some(b'"') => !is_double_quoted,
+            _ => true,
+        }
+    }
+
+    // ${!name} is an indirection and [!a] a bracket expression, neither is a history expansion
+    fn check_expansion_inhibited(&self, current_literal: &[u8]) -> bool {
+        if current_literal.ends_with(b"${") {
+            return true;
+        }
+        current_literal.ends_with(b"[") && self.iter.clone().any(|c| c == b']')
+    }
+
+    pub fn parse(&mut self) -> ParseResult<Vec<HistoryLinePart>> {
+        let mut result = vec![];
+        let mut current_literal: Vec<u8> = vec![];
+        let mut is_single_quoted = false;
+        let mut is_double_quoted = false;
+        if self.iter.peek() == Some(&b'^') {
+            if let Some(expansion) = self.parse_quick_substitution()? {
+                result.push(HistoryLinePart::Expansion(expansion));
+            }
+        }
+        while let Some(next_char) = self.iter.next() {
+            if is_single_quoted {
+                is_single_quoted = next_char != b'\'';
+                current_literal.push(next_char);
+            }
+            else if next_char == b'\\' {
+                current_literal.push(next_char);
+                if let Some(escaped_char) = self.iter.next() {
+                    current_literal.push(escaped_char);
+                }
+            }
+            else if next_char == b'!' && !self.check_expansion_inhibited(&current_literal) && self.check_expansion_start(is_double_quoted) {
+                match self.parse_expansion()? {
+                    Some(expansion) => {
+                        result.push(HistoryLinePart::Literal(std::mem::take(&mut current_literal)));
+                        result.push(HistoryLinePart::Expansion(expansion));
+                    },
+                    None => current_literal.push(next_char),
+                }
+            }
+            else {
+                if next_char == b'\'' && !is_double_quoted {
+                    is_single_quoted = true;
+                } else if next_char == b'"' {
+                    is_double_quoted = !is_double_quoted;
+                }
+                current_literal.push(next_char);
+            }
+        }
+        if !current_literal.is_empty() {
+            result.push(HistoryLinePart::Literal(current_literal));
+        }
+        match result.len() {
+            0 => Ok(None),
+            _ => Ok(Some(result)),
+        }
+    }
+}
@@ -1,69 +1,73 @@
-use std::iter::Peekable;
+use std::rc::Rc;
+
 use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
-use crate::shell_parser_base::ParseResult;
+use crate::shell_parser_base::{ParseError, ParseResult, ShellParsingRules};
+use crate::shell_parsers::shell_redirection_parser::ShellRedirectionParser;
+use crate::shell_structures::shell_command::shell_compound_command::ShellCompoundCommand;
+use crate::shell_structures::shell_command::shell_function_definition::ShellFunctionDefinition;
 use crate::shell_structures::shell_command::ShellCommand;
+use crate::shell_structures::shell_redirection::ShellRedirection;
 
 pub mod shell_simple_command_parser;
+pub mod shell_compound_command_parser;
+
+use shell_compound_command_parser::ShellCompoundCommandParser;
+use shell_simple_command_parser::ShellSimpleCommandParser;
 
-pub struct ShellCommandParser<I>
+pub struct ShellCommandParser<'a, I>
 where
     I: Iterator<Item = u8> + Clone
 {
-    pub iter: ShellInputIterator<I>
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
 }
 
-impl<I> ShellCommandParser<I>
+impl<'a, I> ShellCommandParser<'a, I>
 where
     I: Iterator<Item = u8> + Clone
 {
-    pub fn new(iter: ShellInputIterator<I>) -> Self {
-        Self { iter }
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
     }
 
-    fn compare_next_chars(&mut self, to_compare: &[u8]) -> bool {
+    // The keyword has to be a whole word: "if" but not "ifconfig"
+    fn check_specific_keyword(&mut self, keyword: &[u8]) -> bool {
         let iter_state = self.iter.clone();
-        let matches = self.iter
-            .by_ref()
-            .take(to_compare.len())
-            .zip(to_compare)
-            .filter(|&(a, &b)| { a == b})
-            .count() == to_compare.len();
-        if matches {
-            true
-        } else {
-            self.iter = iter_state;
-            false
+        if self.iter.try_consume_string(keyword, true) {
+            return true;
         }
+        self.iter = iter_state;
+        false
     }
 
-    fn check_specific_keyword(&mut self, keyword: &[u8]) -> bool {
+    // Words that end the list of a compound command are not commands: fi, done, }
+    fn check_reserved_word(&mut self) -> bool {
+        const RESERVED_WORDS: [&[u8]; 8] = [b"}", b"then", b"elif", b"else", b"fi", b"do", b"done", b"esac"];
         let iter_state = self.iter.clone();
-        let mut iter = keyword.iter().peekable();
-        while self.iter.next_if(|c| { iter.peek() == Some(&c) }).is_some() {
-            iter.next();
-        }
-        if iter.peek().is_some() {
-            return true;
-        }
+        let result = RESERVED_WORDS.iter().any(|word| self.check_specific_keyword(word));
         self.iter = iter_state;
-        return false;
+        result
+    }
+
+    fn parse_compound_command(&mut self) -> ParseResult<ShellCompoundCommand> {
+        let mut compound_command_parser = ShellCompoundCommandParser::new(self.iter.clone(), self.rules);
+        let result = compound_command_parser.parse()?;
+        self.iter = compound_command_parser.iter;
+        Ok(result)
     }
 
-    fn check_compound_command_keyword(&mut self) -> ParseResult<&[u8]> {
-        const KEYWORDS: [&str; 6] = [
-            "for",
-            "select",
-            "case",
-            "if",
-            "while",
-            "until"
-        ];
-        for keyword in KEYWORDS {
-            if self.check_specific_keyword(keyword.as_bytes()) {
-                return Ok(Some(keyword.as_bytes()));
+    fn parse_redirections(&mut self) -> Result<Vec<ShellRedirection>, ParseError> {
+        let mut result = vec![];
+        loop {
+            self.iter.skip_whitespace();
+            let mut redirection_parser = ShellRedirectionParser::new(self.iter.clone(), self.rules);
+            let redirection = redirection_parser.parse()?;
+            self.iter = redirection_parser.iter;
+            match redirection {
+                Some(value) => result.push(value),
+                None => return Ok(result),
             }
-        };
-        Ok(None)
+        }
     }
 
     fn check_coproc_command_keyword(&mut self) -> bool {
@@ -94,20 +98,22 @@ where
     }
 
     fn check_function_parentheses(&mut self) -> bool {
-        self.skip_whitespace();
-        if let Some(b'(') = self.iter.peek() {
-            self.iter.next();
-            self.skip_whitespace();
-            if let Some(b')') = self.iter.peek() {
+        let iter_state = self.iter.clone();
+        self.iter.skip_whitespace();
+        if self.iter.next_if(|&c| c == b'(').is_some() {
+            self.iter.skip_whitespace();
+            if self.iter.next_if(|&c| c == b')').is_some() {
                 return true;
             }
         }
+        self.iter = iter_state;
         false
     }
 
     fn check_function_definition(&mut self) -> ParseResult<Vec<u8>> {
         let iter_state = self.iter.clone();
         if self.check_specific_keyword(b"function") {
+            self.iter.skip_whitespace();
             if let Some(function_name) = self.parse_function_name()? {
                 self.check_function_parentheses();
                 return Ok(Some(function_name));
@@ -128,15 +134,34 @@ where
         }
     }
 
+    // Called after the name and the parentheses, the body can start on another line
+    fn parse_function_definition(&mut self, name: Vec<u8>) -> Result<ShellFunctionDefinition, ParseError> {
+        self.skip_whitespace();
+        let body = match self.parse_compound_command()? {
+            Some(value) => value,
+            None if self.iter.peek().is_none() => return Err(ParseError::RequiresNextLine),
+            None => return Err(ParseError::IncorrectSyntax),
+        };
+        let redirections = self.parse_redirections()?;
+        Ok(ShellFunctionDefinition { name, body, redirections })
+    }
+
     pub fn parse(&mut self) -> ParseResult<ShellCommand> {
-        if let Some(keyword) = self.check_compound_command_keyword()? {
-            Ok(Some(ShellCommand::Compound()))
+        if self.check_reserved_word() {
+            Ok(None)
+        } else if let Some(command) = self.parse_compound_command()? {
+            let redirections = self.parse_redirections()?;
+            Ok(Some(ShellCommand::Compound(command, redirections)))
         } else if self.check_coproc_command_keyword() {
             Ok(Some(ShellCommand::Coproc()))
         } else if let Some(function_name) = self.check_function_definition()? {
-            Ok(Some(ShellCommand::FunctionDefinition()))
+            let definition = self.parse_function_definition(function_name)?;
+            Ok(Some(ShellCommand::FunctionDefinition(Rc::new(definition))))
         } else {
-            Ok(Some(ShellCommand::Simple()))
+            let mut simple_command_parser = ShellSimpleCommandParser::new(self.iter.clone(), self.rules);
+            let result = simple_command_parser.parse()?;
+            self.iter = simple_command_parser.iter;
+            Ok(result.map(ShellCommand::Simple))
         }
     }
 }
@@ -0,0 +1,176 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{get_default_word_parsing_rules, ParseError, ParseResult, ShellParsingRules};
+use crate::shell_parsers::shell_list_parser::ShellListParser;
+use crate::shell_parsers::shell_word_parser::ShellWordParser;
+use crate::shell_state::variables::is_valid_name;
+use crate::shell_structures::shell_command::shell_compound_command::{ShellCompoundCommand, ShellIfBranch};
+use crate::shell_structures::shell_list::ShellList;
+use crate::shell_structures::shell_word::ShellWord;
+
+pub struct ShellCompoundCommandParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
+}
+
+impl<'a, I> ShellCompoundCommandParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    fn check_keyword(&mut self, keyword: &[u8]) -> bool {
+        let iter_state = self.iter.clone();
+        if self.iter.try_consume_string(keyword, true) {
+            return true;
+        }
+        self.iter = iter_state;
+        false
+    }
+
+    fn skip_whitespace_and_newlines(&mut self) {
+        while self.iter.next_if(|&c| matches!(c, b' ' | b'\t' | b'\n')).is_some() {}
+    }
+
+    // Running out of input only means the command goes on on the next line
+    fn get_error(&mut self) -> ParseError {
+        match self.iter.peek() {
+            Some(_) => ParseError::IncorrectSyntax,
+            None => ParseError::RequiresNextLine,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &[u8]) -> Result<(), ParseError> {
+        self.skip_whitespace_and_newlines();
+        match self.check_keyword(keyword) {
+            true => Ok(()),
+            false => Err(self.get_error()),
+        }
+    }
+
+    // The lists inside compound commands cannot be empty
+    fn parse_list(&mut self) -> Result<ShellList, ParseError> {
+        let mut list_parser = ShellListParser::new(self.iter.clone(), self.rules);
+        let result = list_parser.parse()?;
+        self.iter = list_parser.iter;
+        match result {
+            Some(value) => Ok(value),
+            None => Err(self.get_error()),
+        }
+    }
+
+    fn parse_word(&mut self) -> ParseResult<ShellWord> {
+        let rules = get_default_word_parsing_rules(self.rules);
+        let mut word_parser = ShellWordParser::new(self.iter.clone(), &rules);
+        let result = word_parser.parse();
+        self.iter = word_parser.iter;
+        result
+    }
+
+    // Called right after "if"
+    fn parse_if(&mut self) -> Result<ShellCompoundCommand, ParseError> {
+        let mut branches = vec![];
+        loop {
+            let condition = self.parse_list()?;
+            self.expect_keyword(b"then")?;
+            let body = self.parse_list()?;
+            branches.push(ShellIfBranch { condition, body });
+            self.skip_whitespace_and_newlines();
+            if !self.check_keyword(b"elif") {
+                break;
+            }
+        }
+        let else_body = match self.check_keyword(b"else") {
+            true => Some(self.parse_list()?),
+            false => None,
+        };
+        self.expect_keyword(b"fi")?;
+        Ok(ShellCompoundCommand::If { branches, else_body })
+    }
+
+    fn parse_do_group(&mut self) -> Result<ShellList, ParseError> {
+        self.expect_keyword(b"do")?;
+        let body = self.parse_list()?;
+        self.expect_keyword(b"done")?;
+        Ok(body)
+    }
+
+    // Called right after "while" or "until"
+    fn parse_while(&mut self, is_until: bool) -> Result<ShellCompoundCommand, ParseError> {
+        let condition = self.parse_list()?;
+        let body = self.parse_do_group()?;
+        Ok(ShellCompoundCommand::While { condition, body, is_until })
+    }
+
+    // Called right after "for": for name [in word...]; do list; done
+    fn parse_for(&mut self) -> Result<ShellCompoundCommand, ParseError> {
+        self.iter.skip_whitespace();
+        let mut variable = vec![];
+        while let Some(next_char) = self.iter.next_if(|&c| c.is_ascii_alphanumeric() || c == b'_') {
+            variable.push(next_char);
+        }
+        if !is_valid_name(&variable) || !self.iter.check_word_end(b"") {
+            return Err(self.get_error());
+        }
+        self.skip_whitespace_and_newlines();
+        let words = match self.check_keyword(b"in") {
+            true => {
+                let mut words = vec![];
+                loop {
+                    self.iter.skip_whitespace();
+                    match self.parse_word()? {
+                        Some(word) => words.push(word),
+                        None => break,
+                    }
+                }
+                if self.iter.next_if(|&c| c == b';' || c == b'\n').is_none() {
+                    return Err(self.get_error());
+                }
+                Some(words)
+            },
+            false => {
+                self.iter.next_if(|&c| c == b';');
+                None
+            },
+        };
+        let body = self.parse_do_group()?;
+        Ok(ShellCompoundCommand::For { variable, words, body })
+    }
+
+    // None when the input does not start with a compound command; case and select are not supported
+    pub fn parse(&mut self) -> ParseResult<ShellCompoundCommand> {
+        if self.iter.next_if(|&c| c == b'(').is_some() {
+            let list = self.parse_list()?;
+            self.skip_whitespace_and_newlines();
+            return match self.iter.next_if(|&c| c == b')') {
+                Some(_) => Ok(Some(ShellCompoundCommand::Subshell(list))),
+                None => Err(self.get_error()),
+            };
+        }
+        if self.check_keyword(b"{") {
+            let list = self.parse_list()?;
+            self.expect_keyword(b"}")?;
+            return Ok(Some(ShellCompoundCommand::BraceGroup(list)));
+        }
+        if self.check_keyword(b"if") {
+            return self.parse_if().map(Some);
+        }
+        if self.check_keyword(b"while") {
+            return self.parse_while(false).map(Some);
+        }
+        if self.check_keyword(b"until") {
+            return self.parse_while(true).map(Some);
+        }
+        if self.check_keyword(b"for") {
+            return self.parse_for().map(Some);
+        }
+        if self.check_keyword(b"case") || self.check_keyword(b"select") {
+            return Err(ParseError::IncorrectSyntax);
+        }
+        Ok(None)
+    }
+}
@@ -0,0 +1,99 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{get_default_word_parsing_rules, ParseResult, ShellParsingRules};
+use crate::shell_parsers::shell_redirection_parser::ShellRedirectionParser;
+use crate::shell_parsers::shell_variable_assignment_parser::ShellVariableAssignmentParser;
+use crate::shell_parsers::shell_word_parser::ShellWordParser;
+use crate::shell_structures::shell_command::shell_simple_command::{ShellArgument, ShellSimpleCommand};
+use crate::shell_structures::shell_redirection::ShellRedirection;
+use crate::shell_structures::shell_variable_assignment::ShellVariableAssignment;
+use crate::shell_structures::shell_word::ShellWord;
+use crate::shell_structures::ShellToken;
+
+// Builtins whose arguments can be assignments
+const DECLARATION_COMMANDS: [&[u8]; 5] = [b"declare", b"typeset", b"local", b"export", b"readonly"];
+
+pub struct ShellSimpleCommandParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
+}
+
+impl<'a, I> ShellSimpleCommandParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    fn parse_assignment(&mut self) -> ParseResult<ShellVariableAssignment> {
+        let mut assignment_parser = ShellVariableAssignmentParser::new(self.iter.clone(), self.rules);
+        let result = assignment_parser.parse()?;
+        self.iter = assignment_parser.iter;
+        Ok(result)
+    }
+
+    fn parse_redirection(&mut self) -> ParseResult<ShellRedirection> {
+        let mut redirection_parser = ShellRedirectionParser::new(self.iter.clone(), self.rules);
+        let result = redirection_parser.parse()?;
+        self.iter = redirection_parser.iter;
+        Ok(result)
+    }
+
+    fn parse_word(&mut self) -> ParseResult<ShellWord> {
+        let rules = get_default_word_parsing_rules(self.rules);
+        let mut word_parser = ShellWordParser::new(self.iter.clone(), &rules);
+        let result = word_parser.parse();
+        self.iter = word_parser.iter;
+        result
+    }
+
+    // A '#' at the start of a word comments out the rest of the line, the newline is left in place
+    fn skip_comment(&mut self) -> bool {
+        if self.iter.peek() != Some(&b'#') {
+            return false;
+        }
+        while self.iter.next_if(|&c| c != b'\n').is_some() {}
+        true
+    }
+
+    // Stops before the first operator or newline, None when there is no command at all
+    pub fn parse(&mut self) -> ParseResult<ShellSimpleCommand> {
+        let mut result = ShellSimpleCommand::default();
+        let mut is_declaration = false;
+        loop {
+            self.iter.skip_whitespace();
+            if self.skip_comment() {
+                break;
+            }
+            if let Some(redirection) = self.parse_redirection()? {
+                result.redirections.push(redirection);
+                continue;
+            }
+            if result.words.is_empty() || is_declaration {
+                if let Some(assignment) = self.parse_assignment()? {
+                    match is_declaration {
+                        true => result.words.push(ShellArgument::Assignment(assignment)),
+                        false => result.assignments.push(assignment),
+                    }
+                    continue;
+                }
+            }
+            match self.parse_word()? {
+                Some(word) => {
+                    if result.words.is_empty() {
+                        is_declaration = DECLARATION_COMMANDS.contains(&word.restore_original().as_slice());
+                    }
+                    result.words.push(ShellArgument::Word(word));
+                },
+                None => break,
+            }
+        }
+        if result.assignments.is_empty() && result.words.is_empty() && result.redirections.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(result))
+    }
+}
@@ -0,0 +1,33 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{ParseError, ParseResult, ShellParsingRules};
+use crate::shell_parsers::shell_list_parser::ShellListParser;
+use crate::shell_structures::shell_list::ShellList;
+
+pub struct ShellInputParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
+}
+
+impl<'a, I> ShellInputParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    // The whole input has to be a list, None when there are no commands at all
+    pub fn parse(&mut self) -> ParseResult<ShellList> {
+        let mut list_parser = ShellListParser::new(self.iter.clone(), self.rules);
+        let result = list_parser.parse()?;
+        self.iter = list_parser.iter;
+        while self.iter.next_if(|&c| matches!(c, b' ' | b'\t' | b'\n')).is_some() {}
+        if self.iter.peek().is_some() {
+            return Err(ParseError::IncorrectSyntax);
+        }
+        Ok(result)
+    }
+}
@@ -0,0 +1,95 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{ParseError, ParseResult, ShellParsingRules};
+use crate::shell_parsers::shell_pipeline_parser::ShellPipelineParser;
+use crate::shell_structures::shell_list::{ShellAndOrList, ShellList, ShellListItem, ShellListOperator};
+use crate::shell_structures::shell_pipeline::ShellPipeline;
+
+pub struct ShellListParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
+}
+
+impl<'a, I> ShellListParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    fn parse_pipeline(&mut self) -> ParseResult<ShellPipeline> {
+        let mut pipeline_parser = ShellPipelineParser::new(self.iter.clone(), self.rules);
+        let result = pipeline_parser.parse()?;
+        self.iter = pipeline_parser.iter;
+        Ok(result)
+    }
+
+    // Lines with nothing but a comment are skipped as well
+    fn skip_whitespace_and_newlines(&mut self) {
+        loop {
+            while self.iter.next_if(|&c| matches!(c, b' ' | b'\t' | b'\n')).is_some() {}
+            if self.iter.next_if(|&c| c == b'#').is_none() {
+                return;
+            }
+            while self.iter.next_if(|&c| c != b'\n').is_some() {}
+        }
+    }
+
+    fn parse_list_operator(&mut self) -> Option<ShellListOperator> {
+        if self.iter.try_consume_string(b"&&", false) {
+            return Some(ShellListOperator::And);
+        }
+        if self.iter.try_consume_string(b"||", false) {
+            return Some(ShellListOperator::Or);
+        }
+        None
+    }
+
+    pub fn parse_and_or_list(&mut self) -> ParseResult<ShellAndOrList> {
+        let first = match self.parse_pipeline()? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let mut result = ShellAndOrList { first, rest: vec![] };
+        loop {
+            self.iter.skip_whitespace();
+            let operator = match self.parse_list_operator() {
+                Some(value) => value,
+                None => return Ok(Some(result)),
+            };
+            // The next pipeline can be on the following line: cmd &&
+            self.skip_whitespace_and_newlines();
+            match self.parse_pipeline()? {
+                Some(pipeline) => result.rest.push((operator, pipeline)),
+                None if self.iter.peek().is_none() => return Err(ParseError::RequiresNextLine),
+                None => return Err(ParseError::IncorrectSyntax),
+            }
+        }
+    }
+
+    // And-or lists terminated by ';', '&' or newlines, stops before anything that cannot start one
+    pub fn parse(&mut self) -> ParseResult<ShellList> {
+        let mut result = ShellList::default();
+        loop {
+            self.skip_whitespace_and_newlines();
+            let and_or_list = match self.parse_and_or_list()? {
+                Some(value) => value,
+                None => break,
+            };
+            self.iter.skip_whitespace();
+            let is_background = self.iter.next_if(|&c| c == b'&').is_some();
+            let is_terminated = is_background || self.iter.next_if(|&c| c == b';' || c == b'\n').is_some();
+            result.items.push(ShellListItem { and_or_list, is_background });
+            if !is_terminated {
+                break;
+            }
+        }
+        match result.items.is_empty() {
+            true => Ok(None),
+            false => Ok(Some(result)),
+        }
+    }
+}
@@ -0,0 +1,75 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{ParseError, ParseResult, ShellParsingRules};
+use crate::shell_parsers::shell_command_parser::ShellCommandParser;
+use crate::shell_structures::shell_command::ShellCommand;
+use crate::shell_structures::shell_pipeline::{ShellPipeline, ShellPipelineStage};
+
+pub struct ShellPipelineParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
+}
+
+impl<'a, I> ShellPipelineParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    fn parse_command(&mut self) -> ParseResult<ShellCommand> {
+        let mut command_parser = ShellCommandParser::new(self.iter.clone(), self.rules);
+        let result = command_parser.parse()?;
+        self.iter = command_parser.iter;
+        Ok(result)
+    }
+
+    // '|' or "|&" but not "||", Some(true) for "|&"
+    fn parse_pipe_operator(&mut self) -> Option<bool> {
+        let iter_state = self.iter.clone();
+        self.iter.next_if(|&c| c == b'|')?;
+        match self.iter.peek() {
+            Some(b'|') => {
+                self.iter = iter_state;
+                None
+            },
+            Some(b'&') => {
+                self.iter.next();
+                Some(true)
+            },
+            _ => Some(false),
+        }
+    }
+
+    pub fn parse(&mut self) -> ParseResult<ShellPipeline> {
+        let mut result = ShellPipeline::default();
+        self.iter.skip_whitespace();
+        let iter_state = self.iter.clone();
+        result.is_negated = self.iter.try_consume_string(b"!", true);
+        if !result.is_negated {
+            self.iter = iter_state;
+        }
+        loop {
+            self.iter.skip_whitespace();
+            let command = match self.parse_command()? {
+                Some(value) => value,
+                None if result.stages.is_empty() && !result.is_negated => return Ok(None),
+                None if self.iter.peek().is_none() && !result.stages.is_empty() => return Err(ParseError::RequiresNextLine),
+                None => return Err(ParseError::IncorrectSyntax),
+            };
+            self.iter.skip_whitespace();
+            match self.parse_pipe_operator() {
+                Some(is_stderr_piped) => result.stages.push(ShellPipelineStage { command, is_stderr_piped }),
+                None => {
+                    result.stages.push(ShellPipelineStage { command, is_stderr_piped: false });
+                    return Ok(Some(result));
+                },
+            }
+            // The next command can be on the following line: cmd |
+            while self.iter.next_if(|&c| matches!(c, b' ' | b'\t' | b'\n')).is_some() {}
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{get_default_word_parsing_rules, ParseError, ParseResult, ShellParsingRules};
+use crate::shell_expansions::quote_removal::remove_quotes;
+use crate::shell_parsers::shell_word_parser::quoted_expression_parser::QuotedExpressionParser;
+use crate::shell_parsers::shell_word_parser::ShellWordParser;
+use crate::shell_structures::shell_redirection::{HereDocument, RedirectionDescriptor, RedirectionOperator, ShellRedirection};
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+
+pub struct ShellRedirectionParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
+}
+
+impl<'a, I> ShellRedirectionParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    fn parse_variable_name(&mut self) -> Option<Vec<u8>> {
+        self.iter.next_if(|&c| c == b'{')?;
+        let mut result = vec![self.iter.next_if(|&c| c.is_ascii_alphabetic() || c == b'_')?];
+        while let Some(next_char) = self.iter.next_if(|&c| c.is_ascii_alphanumeric() || c == b'_') {
+            result.push(next_char);
+        }
+        self.iter.next_if(|&c| c == b'}')?;
+        Some(result)
+    }
+
+    // 2>file or {fd}>file, the descriptor has to be right before the operator
+    fn parse_descriptor(&mut self) -> Option<RedirectionDescriptor> {
+        if self.iter.peek() == Some(&b'{') {
+            return self.parse_variable_name().map(RedirectionDescriptor::Variable);
+        }
+        let mut digits = vec![];
+        while let Some(digit) = self.iter.next_if(u8::is_ascii_digit) {
+            digits.push(digit);
+        }
+        match digits.is_empty() {
+            true => Some(RedirectionDescriptor::Default),
+            false => String::from_utf8(digits).ok()?.parse().ok().map(RedirectionDescriptor::Number),
+        }
+    }
+
+    fn parse_operator(&mut self, descriptor: &RedirectionDescriptor) -> Option<RedirectionOperator> {
+        for operator in RedirectionOperator::ALL {
+            let is_and_operator = operator.get_text().starts_with(b"&");
+            if is_and_operator && *descriptor != RedirectionDescriptor::Default {
+                continue;
+            }
+            if self.iter.try_consume_string(operator.get_text(), false) {
+                return Some(operator);
+            }
+        }
+        None
+    }
+
+    fn parse_target(&mut self) -> ParseResult<ShellWord> {
+        let rules = get_default_word_parsing_rules(self.rules);
+        let mut word_parser = ShellWordParser::new(self.iter.clone(), &rules);
+        let result = word_parser.parse();
+        self.iter = word_parser.iter;
+        result
+    }
+
+    // The delimiter is the target without its quotes, quoting any part of it leaves the contents
+    // unexpanded; expansions in the contents are checked here and performed with the redirection
+    fn parse_here_document(&mut self, target: &ShellWord, is_stripping_tabs: bool) -> Result<HereDocument, ParseError> {
+        let delimiter = remove_quotes(target);
+        let is_quoted = target.parts.iter().any(|part| {
+            matches!(part, ShellExpression::QuoteExpression(_) | ShellExpression::EscapedLiteral(_))
+        });
+        let contents = self.iter.take_here_document(&delimiter, is_stripping_tabs).ok_or(ParseError::RequiresNextLine)?;
+        if !is_quoted {
+            let mut quoted_expression_parser = QuotedExpressionParser::new(ShellInputIterator::new(contents.iter().copied().peekable()));
+            quoted_expression_parser.parse_here_document()?;
+        }
+        Ok(HereDocument { delimiter, contents, is_quoted })
+    }
+
+    // None when the input does not start with a redirection, "2" in "echo 2 >file" is a word
+    pub fn parse(&mut self) -> ParseResult<ShellRedirection> {
+        let iter_state = self.iter.clone();
+        let operator_and_descriptor = self.parse_descriptor()
+            .and_then(|descriptor| self.parse_operator(&descriptor).map(|operator| (descriptor, operator)));
+        let (descriptor, operator) = match operator_and_descriptor {
+            Some(value) => value,
+            None => {
+                self.iter = iter_state;
+                return Ok(None);
+            },
+        };
+        self.iter.skip_whitespace();
+        let target = match self.parse_target()? {
+            Some(value) => value,
+            None => return Err(ParseError::IncorrectSyntax),
+        };
+        let here_document = match operator {
+            RedirectionOperator::HereDocument => Some(self.parse_here_document(&target, false)?),
+            RedirectionOperator::HereDocumentStrippingTabs => Some(self.parse_here_document(&target, true)?),
+            _ => None,
+        };
+        Ok(Some(ShellRedirection { descriptor, operator, target, here_document }))
+    }
+}
@@ -1,71 +1,125 @@
 use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
-use crate::shell_parser_base::ParseResult;
+use crate::shell_parser_base::{ParseError, ParseResult};
 use crate::shell_structures::shell_word::ShellWord;
-use crate::shell_structures::shell_variable_assignment::{ShellVariableAssignment, VariableAssignmentType};
+use crate::shell_structures::shell_variable_assignment::{ShellArrayElement, ShellVariableAssignment, VariableAssignmentType};
 
 use crate::shell_parsers::shell_word_parser::ShellWordParser;
-use crate::shell_parser_base::{get_variable_value_word_parsing_rules, ShellParsingRules};
+use crate::shell_parsers::shell_word_parser::subscript_parser::SubscriptParser;
+use crate::shell_parser_base::{get_default_word_parsing_rules, get_variable_value_word_parsing_rules, ShellParsingRules};
 
-pub struct ShellVariableAssignmentParser<I>
+pub struct ShellVariableAssignmentParser<'a, I>
 where
     I: Iterator<Item = u8> + Clone
 {
     pub iter: ShellInputIterator<I>,
+    rules: &'a ShellParsingRules,
 }
 
-impl<I> ShellVariableAssignmentParser<I>
+impl<'a, I> ShellVariableAssignmentParser<'a, I>
 where
     I: Iterator<Item = u8> + Clone
 {
-    pub fn new(iter: ShellInputIterator<I>) -> Self {
-        Self { iter }
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellParsingRules) -> Self {
+        Self { iter, rules }
     }
 
     fn parse_identifier(&mut self) -> ParseResult<Vec<u8>> {
-        let mut result = match self.iter.peek() {
-            Some(c) if c.is_ascii_alphabetic() => vec![],
-            _ => return Ok(None),
+        let mut result = match self.iter.next_if(|&c| c.is_ascii_alphabetic() || c == b'_') {
+            Some(value) => vec![value],
+            None => return Ok(None),
         };
-        while let Some(next_char) = self.iter.next_if(|&c| c.is_ascii_alphanumeric()) {
+        while let Some(next_char) = self.iter.next_if(|&c| c.is_ascii_alphanumeric() || c == b'_') {
             result.push(next_char);
         }
         Ok(Some(result))
     }
 
+    fn parse_subscript(&mut self) -> ParseResult<ShellWord> {
+        let mut subscript_parser = SubscriptParser::new(self.iter.clone());
+        let result = subscript_parser.parse()?;
+        self.iter = subscript_parser.iter;
+        Ok(result)
+    }
+
     fn parse_next_word(&mut self) -> ParseResult<ShellWord> {
-        let i_rules = ShellParsingRules { is_interactive: true };
-        let rules = get_variable_value_word_parsing_rules(&i_rules);
+        let rules = get_variable_value_word_parsing_rules(self.rules);
         let mut word_parser = ShellWordParser::new(self.iter.clone(), &rules);
         let result = word_parser.parse();
         self.iter = word_parser.iter;
         result
     }
 
-    fn parse_word_list(&mut self) -> ParseResult<Vec<ShellWord>> {
+    // Elements without a subscript are ordinary words: a{1,2} *.txt
+    fn parse_list_word(&mut self) -> ParseResult<ShellWord> {
+        let rules = get_default_word_parsing_rules(self.rules);
+        let mut word_parser = ShellWordParser::new(self.iter.clone(), &rules);
+        let result = word_parser.parse();
+        self.iter = word_parser.iter;
+        result
+    }
+
+    fn parse_array_element(&mut self) -> Result<ShellArrayElement, ParseError> {
+        let iter_state = self.iter.clone();
+        if self.iter.next_if(|&c| c == b'[').is_some() {
+            if let Some(subscript) = self.parse_subscript()? {
+                if self.iter.next_if(|&c| c == b'=').is_some() {
+                    let value = self.parse_next_word()?.unwrap_or_default();
+                    return Ok(ShellArrayElement { subscript: Some(subscript), value });
+                }
+            }
+            self.iter = iter_state;
+        }
+        match self.parse_list_word()? {
+            Some(value) => Ok(ShellArrayElement { subscript: None, value }),
+            None => Err(ParseError::IncorrectSyntax),
+        }
+    }
+
+    // Called right after '(', the list can span several lines
+    fn parse_word_list(&mut self) -> ParseResult<Vec<ShellArrayElement>> {
         let mut result = vec![];
-        self.iter.skip_whitespace();
-        while self.iter.peek() != Some(&b')') {
-            result.push(self.parse_next_word()?.unwrap());
-            self.iter.skip_whitespace();
+        loop {
+            while self.iter.next_if(|&c| matches!(c, b' ' | b'\t' | b'\n')).is_some() {}
+            match self.iter.peek() {
+                Some(b')') => {
+                    self.iter.next();
+                    return Ok(Some(result));
+                },
+                Some(_) => result.push(self.parse_array_element()?),
+                None => return Err(ParseError::RequiresNextLine),
+            }
         }
-        self.iter.next();
-        Ok(Some(result))
     }
 
     pub fn parse(&mut self) -> ParseResult<ShellVariableAssignment> {
+        let iter_state = self.iter.clone();
         let identifier = match self.parse_identifier()? {
             Some(value) => value,
             None => return Ok(None),
         };
-        match self.iter.peek() {
-            Some(b'=') => {},
-            _ => return Ok(None),
+        let subscript = match self.iter.next_if(|&c| c == b'[') {
+            Some(_) => match self.parse_subscript()? {
+                Some(value) => Some(value),
+                None => {
+                    self.iter = iter_state;
+                    return Ok(None);
+                },
+            },
+            None => None,
+        };
+        let is_append = self.iter.next_if(|&c| c == b'+').is_some();
+        if self.iter.next_if(|&c| c == b'=').is_none() {
+            self.iter = iter_state;
+            return Ok(None);
         }
-        self.iter.next();
         let value = match self.iter.peek() {
-            Some(b'(') => VariableAssignmentType::List(self.parse_word_list()?.unwrap()),
-            _ => VariableAssignmentType::Simple(self.parse_next_word()?.unwrap())
+            Some(b'(') if subscript.is_some() => return Err(ParseError::IncorrectSyntax), // arr[1]=(a b)
+            Some(b'(') => {
+                self.iter.next();
+                VariableAssignmentType::List(self.parse_word_list()?.unwrap())
+            },
+            _ => VariableAssignmentType::Simple(self.parse_next_word()?.unwrap_or_default()),
         };
-        Ok(Some(ShellVariableAssignment {identifier, value}))
+        Ok(Some(ShellVariableAssignment { identifier, subscript, is_append, value }))
     }
 }
@@ -1,6 +1,8 @@
 mod shell_brace_expansion_parser;
 mod shell_dollar_sign_expansion_parser;
-mod quoted_expression_parser;
+pub mod quoted_expression_parser;
+pub mod globbing_pattern_parser;
+pub mod subscript_parser;
 
 use shell_brace_expansion_parser::ShellBraceExpressionParser;
 use shell_dollar_sign_expansion_parser::ShellDollarSignExpansionParser;
@@ -13,6 +15,9 @@ use crate::shell_structures::shell_word::shell_brace_expansion::BraceExpansion;
 
 use crate::shell_parser_base::ShellWordParsingRules;
 use quoted_expression_parser::QuotedExpressionParser;
+use globbing_pattern_parser::GlobbingPatternParser;
+use crate::shell_structures::shell_word::globbing_pattern::GlobbingPattern;
+use crate::shell_structures::shell_word::globbing_pattern::extended_glob::ExtendedGlobKind;
 use crate::shell_structures::shell_word::quoted_expressions::QuoteExpression;
 
 pub struct ShellWordParser<'a, I>
@@ -33,11 +38,45 @@ where
 
     fn try_parse_dollar_sign_expansion(&mut self) -> ParseResult<ShellExpression> {
         match self.iter.peek() {
-            Some(b'(' | b'{' | b'$') => todo!(),
+            Some(b'\'') => {
+                self.iter.next();
+                self.try_parse_ansi_c_quote_expression()
+            },
+            Some(b'"') => {
+                self.iter.next();
+                self.try_parse_translatable_quote_expression()
+            },
+            _ if self.rules.parse_dollar_sign_expansions => {
+                let mut dollar_sign_expansion_parser = ShellDollarSignExpansionParser::new(self.iter.clone());
+                match dollar_sign_expansion_parser.parse()? {
+                    Some(expansion) => {
+                        self.iter = dollar_sign_expansion_parser.iter;
+                        Ok(Some(ShellExpression::DollarSignExpansion(expansion)))
+                    },
+                    None => Ok(Some(ShellExpression::Literal(b"$".to_vec()))),
+                }
+            },
             _ => Ok(Some(ShellExpression::Literal(b"$".to_vec())))
         }
     }
 
+    fn try_parse_ansi_c_quote_expression(&mut self) -> ParseResult<ShellExpression> {
+        let mut quoted_expression_parser = QuotedExpressionParser::new(self.iter.clone());
+        let result = quoted_expression_parser.parse_ansi_c()?.unwrap();
+        self.iter = quoted_expression_parser.iter;
+        Ok(Some(ShellExpression::QuoteExpression(result)))
+    }
+
+    fn try_parse_translatable_quote_expression(&mut self) -> ParseResult<ShellExpression> {
+        let mut quoted_expression_parser = QuotedExpressionParser::new(self.iter.clone());
+        let result = match quoted_expression_parser.parse_weak(b'\"')?.unwrap() {
+            QuoteExpression::Weak(expression) => QuoteExpression::Translatable(expression),
+            other => other,
+        };
+        self.iter = quoted_expression_parser.iter;
+        Ok(Some(ShellExpression::QuoteExpression(result)))
+    }
+
     fn try_parse_double_quote_expression(&mut self) -> ParseResult<ShellExpression> {
         let mut quoted_expression_parser = QuotedExpressionParser::new(self.iter.clone());
         let result = quoted_expression_parser.parse_weak(b'\"')?.unwrap();
@@ -53,7 +92,7 @@ where
     }
 
     fn try_parse_brace_expansion(&mut self) -> ParseResult<ShellExpression> {
-        let mut shell_brace_expression_parser = ShellBraceExpressionParser::new(self.iter.clone());
+        let mut shell_brace_expression_parser = ShellBraceExpressionParser::new(self.iter.clone(), self.rules);
         if let Some(result) = shell_brace_expression_parser.parse()? {
             self.iter = shell_brace_expression_parser.iter;
             Ok(Some(ShellExpression::BraceExpansion(result)))
@@ -62,6 +101,70 @@ where
         }
     }
 
+    fn parse_any_string_pattern(&mut self) -> ShellExpression {
+        let mut count = 1;
+        while self.iter.next_if(|&c| c == b'*').is_some() {
+            count += 1;
+        }
+        ShellExpression::GlobbingPattern(GlobbingPattern::AnyString(count))
+    }
+
+    fn try_parse_bracket_expression(&mut self) -> ParseResult<ShellExpression> {
+        let stop_characters = self.rules.additional_word_stop_characters.as_slice();
+        let mut globbing_pattern_parser = GlobbingPatternParser::new(self.iter.clone(), stop_characters);
+        if let Some(result) = globbing_pattern_parser.parse_bracket_expression()? {
+            self.iter = globbing_pattern_parser.iter;
+            Ok(Some(ShellExpression::GlobbingPattern(result)))
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn check_extended_glob_start(&mut self, next_char: u8) -> Option<ExtendedGlobKind> {
+        if !self.rules.parse_filename_expansions || !self.rules.parse_extended_globs {
+            return None;
+        }
+        match self.iter.peek() {
+            Some(b'(') => ExtendedGlobKind::from_char(next_char),
+            _ => None,
+        }
+    }
+
+    fn try_parse_extended_glob(&mut self, kind: ExtendedGlobKind) -> ParseResult<ShellExpression> {
+        let stop_characters = self.rules.additional_word_stop_characters.as_slice();
+        let mut globbing_pattern_parser = GlobbingPatternParser::new(self.iter.clone(), stop_characters);
+        let result = globbing_pattern_parser.parse_extended_glob(kind, self.rules)?;
+        self.iter = globbing_pattern_parser.iter;
+        Ok(result.map(ShellExpression::GlobbingPattern))
+    }
+
+    fn check_tilde_prefix_start(&self, parts: &[ShellExpression], current_literal: &[u8]) -> bool {
+        if current_literal.is_empty() && parts.is_empty() {
+            self.rules.parse_tilde_expansions
+        } else {
+            self.rules.parse_tilde_expansions_after_colons && current_literal.last() == Some(&b':')
+        }
+    }
+
+    // Called right after '~', the prefix lasts until the first unquoted slash (or colon in assignments)
+    fn try_parse_tilde_prefix(&mut self) -> Option<ShellExpression> {
+        let iter_state = self.iter.clone();
+        let mut login_name = vec![];
+        let mut stop_characters = self.rules.additional_word_stop_characters.clone();
+        stop_characters.push(b'/');
+        if self.rules.parse_tilde_expansions_after_colons {
+            stop_characters.push(b':');
+        }
+        while let Some(next_char) = self.iter.next_in_word(&stop_characters) {
+            if !next_char.is_ascii_alphanumeric() && !matches!(next_char, b'.' | b'_' | b'-' | b'+') {
+                self.iter = iter_state;
+                return None;
+            }
+            login_name.push(next_char);
+        }
+        Some(ShellExpression::TildePrefix(login_name))
+    }
+
     pub fn parse(&mut self) -> ParseResult<ShellWord> {
         let mut result = ShellWord {
             parts: vec![],
@@ -91,8 +194,43 @@ where
                 result.parts.push(self.try_parse_double_quote_expression()?.unwrap())
             }
             else if next_char == b'{' && self.rules.parse_brace_expansions {
+                match self.try_parse_brace_expansion()? {
+                    Some(expression) => {
+                        result.parts.push(ShellExpression::Literal(std::mem::take(&mut current_literal)));
+                        result.parts.push(expression);
+                    },
+                    None => current_literal.push(next_char),
+                }
+            }
+            else if next_char == b'~' && self.check_tilde_prefix_start(&result.parts, &current_literal) {
+                match self.try_parse_tilde_prefix() {
+                    Some(expression) => {
+                        result.parts.push(ShellExpression::Literal(std::mem::take(&mut current_literal)));
+                        result.parts.push(expression);
+                    },
+                    None => current_literal.push(next_char),
+                }
+            }
+            else if let Some(kind) = self.check_extended_glob_start(next_char) {
+                result.parts.push(ShellExpression::Literal(std::mem::take(&mut current_literal)));
+                result.parts.push(self.try_parse_extended_glob(kind)?.unwrap());
+            }
+            else if next_char == b'*' && self.rules.parse_filename_expansions {
                 result.parts.push(ShellExpression::Literal(std::mem::take(&mut current_literal)));
-                result.parts.push(self.try_parse_brace_expansion()?.unwrap());
+                result.parts.push(self.parse_any_string_pattern());
+            }
+            else if next_char == b'?' && self.rules.parse_filename_expansions {
+                result.parts.push(ShellExpression::Literal(std::mem::take(&mut current_literal)));
+                result.parts.push(ShellExpression::GlobbingPattern(GlobbingPattern::AnySingleCharacter));
+            }
+            else if next_char == b'[' && self.rules.parse_filename_expansions {
+                match self.try_parse_bracket_expression()? {
+                    Some(expression) => {
+                        result.parts.push(ShellExpression::Literal(std::mem::take(&mut current_literal)));
+                        result.parts.push(expression);
+                    },
+                    None => current_literal.push(next_char),
+                }
             }
             else {
                 current_literal.push(next_char);
@@ -0,0 +1,156 @@
+use crate::shell_parser_base::{ParseError, ParseResult, ShellWordParsingRules};
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+
+use super::ShellWordParser;
+use crate::shell_structures::shell_word::globbing_pattern::GlobbingPattern;
+use crate::shell_structures::shell_word::globbing_pattern::bracket_expression::*;
+use crate::shell_structures::shell_word::globbing_pattern::extended_glob::{ExtendedGlob, ExtendedGlobKind};
+
+pub struct GlobbingPatternParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+    stop_characters: &'a [u8],
+}
+
+impl<'a, I> GlobbingPatternParser<'a, I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>, stop_characters: &'a [u8]) -> Self {
+        Self { iter, stop_characters }
+    }
+
+    // Reads a whole UTF-8 sequence, invalid bytes are taken as is
+    fn next_char(&mut self) -> Option<char> {
+        let first_byte = self.iter.next_in_word(self.stop_characters)?;
+        let length = match first_byte {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => return Some(char::from(first_byte)),
+        };
+        let iter_state = self.iter.clone();
+        let mut bytes = vec![first_byte];
+        while bytes.len() < length {
+            match self.iter.next_if(|&c| c & 0xC0 == 0x80) {
+                Some(c) => bytes.push(c),
+                None => break,
+            }
+        }
+        match std::str::from_utf8(&bytes) {
+            Ok(value) => value.chars().next(),
+            Err(_) => {
+                self.iter = iter_state;
+                Some(char::from(first_byte))
+            },
+        }
+    }
+
+    fn parse_delimited_name(&mut self, delimiter: u8) -> ParseResult<String> {
+        let mut result = vec![];
+        loop {
+            if self.iter.try_consume_string(&[delimiter, b']'], false) {
+                return Ok(String::from_utf8(result).ok());
+            }
+            match self.iter.next_in_word(self.stop_characters) {
+                Some(c) => result.push(c),
+                None => return Ok(None),
+            }
+        }
+    }
+
+    // Called right after the opening "[[" with the kind character (':', '=' or '.') still unread
+    fn parse_bracketed_item(&mut self) -> ParseResult<BracketExpressionItem> {
+        let delimiter = match self.iter.next() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let name = match self.parse_delimited_name(delimiter)? {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let item = match delimiter {
+            b':' => CharacterClass::from_name(name.as_bytes()).map(BracketExpressionItem::Class),
+            b'=' => get_collating_symbol(&name).map(BracketExpressionItem::EquivalenceClass),
+            _ => get_collating_symbol(&name).map(BracketExpressionItem::CollatingSymbol),
+        };
+        Ok(item)
+    }
+
+    fn parse_member_char(&mut self) -> Option<char> {
+        match self.next_char()? {
+            '\\' => self.next_char(),
+            '\'' | '"' => None, // quoted members are not supported, the bracket is taken literally
+            c => Some(c),
+        }
+    }
+
+    fn try_parse_range_end(&mut self) -> Option<char> {
+        let iter_state = self.iter.clone();
+        if self.iter.next_if(|&c| c == b'-').is_some() && self.iter.peek() != Some(&b']') {
+            if let Some(end) = self.parse_member_char() {
+                return Some(end);
+            }
+        }
+        self.iter = iter_state;
+        None
+    }
+
+    // Called right after the opening '['
+    pub fn parse_bracket_expression(&mut self) -> ParseResult<GlobbingPattern> {
+        let is_negated = self.iter.next_if(|&c| c == b'!' || c == b'^').is_some();
+        let mut items = vec![];
+        let mut is_first = true;
+        loop {
+            if self.iter.peek() == Some(&b']') && !is_first {
+                self.iter.next();
+                break;
+            }
+            if self.iter.peek() == Some(&b'[') {
+                let iter_state = self.iter.clone();
+                self.iter.next();
+                if matches!(self.iter.peek(), Some(b':' | b'=' | b'.')) {
+                    match self.parse_bracketed_item()? {
+                        Some(item) => items.push(item),
+                        None => return Ok(None),
+                    }
+                    is_first = false;
+                    continue;
+                }
+                self.iter = iter_state;
+            }
+            let c = match self.parse_member_char() {
+                Some(value) => value,
+                None => return Ok(None),
+            };
+            match self.try_parse_range_end() {
+                Some(end) => items.push(BracketExpressionItem::Range(c, end)),
+                None => items.push(BracketExpressionItem::Character(c)),
+            }
+            is_first = false;
+        }
+        Ok(Some(GlobbingPattern::SpecificCharacter(BracketExpression { is_negated, items })))
+    }
+
+    // Called right after the kind character with the opening '(' still unread
+    pub fn parse_extended_glob(&mut self, kind: ExtendedGlobKind, rules: &ShellWordParsingRules) -> ParseResult<GlobbingPattern> {
+        if self.iter.next_if(|&c| c == b'(').is_none() {
+            return Ok(None);
+        }
+        let mut alternatives = vec![];
+        loop {
+            let mut word_parser = ShellWordParser::new(self.iter.clone(), rules);
+            alternatives.push(word_parser.parse()?.unwrap_or_default());
+            self.iter = word_parser.iter;
+            match self.iter.next() {
+                Some(b'|') => {},
+                Some(b')') => break,
+                Some(_) => return Err(ParseError::IncorrectSyntax),
+                None => return Err(ParseError::RequiresNextLine),
+            }
+        }
+        Ok(Some(GlobbingPattern::Extended(ExtendedGlob { kind, alternatives })))
+    }
+}
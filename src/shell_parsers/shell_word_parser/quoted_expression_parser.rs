@@ -1,6 +1,7 @@
 use crate::shell_parser_base::{ParseError, ParseResult};
 use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
 
+use super::shell_dollar_sign_expansion_parser::ShellDollarSignExpansionParser;
 
 use crate::shell_structures::shell_word::quoted_expressions::*;
 
@@ -19,10 +20,15 @@ where
         Self { iter }
     }
 
+    // $'...' and $"..." are not special inside double quotes
     fn try_parse_dollar_sign_expansion(&mut self) -> ParseResult<WeakQuoteExpressionPart> {
-        match self.iter.peek() {
-            Some(b'(' | b'{' | b'$') => todo!(),
-            _ => Ok(Some(WeakQuoteExpressionPart::Literal(b"$".to_vec())))
+        let mut dollar_sign_expansion_parser = ShellDollarSignExpansionParser::new(self.iter.clone());
+        match dollar_sign_expansion_parser.parse()? {
+            Some(expansion) => {
+                self.iter = dollar_sign_expansion_parser.iter;
+                Ok(Some(WeakQuoteExpressionPart::DollarSignExpansion(Box::new(expansion))))
+            },
+            None => Ok(Some(WeakQuoteExpressionPart::Literal(b"$".to_vec()))),
         }
     }
 
@@ -37,7 +43,103 @@ where
         Err(ParseError::RequiresNextLine)
     }
 
+    fn parse_escaped_number(&mut self, radix: u32, max_digits: usize) -> Option<u32> {
+        let mut digits = String::new();
+        while digits.len() < max_digits {
+            match self.iter.next_if(|&c| (c as char).is_digit(radix)) {
+                Some(c) => digits.push(c as char),
+                None => break,
+            }
+        }
+        u32::from_str_radix(&digits, radix).ok()
+    }
+
+    fn push_unicode_escape(result: &mut Vec<u8>, code_point: Option<u32>, original: &[u8]) {
+        match code_point.and_then(char::from_u32) {
+            Some(c) => result.extend(c.to_string().as_bytes()),
+            None => result.extend(original),
+        }
+    }
+
+    // Called right after the backslash
+    fn parse_ansi_c_escape(&mut self, result: &mut Vec<u8>) -> Result<(), ParseError> {
+        let next_char = match self.iter.next() {
+            Some(value) => value,
+            None => return Err(ParseError::RequiresNextLine),
+        };
+        match next_char {
+            b'a' => result.push(0x07),
+            b'b' => result.push(0x08),
+            b'e' | b'E' => result.push(0x1B),
+            b'f' => result.push(0x0C),
+            b'n' => result.push(b'\n'),
+            b'r' => result.push(b'\r'),
+            b't' => result.push(b'\t'),
+            b'v' => result.push(0x0B),
+            b'\\' | b'\'' | b'"' | b'?' => result.push(next_char),
+            b'0'..=b'7' => {
+                let mut value = (next_char - b'0') as u32;
+                for _ in 0..2 {
+                    match self.iter.next_if(|c| (b'0'..=b'7').contains(c)) {
+                        Some(digit) => value = value * 8 + (digit - b'0') as u32,
+                        None => break,
+                    }
+                }
+                result.push(value as u8);
+            },
+            b'x' => match self.parse_escaped_number(16, 2) {
+                Some(value) => result.push(value as u8),
+                None => result.extend(b"\\x"),
+            },
+            b'u' => {
+                let code_point = self.parse_escaped_number(16, 4);
+                Self::push_unicode_escape(result, code_point, b"\\u");
+            },
+            b'U' => {
+                let code_point = self.parse_escaped_number(16, 8);
+                Self::push_unicode_escape(result, code_point, b"\\U");
+            },
+            b'c' => match self.iter.next() {
+                Some(b'?') => result.push(0x7F),
+                Some(b'\\') => {
+                    // \c\\ is the same as \c\
+                    self.iter.next_if(|&c| c == b'\\');
+                    result.push(0x1C);
+                },
+                Some(control_char) => result.push(control_char & 0x1F),
+                None => return Err(ParseError::RequiresNextLine),
+            },
+            _ => {
+                result.push(b'\\');
+                result.push(next_char);
+            },
+        }
+        Ok(())
+    }
+
+    // Called right after $'
+    pub fn parse_ansi_c(&mut self) -> ParseResult<QuoteExpression> {
+        let mut result: Vec<u8> = vec![];
+        while let Some(next_char) = self.iter.next() {
+            match next_char {
+                b'\'' => return Ok(Some(QuoteExpression::AnsiC(AnsiCQuoteExpression {contents: result}))),
+                b'\\' => self.parse_ansi_c_escape(&mut result)?,
+                _ => result.push(next_char),
+            }
+        }
+        Err(ParseError::RequiresNextLine)
+    }
+
     pub fn parse_weak(&mut self, end_char: u8) -> ParseResult<QuoteExpression> {
+        self.parse_weak_until(Some(end_char))
+    }
+
+    // Contents of a here-document, up to the end of the input: as in double quotes, but " is not special
+    pub fn parse_here_document(&mut self) -> ParseResult<QuoteExpression> {
+        self.parse_weak_until(None)
+    }
+
+    fn parse_weak_until(&mut self, end_char: Option<u8>) -> ParseResult<QuoteExpression> {
         let mut result = WeakQuoteExpression {parts: vec![]};
         let mut current_literal: Vec<u8> = vec![];
         while let Some(next_char) = self.iter.next() {
@@ -45,7 +147,7 @@ where
                 if let Some(next_char) = self.iter.next() {
                     match next_char {
                         b'\\' | b'$'  => current_literal.push(next_char),
-                        _ if next_char == end_char => current_literal.push(next_char),
+                        _ if Some(next_char) == end_char => current_literal.push(next_char),
                         b'\n' => {},
                         _ => {
                             current_literal.push(b'\\');
@@ -61,7 +163,7 @@ where
                 result.parts.push(WeakQuoteExpressionPart::Literal(std::mem::take(&mut current_literal)));
                 result.parts.push(self.try_parse_dollar_sign_expansion()?.unwrap());
             }
-            else if next_char == end_char {
+            else if Some(next_char) == end_char {
                 result.parts.push(WeakQuoteExpressionPart::Literal(std::mem::take(&mut current_literal)));
                 return Ok(Some(QuoteExpression::Weak(result)));
             }
@@ -69,6 +171,10 @@ where
                 current_literal.push(next_char);
             }
         }
+        if end_char.is_none() {
+            result.parts.push(WeakQuoteExpressionPart::Literal(current_literal));
+            return Ok(Some(QuoteExpression::Weak(result)));
+        }
         Err(ParseError::RequiresNextLine)
     }
 }
@@ -1,24 +1,52 @@
-use crate::shell_parser_base::ParseResult;
+use crate::shell_parser_base::{ParseError, ParseResult, ShellWordParsingRules};
 use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parsers::shell_word_parser::ShellWordParser;
 
 pub mod shell_range_parser;
 
 use shell_range_parser::ShellRangeParser;
 use crate::shell_structures::shell_word::shell_brace_expansion::BraceExpansion;
+use crate::shell_structures::shell_word::shell_brace_expansion::shell_brace_list::ShellBraceList;
 
-pub struct ShellBraceExpressionParser<I>
+pub struct ShellBraceExpressionParser<'a, I>
 where
     I: Iterator<Item = u8> + Clone
 {
-    pub iter: ShellInputIterator<I>
+    pub iter: ShellInputIterator<I>,
+    rules: &'a ShellWordParsingRules,
 }
 
-impl<I> ShellBraceExpressionParser<I>
+impl<'a, I> ShellBraceExpressionParser<'a, I>
 where
     I: Iterator<Item = u8> + Clone
 {
-    pub fn new(iter: ShellInputIterator<I>) -> Self {
-        Self { iter }
+    pub fn new(iter: ShellInputIterator<I>, rules: &'a ShellWordParsingRules) -> Self {
+        Self { iter, rules }
+    }
+
+    // {a,b,c}: at least one unquoted comma is needed, elements can be empty and contain nested braces
+    fn parse_list(&mut self) -> ParseResult<BraceExpansion> {
+        let iter_state = self.iter.clone();
+        let mut element_rules = self.rules.clone();
+        element_rules.additional_word_stop_characters.extend([b',', b'}']);
+        let mut words = vec![];
+        loop {
+            let mut word_parser = ShellWordParser::new(self.iter.clone(), &element_rules);
+            let word = match word_parser.parse() {
+                Ok(value) => value.unwrap_or_default(),
+                Err(ParseError::IncorrectSyntax) => break,
+                Err(err) => return Err(err),
+            };
+            self.iter = word_parser.iter;
+            words.push(word);
+            match self.iter.next_if(|&c| c == b',' || c == b'}') {
+                Some(b',') => continue,
+                Some(_) if words.len() > 1 => return Ok(Some(BraceExpansion::List(ShellBraceList { words }))),
+                _ => break,
+            }
+        }
+        self.iter = iter_state;
+        Ok(None)
     }
 
     pub fn parse(&mut self) -> ParseResult<BraceExpansion> {
@@ -27,6 +55,6 @@ where
             self.iter = shell_range_parser.iter;
             return Ok(Some(BraceExpansion::Range(shell_range)));
         }
-        Ok(None)
+        self.parse_list()
     }
 }
@@ -1,7 +1,13 @@
-use crate::shell_parser_base::ParseResult;
+use crate::shell_parser_base::{ParseError, ParseResult};
 use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
 
 use crate::shell_structures::shell_word::shell_dollar_sign_expansion::DollarSignExpansion;
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::parameter_expansion::*;
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::command_expansion::CommandExpansion;
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::arithmetic_expansion::ArithmeticExpansion;
+use crate::shell_structures::shell_word::quoted_expressions::WeakQuoteExpressionPart;
+
+use super::subscript_parser::SubscriptParser;
 
 pub struct ShellDollarSignExpansionParser<I>
 where
@@ -18,8 +24,216 @@ where
         Self { iter }
     }
 
+    fn parse_name(&mut self) -> Option<Vec<u8>> {
+        let mut result = match self.iter.next_if(|&c| c.is_ascii_alphabetic() || c == b'_') {
+            Some(value) => vec![value],
+            None => return None,
+        };
+        while let Some(next_char) = self.iter.next_if(|&c| c.is_ascii_alphanumeric() || c == b'_') {
+            result.push(next_char);
+        }
+        Some(result)
+    }
+
+    // Without braces only a single digit is taken: $10 is ${1}0
+    fn parse_positional(&mut self, is_braced: bool) -> Option<Parameter> {
+        let mut digits = vec![self.iter.next_if(|c| c.is_ascii_digit())?];
+        if is_braced {
+            while let Some(digit) = self.iter.next_if(|c| c.is_ascii_digit()) {
+                digits.push(digit);
+            }
+        }
+        if digits.iter().all(|&c| c == b'0') {
+            return Some(Parameter::Special(SpecialParameter::ShellName));
+        }
+        String::from_utf8(digits).ok()?.parse().ok().map(Parameter::Positional)
+    }
+
+    fn parse_parameter(&mut self, is_braced: bool) -> Option<Parameter> {
+        let next_char = *self.iter.peek()?;
+        if next_char.is_ascii_alphabetic() || next_char == b'_' {
+            return self.parse_name().map(Parameter::Named);
+        }
+        if next_char.is_ascii_digit() {
+            return self.parse_positional(is_braced);
+        }
+        let special = SpecialParameter::from_char(next_char)?;
+        self.iter.next();
+        Some(Parameter::Special(special))
+    }
+
+    // Called right after '[' inside braces
+    fn parse_subscript(&mut self) -> Result<ArraySubscript, ParseError> {
+        if self.iter.try_consume_string(b"@]", false) {
+            return Ok(ArraySubscript::AllFields);
+        }
+        if self.iter.try_consume_string(b"*]", false) {
+            return Ok(ArraySubscript::AllJoined);
+        }
+        let mut subscript_parser = SubscriptParser::new(self.iter.clone());
+        let result = subscript_parser.parse()?.ok_or(ParseError::RequiresNextLine)?;
+        self.iter = subscript_parser.iter;
+        Ok(ArraySubscript::Expression(result))
+    }
+
+    fn parse_braced_parameter(&mut self) -> Result<Option<Parameter>, ParseError> {
+        match self.parse_parameter(true) {
+            Some(Parameter::Named(name)) if self.iter.next_if(|&c| c == b'[').is_some() => {
+                Ok(Some(Parameter::Element(name, self.parse_subscript()?)))
+            },
+            parameter => Ok(parameter),
+        }
+    }
+
+    // Called right after "${!", only the keys of arrays are supported: ${!name[@]}
+    fn parse_keys(&mut self) -> ParseResult<ParameterExpansion> {
+        let name = match self.parse_name() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let is_joined = match self.iter.next_if(|&c| c == b'[').map(|_| self.parse_subscript()).transpose()? {
+            Some(ArraySubscript::AllFields) => false,
+            Some(ArraySubscript::AllJoined) => true,
+            _ => return Err(ParseError::IncorrectSyntax),
+        };
+        self.expect_closing_brace()?;
+        Ok(Some(ParameterExpansion::Keys(name, is_joined)))
+    }
+
+    fn expect_closing_brace(&mut self) -> Result<(), ParseError> {
+        match self.iter.next() {
+            Some(b'}') => Ok(()),
+            Some(_) => Err(ParseError::IncorrectSyntax), // bad substitution
+            None => Err(ParseError::RequiresNextLine),
+        }
+    }
+
+    // Called right after "${"
+    fn parse_braced(&mut self) -> ParseResult<ParameterExpansion> {
+        let iter_state = self.iter.clone();
+        if self.iter.next_if(|&c| c == b'#').is_some() && self.iter.peek() != Some(&b'}') {
+            let parameter = self.parse_braced_parameter()?.ok_or(ParseError::IncorrectSyntax)?;
+            self.expect_closing_brace()?;
+            return Ok(Some(ParameterExpansion::Length(parameter)));
+        }
+        self.iter = iter_state.clone();
+        if self.iter.next_if(|&c| c == b'!').is_some() && self.iter.peek() != Some(&b'}') {
+            return self.parse_keys()?.map(Some).ok_or(ParseError::IncorrectSyntax);
+        }
+        self.iter = iter_state;
+        let parameter = match self.parse_braced_parameter()? {
+            Some(value) => value,
+            None if self.iter.peek().is_none() => return Err(ParseError::RequiresNextLine),
+            None => return Err(ParseError::IncorrectSyntax),
+        };
+        self.expect_closing_brace()?;
+        Ok(Some(ParameterExpansion::Braced(parameter)))
+    }
+
+    // Called right after "$((", fails with IncorrectSyntax when the parentheses are not closed by "))",
+    // "$( (cmd) )" is then a command substitution
+    fn parse_arithmetic(&mut self) -> ParseResult<ArithmeticExpansion> {
+        let mut parts = vec![];
+        let mut current_literal = vec![];
+        let mut depth = 0usize;
+        loop {
+            match self.iter.next() {
+                None => return Err(ParseError::RequiresNextLine),
+                Some(b'$') => {
+                    let mut dollar_sign_expansion_parser = ShellDollarSignExpansionParser::new(self.iter.clone());
+                    match dollar_sign_expansion_parser.parse()? {
+                        Some(expansion) => {
+                            self.iter = dollar_sign_expansion_parser.iter;
+                            parts.push(WeakQuoteExpressionPart::Literal(std::mem::take(&mut current_literal)));
+                            parts.push(WeakQuoteExpressionPart::DollarSignExpansion(Box::new(expansion)));
+                        },
+                        None => current_literal.push(b'$'),
+                    }
+                },
+                Some(b'(') => {
+                    depth += 1;
+                    current_literal.push(b'(');
+                },
+                Some(b')') if depth == 0 => {
+                    if self.iter.next_if(|&c| c == b')').is_none() {
+                        return Err(ParseError::IncorrectSyntax);
+                    }
+                    parts.push(WeakQuoteExpressionPart::Literal(current_literal));
+                    return Ok(Some(ArithmeticExpansion { parts }));
+                },
+                Some(b')') => {
+                    depth -= 1;
+                    current_literal.push(b')');
+                },
+                Some(next_char) => current_literal.push(next_char),
+            }
+        }
+    }
+
+    fn push_quoted(&mut self, command: &mut Vec<u8>, quote_char: u8) -> Result<(), ParseError> {
+        loop {
+            let next_char = self.iter.next().ok_or(ParseError::RequiresNextLine)?;
+            command.push(next_char);
+            if next_char == quote_char {
+                return Ok(());
+            }
+            if next_char == b'\\' && quote_char == b'"' {
+                command.push(self.iter.next().ok_or(ParseError::RequiresNextLine)?);
+            }
+        }
+    }
+
+    // Called right after "$(", the command lasts until the matching parenthesis outside of quotes;
+    // unbalanced parentheses of case patterns are not recognized
+    fn parse_command(&mut self) -> ParseResult<CommandExpansion> {
+        let mut command = vec![];
+        let mut depth = 0usize;
+        loop {
+            let next_char = self.iter.next().ok_or(ParseError::RequiresNextLine)?;
+            match next_char {
+                b')' if depth == 0 => return Ok(Some(CommandExpansion { command })),
+                b')' => depth -= 1,
+                b'(' => depth += 1,
+                b'\\' => {
+                    command.push(next_char);
+                    command.push(self.iter.next().ok_or(ParseError::RequiresNextLine)?);
+                    continue;
+                },
+                b'\'' | b'"' => {
+                    command.push(next_char);
+                    self.push_quoted(&mut command, next_char)?;
+                    continue;
+                },
+                _ => {},
+            }
+            command.push(next_char);
+        }
+    }
+
+    // Called right after '$', None means the dollar sign is a literal one
     pub fn parse(&mut self) -> ParseResult<DollarSignExpansion> {
-        todo!();
-        Ok(None)
+        match self.iter.peek() {
+            Some(b'{') => {
+                self.iter.next();
+                Ok(self.parse_braced()?.map(DollarSignExpansion::Parameter))
+            },
+            Some(b'(') => {
+                self.iter.next();
+                if self.iter.peek() == Some(&b'(') {
+                    let iter_state = self.iter.clone();
+                    self.iter.next();
+                    match self.parse_arithmetic() {
+                        Ok(expansion) => return Ok(expansion.map(DollarSignExpansion::Arithmetic)),
+                        Err(ParseError::IncorrectSyntax) => self.iter = iter_state,
+                        Err(err) => return Err(err),
+                    }
+                }
+                Ok(self.parse_command()?.map(DollarSignExpansion::Command))
+            },
+            _ => {
+                let expansion = self.parse_parameter(false).map(ParameterExpansion::Simple);
+                Ok(expansion.map(DollarSignExpansion::Parameter))
+            },
+        }
     }
 }
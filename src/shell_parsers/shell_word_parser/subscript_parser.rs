@@ -0,0 +1,87 @@
+use crate::shell_input_iterator::{ShellInputIterator, ShellInputIteratorExt};
+use crate::shell_parser_base::{get_subscript_word_parsing_rules, ParseResult};
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+
+use super::ShellWordParser;
+
+pub struct SubscriptParser<I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub iter: ShellInputIterator<I>,
+}
+
+impl<I> SubscriptParser<I>
+where
+    I: Iterator<Item = u8> + Clone
+{
+    pub fn new(iter: ShellInputIterator<I>) -> Self {
+        Self { iter }
+    }
+
+    fn push_quoted(&mut self, subscript: &mut Vec<u8>, quote_char: u8) -> Option<()> {
+        loop {
+            let next_char = self.iter.next()?;
+            subscript.push(next_char);
+            if next_char == quote_char {
+                return Some(());
+            }
+            if next_char == b'\\' && quote_char == b'"' {
+                subscript.push(self.iter.next()?);
+            }
+        }
+    }
+
+    // The subscript lasts until the matching bracket outside of quotes and can contain blanks:
+    // assoc[key with spaces]=x
+    fn parse_raw(&mut self) -> Option<Vec<u8>> {
+        let mut result = vec![];
+        let mut depth = 0usize;
+        loop {
+            let next_char = self.iter.next_if(|&c| c != b'\n')?;
+            match next_char {
+                b']' if depth == 0 => return Some(result),
+                b']' => depth -= 1,
+                b'[' => depth += 1,
+                b'\\' => {
+                    result.push(next_char);
+                    result.push(self.iter.next()?);
+                    continue;
+                },
+                b'\'' | b'"' => {
+                    result.push(next_char);
+                    self.push_quoted(&mut result, next_char)?;
+                    continue;
+                },
+                _ => {},
+            }
+            result.push(next_char);
+        }
+    }
+
+    // Called right after '[', None when the line ends before the closing bracket;
+    // blanks and operator characters between the words of the subscript are kept as literals
+    pub fn parse(&mut self) -> ParseResult<ShellWord> {
+        let raw_subscript = match self.parse_raw() {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        let rules = get_subscript_word_parsing_rules();
+        let mut iter = ShellInputIterator::new(raw_subscript.into_iter().peekable());
+        let mut result = ShellWord::default();
+        while let Some(&next_char) = iter.peek() {
+            let mut word_parser = ShellWordParser::new(iter.clone(), &rules);
+            match word_parser.parse()? {
+                Some(word) => {
+                    iter = word_parser.iter;
+                    result.parts.extend(word.parts);
+                },
+                None => {
+                    iter.next();
+                    result.parts.push(ShellExpression::Literal(vec![next_char]));
+                },
+            }
+        }
+        Ok(Some(result))
+    }
+}
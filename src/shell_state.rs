@@ -1,11 +1,153 @@
-struct EnvVariable {
-    name: Vec<u8>,
-    value: Vec<Vec<u8>>,
-    count: usize,
-    is_array: bool,
-}
+pub mod shell_options;
+pub mod variables;
+pub mod dynamic_variables;
+pub mod command_hash;
+pub mod jobs;
+pub mod functions;
+
+use std::ffi::OsStr;
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::MetadataExt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::shell_builtins::BuiltinRegistry;
+use crate::shell_parser_base::ShellParsingRules;
+use crate::shell_translation::message_catalog::MessageCatalogCache;
+
+use command_hash::CommandHashTable;
+use functions::FunctionTable;
+use jobs::JobTable;
+use shell_options::{ShellOption, ShellOptions};
+use variables::{is_valid_name, VariableError, VariableStore, VariableValue};
 
 pub struct ShellState {
     parameters: Vec<Vec<u8>>,
+    pub options: ShellOptions,
+    pub shell_name: Vec<u8>, // $0
+    pub shell_pid: u32, // $$, stays the same in subshells
+    pub start_time: i64, // seconds since the epoch, for printf %(...)T with -2
+    pub last_exit_status: i32, // $?
+    pub last_substitution_status: Option<i32>, // status of an assignment-only command with command substitutions
+    pub last_background_pid: Option<i32>, // $!
+    pub is_interactive: bool,
+    pub variables: VariableStore,
+    pub command_hash: CommandHashTable,
+    pub pipeline_pgid: Option<i32>, // process group of the pipeline being started, with job control only
+    pub can_exec_directly: bool, // in a forked child running a single command, which can replace the process
+    pub jobs: JobTable,
+    pub foreground_command: Vec<u8>, // text of the running pipeline, kept for the job if it gets stopped
+    pub functions: FunctionTable,
+    pub control_flow: Option<ControlFlow>, // set by return, exit, break and continue, the commands left are skipped
+    pub loop_depth: usize, // loops being run, outside of the functions called from them
+    pub builtins: BuiltinRegistry,
+    pub source_depth: usize, // files being read by ., return ends the innermost one
+    pub directory_stack: Vec<Vec<u8>>, // pushd and popd, the current directory is not part of it
+    pub message_catalogs: MessageCatalogCache, // for $"..."
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ControlFlow {
+    Return, // until the function or the sourced file ends
+    Exit(i32), // until the shell ends, with this status
+    Break(usize), // out of this many enclosing loops
+    Continue(usize), // to the next iteration of the nth enclosing loop
+}
+
+impl ShellState {
+    pub fn new(shell_name: Vec<u8>, parameters: Vec<Vec<u8>>, is_interactive: bool) -> Self {
+        let mut result = Self {
+            parameters,
+            options: ShellOptions::default(),
+            shell_name,
+            shell_pid: std::process::id(),
+            start_time: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs() as i64),
+            last_exit_status: 0,
+            last_substitution_status: None,
+            last_background_pid: None,
+            is_interactive,
+            variables: VariableStore::default(),
+            command_hash: CommandHashTable::default(),
+            pipeline_pgid: None,
+            can_exec_directly: false,
+            jobs: JobTable::default(),
+            foreground_command: vec![],
+            functions: FunctionTable::default(),
+            control_flow: None,
+            loop_depth: 0,
+            builtins: BuiltinRegistry::default(),
+            source_depth: 0,
+            directory_stack: vec![],
+            message_catalogs: MessageCatalogCache::default(),
+        };
+        for (name, value) in std::env::vars_os() {
+            let name = name.into_vec();
+            if is_valid_name(&name) {
+                let variable = result.variables.declare(&name, true);
+                variable.value = Some(VariableValue::Scalar(value.into_vec()));
+                variable.attributes.is_exported = true;
+            }
+        }
+        // $_ starts as the name the shell was run with, afterwards it is the last argument of the
+        // previous command
+        let shell_name = result.shell_name.clone();
+        let _ = result.set_variable(b"_", shell_name);
+        result.initialize_working_directory();
+        result
+    }
 
-}
\ No newline at end of file
+    // Rules for the commands read from now on, shopt -s extglob affects the lines after it
+    pub fn get_parsing_rules(&self) -> ShellParsingRules {
+        ShellParsingRules {
+            is_interactive: self.is_interactive,
+            is_extglob_enabled: self.options.is_enabled(ShellOption::ExtGlob),
+        }
+    }
+
+    fn initialize_working_directory(&mut self) {
+        if let Some(path) = self.get_working_directory() {
+            let _ = self.set_variable(b"PWD", path);
+        }
+    }
+
+    // PWD when it is an absolute path naming the current directory, which keeps the path through
+    // symlinks that led there; the physical path otherwise
+    pub fn get_working_directory(&self) -> Option<Vec<u8>> {
+        let current = std::fs::metadata(".");
+        let is_current = |pwd: &[u8]| match (&current, std::fs::metadata(OsStr::from_bytes(pwd))) {
+            (Ok(current), Ok(pwd_metadata)) => current.dev() == pwd_metadata.dev() && current.ino() == pwd_metadata.ino(),
+            _ => false,
+        };
+        match self.get_variable(b"PWD") {
+            Some(pwd) if pwd.starts_with(b"/") && is_current(&pwd) => Some(pwd),
+            _ => std::env::current_dir().ok().map(|path| path.into_os_string().into_vec()),
+        }
+    }
+
+    pub fn get_positional_parameters(&self) -> &[Vec<u8>] {
+        &self.parameters
+    }
+
+    pub fn set_positional_parameters(&mut self, parameters: Vec<Vec<u8>>) {
+        self.parameters = parameters;
+    }
+
+    pub fn get_variable(&self, name: &[u8]) -> Option<Vec<u8>> {
+        self.variables.get_scalar(name)
+    }
+
+    pub fn set_variable(&mut self, name: &[u8], value: Vec<u8>) -> Result<(), VariableError> {
+        self.variables.set_scalar(name, value, false)
+    }
+
+    // $-
+    pub fn get_flags(&self) -> Vec<u8> {
+        let mut result = vec![];
+        if self.is_interactive {
+            result.push(b'i');
+        }
+        for (option, is_enabled) in self.options.iter_set_options() {
+            result.extend(option.get_flag().filter(|_| is_enabled));
+        }
+        result
+    }
+}
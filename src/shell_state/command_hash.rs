@@ -0,0 +1,43 @@
+use std::collections::HashMap;
+
+#[derive(Clone, Debug)]
+pub struct HashedCommand {
+    pub path: Vec<u8>,
+    pub hits: usize, // shown by hash
+}
+
+// Remembered PATH lookups, forgotten whenever PATH changes
+#[derive(Clone, Debug, Default)]
+pub struct CommandHashTable {
+    search_path: Vec<u8>, // PATH the entries were found with
+    entries: HashMap<Vec<u8>, HashedCommand>,
+}
+
+impl CommandHashTable {
+    pub fn get(&mut self, name: &[u8], search_path: &[u8]) -> Option<&mut HashedCommand> {
+        if self.search_path != search_path {
+            self.search_path = search_path.to_vec();
+            self.entries.clear();
+        }
+        self.entries.get_mut(name)
+    }
+
+    pub fn insert(&mut self, name: &[u8], path: Vec<u8>) {
+        self.entries.insert(name.to_vec(), HashedCommand { path, hits: 0 });
+    }
+
+    pub fn remove(&mut self, name: &[u8]) {
+        self.entries.remove(name);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    // Sorted by name for hash without arguments
+    pub fn get_entries(&self) -> Vec<(&[u8], &HashedCommand)> {
+        let mut result: Vec<(&[u8], &HashedCommand)> = self.entries.iter().map(|(name, command)| (name.as_slice(), command)).collect();
+        result.sort_by(|a, b| a.0.cmp(b.0));
+        result
+    }
+}
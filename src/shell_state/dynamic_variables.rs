@@ -0,0 +1,125 @@
+use std::cell::RefCell;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use rand::rngs::{OsRng, StdRng};
+use rand::{Rng, RngCore, SeedableRng};
+
+// Variables whose values are computed on every read. Unsetting one of them removes its special
+// meaning for the rest of the session, even when it is set again later, as in bash
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DynamicVariable {
+    Random, // RANDOM, 0 to 32767, assigning a value seeds the generator
+    SecureRandom, // SRANDOM, 32 bits from the system generator, assignments are ignored
+    Seconds, // SECONDS, assigning a value restarts the count from it
+    EpochSeconds, // EPOCHSECONDS, assignments are ignored
+    EpochRealtime, // EPOCHREALTIME, with microseconds, assignments are ignored
+    LineNumber, // LINENO, assignments are ignored
+    ShellPid, // BASHPID, differs from $$ in subshells, assignments are ignored
+}
+
+pub struct DynamicVariables {
+    active: Vec<DynamicVariable>,
+    random: RefCell<StdRng>, // reading RANDOM advances the generator, reads only borrow the store
+    is_random_seeded: bool, // RANDOM was assigned, subshells go on with the same sequence
+    seconds_start: Instant,
+    seconds_offset: i64,
+    pub line_number: usize, // kept up to date by whoever reads the input
+}
+
+impl DynamicVariable {
+    pub const ALL: [DynamicVariable; 7] = [
+        DynamicVariable::Random,
+        DynamicVariable::SecureRandom,
+        DynamicVariable::Seconds,
+        DynamicVariable::EpochSeconds,
+        DynamicVariable::EpochRealtime,
+        DynamicVariable::LineNumber,
+        DynamicVariable::ShellPid,
+    ];
+
+    pub fn get_name(&self) -> &'static [u8] {
+        match self {
+            DynamicVariable::Random => b"RANDOM",
+            DynamicVariable::SecureRandom => b"SRANDOM",
+            DynamicVariable::Seconds => b"SECONDS",
+            DynamicVariable::EpochSeconds => b"EPOCHSECONDS",
+            DynamicVariable::EpochRealtime => b"EPOCHREALTIME",
+            DynamicVariable::LineNumber => b"LINENO",
+            DynamicVariable::ShellPid => b"BASHPID",
+        }
+    }
+
+    // declare -p shows these with -i
+    pub fn is_integer(&self) -> bool {
+        !matches!(self, DynamicVariable::EpochSeconds | DynamicVariable::EpochRealtime)
+    }
+}
+
+// Values assigned to RANDOM and SECONDS have already been evaluated because of their integer attribute
+fn parse_integer(value: &[u8]) -> i64 {
+    String::from_utf8_lossy(value).trim().parse().unwrap_or(0)
+}
+
+impl Default for DynamicVariables {
+    fn default() -> Self {
+        Self {
+            active: DynamicVariable::ALL.to_vec(),
+            random: RefCell::new(StdRng::from_entropy()),
+            is_random_seeded: false,
+            seconds_start: Instant::now(),
+            seconds_offset: 0,
+            line_number: 0,
+        }
+    }
+}
+
+impl DynamicVariables {
+    pub fn get_active(&self, name: &[u8]) -> Option<DynamicVariable> {
+        self.active.iter().copied().find(|variable| variable.get_name() == name)
+    }
+
+    pub fn deactivate(&mut self, name: &[u8]) {
+        self.active.retain(|variable| variable.get_name() != name);
+    }
+
+    pub fn get_value(&self, variable: DynamicVariable) -> Vec<u8> {
+        let since_epoch = || SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let value = match variable {
+            DynamicVariable::Random => self.random.borrow_mut().gen_range(0..32768).to_string(),
+            DynamicVariable::SecureRandom => OsRng.next_u32().to_string(),
+            DynamicVariable::Seconds => {
+                (self.seconds_offset + self.seconds_start.elapsed().as_secs() as i64).to_string()
+            },
+            DynamicVariable::EpochSeconds => since_epoch().as_secs().to_string(),
+            DynamicVariable::EpochRealtime => {
+                let duration = since_epoch();
+                format!("{}.{:06}", duration.as_secs(), duration.subsec_micros())
+            },
+            DynamicVariable::LineNumber => self.line_number.to_string(),
+            DynamicVariable::ShellPid => std::process::id().to_string(),
+        };
+        value.into_bytes()
+    }
+
+    // Called in forked children, which would otherwise all get the values of the copied generator
+    pub fn reseed_random(&mut self) {
+        if !self.is_random_seeded {
+            self.random = RefCell::new(StdRng::from_entropy());
+        }
+    }
+
+    // RANDOM=seed makes the following values reproducible
+    pub fn assign(&mut self, variable: DynamicVariable, value: &[u8]) {
+        match variable {
+            DynamicVariable::Random => {
+                self.random = RefCell::new(StdRng::seed_from_u64(parse_integer(value) as u64));
+                self.is_random_seeded = true;
+            },
+            DynamicVariable::Seconds => {
+                self.seconds_start = Instant::now();
+                self.seconds_offset = parse_integer(value);
+            },
+            _ => {},
+        }
+    }
+}
@@ -0,0 +1,45 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::shell_structures::shell_command::shell_function_definition::ShellFunctionDefinition;
+
+#[derive(Clone, Debug)]
+pub struct ShellFunction {
+    pub definition: Rc<ShellFunctionDefinition>,
+    pub source: Vec<u8>, // where it was defined, shown in BASH_SOURCE
+}
+
+// A running function, innermost last
+#[derive(Clone, Debug)]
+pub struct CallFrame {
+    pub name: Vec<u8>,
+    pub source: Vec<u8>,
+    pub line: usize, // line of the call, shown in BASH_LINENO
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct FunctionTable {
+    functions: HashMap<Vec<u8>, ShellFunction>,
+    pub call_stack: Vec<CallFrame>,
+}
+
+impl FunctionTable {
+    pub fn get(&self, name: &[u8]) -> Option<&ShellFunction> {
+        self.functions.get(name)
+    }
+
+    pub fn define(&mut self, definition: Rc<ShellFunctionDefinition>, source: Vec<u8>) {
+        self.functions.insert(definition.name.clone(), ShellFunction { definition, source });
+    }
+
+    pub fn remove(&mut self, name: &[u8]) -> Option<ShellFunction> {
+        self.functions.remove(name)
+    }
+
+    // Sorted by name, as declare -f lists them
+    pub fn iter(&self) -> impl Iterator<Item = &ShellFunction> {
+        let mut functions: Vec<&ShellFunction> = self.functions.values().collect();
+        functions.sort_by(|a, b| a.definition.name.cmp(&b.definition.name));
+        functions.into_iter()
+    }
+}
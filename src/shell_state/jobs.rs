@@ -0,0 +1,232 @@
+use std::ffi::CStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProcessStatus {
+    Running,
+    Stopped(i32), // signal that stopped it
+    Exited(i32),
+    Signaled(i32),
+}
+
+#[derive(Clone, Debug)]
+pub struct JobProcess {
+    pub pid: i32,
+    pub status: ProcessStatus,
+}
+
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub id: usize, // the n of %n
+    pub pgid: i32,
+    pub command: Vec<u8>,
+    pub processes: Vec<JobProcess>,
+    pub is_changed: bool, // the new status has not been reported yet
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JobSpecError {
+    NoSuchJob(Vec<u8>),
+    Ambiguous(Vec<u8>),
+}
+
+// %+ is the current job, the most recently started or stopped one, %- the one before it
+#[derive(Clone, Debug, Default)]
+pub struct JobTable {
+    jobs: Vec<Job>,
+    current: Option<usize>,
+    previous: Option<usize>,
+}
+
+fn get_signal_name(signal: i32) -> String {
+    let name = unsafe { libc::strsignal(signal) };
+    if name.is_null() {
+        return format!("Signal {signal}");
+    }
+    unsafe { CStr::from_ptr(name) }.to_string_lossy().into_owned()
+}
+
+impl ProcessStatus {
+    pub fn from_wait_status(wait_status: i32) -> Self {
+        if libc::WIFEXITED(wait_status) {
+            ProcessStatus::Exited(libc::WEXITSTATUS(wait_status))
+        } else if libc::WIFSIGNALED(wait_status) {
+            ProcessStatus::Signaled(libc::WTERMSIG(wait_status))
+        } else if libc::WIFSTOPPED(wait_status) {
+            ProcessStatus::Stopped(libc::WSTOPSIG(wait_status))
+        } else {
+            ProcessStatus::Running // WIFCONTINUED
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ProcessStatus::Exited(_) | ProcessStatus::Signaled(_))
+    }
+
+    // Stopped processes count as killed by the signal that stopped them
+    pub fn get_exit_status(&self) -> i32 {
+        match *self {
+            ProcessStatus::Running => 0,
+            ProcessStatus::Exited(status) => status,
+            ProcessStatus::Stopped(signal) | ProcessStatus::Signaled(signal) => 128 + signal,
+        }
+    }
+
+    // Shown by jobs and in the notifications
+    pub fn describe(&self) -> String {
+        match *self {
+            ProcessStatus::Running => "Running".to_string(),
+            ProcessStatus::Stopped(libc::SIGTSTP) => "Stopped".to_string(),
+            ProcessStatus::Stopped(libc::SIGTTIN) => "Stopped (tty input)".to_string(),
+            ProcessStatus::Stopped(libc::SIGTTOU) => "Stopped (tty output)".to_string(),
+            ProcessStatus::Stopped(_) => "Stopped (signal)".to_string(),
+            ProcessStatus::Exited(0) => "Done".to_string(),
+            ProcessStatus::Exited(status) => format!("Exit {status}"),
+            ProcessStatus::Signaled(signal) => get_signal_name(signal),
+        }
+    }
+}
+
+impl JobSpecError {
+    pub fn get_message(&self) -> String {
+        match self {
+            JobSpecError::NoSuchJob(spec) => format!("{}: no such job", String::from_utf8_lossy(spec)),
+            JobSpecError::Ambiguous(spec) => format!("{}: ambiguous job spec", String::from_utf8_lossy(spec)),
+        }
+    }
+}
+
+impl Job {
+    // A stopped process stops the job, otherwise the job runs until all of its processes are finished
+    // and ends with the status of the last one
+    pub fn get_status(&self) -> ProcessStatus {
+        let statuses = || self.processes.iter().map(|process| process.status);
+        if let Some(status) = statuses().find(|status| matches!(status, ProcessStatus::Stopped(_))) {
+            return status;
+        }
+        if statuses().any(|status| status == ProcessStatus::Running) {
+            return ProcessStatus::Running;
+        }
+        statuses().next_back().unwrap_or(ProcessStatus::Exited(0))
+    }
+
+    pub fn set_running(&mut self) {
+        for process in self.processes.iter_mut().filter(|process| !process.status.is_finished()) {
+            process.status = ProcessStatus::Running;
+        }
+    }
+
+    // [1]+  Running                 sleep 10 &
+    pub fn format(&self, marker: char, pid: Option<i32>) -> String {
+        let status = self.get_status();
+        let pid = pid.map(|pid| format!(" {pid}")).unwrap_or_default();
+        let separator = if pid.is_empty() { " " } else { "" };
+        let suffix = if status == ProcessStatus::Running { " &" } else { "" };
+        let command = String::from_utf8_lossy(&self.command);
+        format!("[{}]{marker}{pid}{separator} {:<24}{command}{suffix}", self.id, status.describe())
+    }
+}
+
+impl JobTable {
+    pub fn iter(&self) -> impl Iterator<Item = &Job> {
+        self.jobs.iter()
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Job> {
+        self.jobs.iter().find(|job| job.id == id)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Job> {
+        self.jobs.iter_mut().find(|job| job.id == id)
+    }
+
+    pub fn get_marker(&self, id: usize) -> char {
+        if self.current == Some(id) {
+            '+'
+        } else if self.previous == Some(id) {
+            '-'
+        } else {
+            ' '
+        }
+    }
+
+    pub fn set_current(&mut self, id: usize) {
+        if self.current != Some(id) {
+            self.previous = self.current;
+            self.current = Some(id);
+        }
+    }
+
+    pub fn add(&mut self, pgid: i32, processes: Vec<JobProcess>, command: Vec<u8>) -> usize {
+        self.insert(Job { id: 0, pgid, command, processes, is_changed: false })
+    }
+
+    // Jobs without a number get one after the highest in use, a job put back keeps its own
+    pub fn insert(&mut self, mut job: Job) -> usize {
+        if job.id == 0 {
+            job.id = self.jobs.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        }
+        let id = job.id;
+        let index = self.jobs.partition_point(|other| other.id < id);
+        self.jobs.insert(index, job);
+        self.set_current(id);
+        id
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Job> {
+        let index = self.jobs.iter().position(|job| job.id == id)?;
+        let job = self.jobs.remove(index);
+        if self.current == Some(id) {
+            self.current = self.previous.take();
+        } else if self.previous == Some(id) {
+            self.previous = None;
+        }
+        let most_recent = |except: Option<usize>| self.jobs.iter().rev().map(|job| job.id).find(|&id| Some(id) != except);
+        if self.current.is_none() {
+            self.current = most_recent(None);
+        }
+        if self.previous.is_none() {
+            self.previous = most_recent(self.current);
+        }
+        Some(job)
+    }
+
+    // Called for every status change reported by waitpid, false when the process is not in a job
+    pub fn update_process(&mut self, pid: i32, status: ProcessStatus) -> bool {
+        for job in self.jobs.iter_mut() {
+            if let Some(process) = job.processes.iter_mut().find(|process| process.pid == pid) {
+                process.status = status;
+                job.is_changed = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    // %n, %+ or %%, %-, %str for a command starting with str and %?str for one containing it;
+    // the % can be omitted
+    pub fn find(&self, spec: &[u8]) -> Result<usize, JobSpecError> {
+        let no_such_job = || JobSpecError::NoSuchJob(spec.to_vec());
+        let body = spec.strip_prefix(b"%").unwrap_or(spec);
+        match body {
+            b"" | b"+" | b"%" => return self.current.ok_or_else(no_such_job),
+            b"-" => return self.previous.or(self.current).ok_or_else(no_such_job),
+            _ => {},
+        }
+        if body.iter().all(u8::is_ascii_digit) {
+            let id: usize = String::from_utf8_lossy(body).parse().map_err(|_| no_such_job())?;
+            return self.get(id).map(|job| job.id).ok_or_else(no_such_job);
+        }
+        let matches: Vec<usize> = match body.strip_prefix(b"?") {
+            Some(text) => self.jobs.iter()
+                .filter(|job| job.command.windows(text.len().max(1)).any(|window| window == text))
+                .map(|job| job.id)
+                .collect(),
+            None => self.jobs.iter().filter(|job| job.command.starts_with(body)).map(|job| job.id).collect(),
+        };
+        match matches.as_slice() {
+            [] => Err(no_such_job()),
+            [id] => Ok(*id),
+            _ => Err(JobSpecError::Ambiguous(spec.to_vec())),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ShellOption {
+    DotGlob,
+    ExtGlob,
+    FailGlob,
+    GlobStar,
+    NoCaseGlob,
+    NullGlob,
+    LastPipe,
+    Monitor,
+    PipeFail,
+    NoClobber,
+    XpgEcho,
+}
+
+// Name used by shopt and the default value
+const SHELL_OPTIONS: [(ShellOption, &str, bool); 8] = [
+    (ShellOption::DotGlob, "dotglob", false),
+    (ShellOption::ExtGlob, "extglob", false),
+    (ShellOption::FailGlob, "failglob", false),
+    (ShellOption::GlobStar, "globstar", false),
+    (ShellOption::NoCaseGlob, "nocaseglob", false),
+    (ShellOption::NullGlob, "nullglob", false),
+    (ShellOption::LastPipe, "lastpipe", false),
+    (ShellOption::XpgEcho, "xpg_echo", false),
+];
+
+// Name used by set -o, the single-letter flag and the default value; monitor is enabled for
+// interactive shells on a terminal
+const SET_OPTIONS: [(ShellOption, &str, Option<u8>, bool); 3] = [
+    (ShellOption::Monitor, "monitor", Some(b'm'), false),
+    (ShellOption::NoClobber, "noclobber", Some(b'C'), false),
+    (ShellOption::PipeFail, "pipefail", None, false),
+];
+
+impl ShellOption {
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        SHELL_OPTIONS
+            .iter()
+            .find(|(_, option_name, _)| option_name.as_bytes() == name)
+            .map(|&(option, _, _)| option)
+    }
+
+    pub fn from_set_name(name: &[u8]) -> Option<Self> {
+        SET_OPTIONS
+            .iter()
+            .find(|(_, option_name, _, _)| option_name.as_bytes() == name)
+            .map(|&(option, _, _, _)| option)
+    }
+
+    pub fn from_flag(flag: u8) -> Option<Self> {
+        SET_OPTIONS.iter().find(|(_, _, option_flag, _)| *option_flag == Some(flag)).map(|&(option, _, _, _)| option)
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        SHELL_OPTIONS
+            .iter()
+            .map(|&(option, name, _)| (option, name))
+            .chain(SET_OPTIONS.iter().map(|&(option, name, _, _)| (option, name)))
+            .find(|(option, _)| option == self)
+            .map(|(_, name)| name)
+            .unwrap()
+    }
+
+    pub fn get_flag(&self) -> Option<u8> {
+        SET_OPTIONS.iter().find(|(option, _, _, _)| option == self).and_then(|&(_, _, flag, _)| flag)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ShellOptions {
+    enabled: HashSet<ShellOption>,
+}
+
+impl Default for ShellOptions {
+    fn default() -> Self {
+        Self {
+            enabled: SHELL_OPTIONS
+                .iter()
+                .map(|&(option, _, is_default)| (option, is_default))
+                .chain(SET_OPTIONS.iter().map(|&(option, _, _, is_default)| (option, is_default)))
+                .filter(|(_, is_default)| *is_default)
+                .map(|(option, _)| option)
+                .collect(),
+        }
+    }
+}
+
+impl ShellOptions {
+    pub fn is_enabled(&self, option: ShellOption) -> bool {
+        self.enabled.contains(&option)
+    }
+
+    pub fn set(&mut self, option: ShellOption, value: bool) {
+        if value {
+            self.enabled.insert(option);
+        } else {
+            self.enabled.remove(&option);
+        }
+    }
+
+    // shopt options
+    pub fn iter(&self) -> impl Iterator<Item = (ShellOption, bool)> + '_ {
+        SHELL_OPTIONS.iter().map(|&(option, _, _)| (option, self.is_enabled(option)))
+    }
+
+    // set -o options
+    pub fn iter_set_options(&self) -> impl Iterator<Item = (ShellOption, bool)> + '_ {
+        SET_OPTIONS.iter().map(|&(option, _, _, _)| (option, self.is_enabled(option)))
+    }
+}
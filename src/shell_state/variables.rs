@@ -0,0 +1,549 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::dynamic_variables::{DynamicVariable, DynamicVariables};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct VariableAttributes {
+    pub is_exported: bool, // -x
+    pub is_readonly: bool, // -r
+    pub is_integer: bool, // -i, assigned values are evaluated as arithmetic expressions by the caller
+    pub is_lowercase: bool, // -l
+    pub is_uppercase: bool, // -u
+    pub is_nameref: bool, // -n
+    pub is_traced: bool, // -t
+}
+
+#[derive(Clone, Debug)]
+pub enum VariableValue {
+    Scalar(Vec<u8>),
+    Indexed(BTreeMap<i64, Vec<u8>>), // sparse, the indices are never negative
+    Associative(BTreeMap<Vec<u8>, Vec<u8>>),
+}
+
+// Subscript after the evaluation: arithmetic for indexed arrays, negative indices count from the end
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ArrayKey {
+    Index(i64),
+    Key(Vec<u8>),
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Variable {
+    pub value: Option<VariableValue>, // None for variables that are declared but unset: local x
+    pub attributes: VariableAttributes,
+}
+
+#[derive(Debug)]
+pub enum VariableError {
+    ReadOnly(Vec<u8>),
+    CannotUnset(Vec<u8>),
+    BadSubscript(Vec<u8>),
+    CircularReference(Vec<u8>),
+    InvalidReference(Vec<u8>),
+}
+
+// Variables of the shell, the first frame is the global one and every function call pushes another;
+// the prefix assignments of a builtin get a temporary frame, which declarations go past
+pub struct VariableStore {
+    frames: Vec<HashMap<Vec<u8>, Variable>>,
+    temporary_frames: Vec<usize>, // indices in frames
+    pub dynamic: DynamicVariables,
+}
+
+pub fn is_valid_name(name: &[u8]) -> bool {
+    match name.split_first() {
+        Some((first, rest)) => {
+            (first.is_ascii_alphabetic() || *first == b'_') && rest.iter().all(|c| c.is_ascii_alphanumeric() || *c == b'_')
+        },
+        None => false,
+    }
+}
+
+// Targets of namerefs are names or array elements: arr[2]
+fn split_reference(target: &[u8]) -> (&[u8], Option<&[u8]>) {
+    match target.iter().position(|&c| c == b'[') {
+        Some(position) if target.ends_with(b"]") => (&target[..position], Some(&target[position + 1..target.len() - 1])),
+        _ => (target, None),
+    }
+}
+
+pub fn is_valid_reference(target: &[u8]) -> bool {
+    is_valid_name(split_reference(target).0)
+}
+
+impl VariableError {
+    pub fn get_message(&self) -> String {
+        match self {
+            VariableError::ReadOnly(name) => format!("{}: readonly variable", String::from_utf8_lossy(name)),
+            VariableError::CannotUnset(name) => {
+                format!("{}: cannot unset: readonly variable", String::from_utf8_lossy(name))
+            },
+            VariableError::BadSubscript(name) => format!("{}: bad array subscript", String::from_utf8_lossy(name)),
+            VariableError::CircularReference(name) => {
+                format!("{}: circular name reference", String::from_utf8_lossy(name))
+            },
+            VariableError::InvalidReference(target) => {
+                format!("`{}': invalid variable name for name reference", String::from_utf8_lossy(target))
+            },
+        }
+    }
+}
+
+impl Variable {
+    pub fn is_associative(&self) -> bool {
+        matches!(self.value, Some(VariableValue::Associative(_)))
+    }
+
+    pub fn is_array(&self) -> bool {
+        matches!(self.value, Some(VariableValue::Indexed(_) | VariableValue::Associative(_)))
+    }
+
+    // $name of an array is its element 0
+    pub fn get_scalar(&self) -> Option<&[u8]> {
+        match self.value.as_ref()? {
+            VariableValue::Scalar(value) => Some(value),
+            VariableValue::Indexed(elements) => elements.get(&0).map(Vec::as_slice),
+            VariableValue::Associative(elements) => elements.get(b"0".as_slice()).map(Vec::as_slice),
+        }
+    }
+
+    fn resolve_index(&self, index: i64) -> Option<i64> {
+        if index >= 0 {
+            return Some(index);
+        }
+        let next_index = match self.value.as_ref()? {
+            VariableValue::Scalar(_) => 1,
+            VariableValue::Indexed(elements) => elements.keys().next_back().map_or(0, |last| last + 1),
+            VariableValue::Associative(_) => return None,
+        };
+        Some(next_index + index).filter(|&index| index >= 0)
+    }
+
+    pub fn get_element(&self, key: &ArrayKey) -> Option<&[u8]> {
+        match (self.value.as_ref()?, key) {
+            (VariableValue::Associative(elements), ArrayKey::Key(key)) => elements.get(key).map(Vec::as_slice),
+            (VariableValue::Associative(elements), ArrayKey::Index(index)) => {
+                elements.get(index.to_string().as_bytes()).map(Vec::as_slice)
+            },
+            (VariableValue::Indexed(elements), ArrayKey::Index(index)) => {
+                elements.get(&self.resolve_index(*index)?).map(Vec::as_slice)
+            },
+            (VariableValue::Scalar(value), ArrayKey::Index(index)) if self.resolve_index(*index) == Some(0) => Some(value),
+            _ => None,
+        }
+    }
+
+    // ${name[@]}, in the order of the indices
+    pub fn get_values(&self) -> Vec<Vec<u8>> {
+        match &self.value {
+            Some(VariableValue::Scalar(value)) => vec![value.clone()],
+            Some(VariableValue::Indexed(elements)) => elements.values().cloned().collect(),
+            Some(VariableValue::Associative(elements)) => elements.values().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    // ${!name[@]}
+    pub fn get_keys(&self) -> Vec<Vec<u8>> {
+        match &self.value {
+            Some(VariableValue::Scalar(_)) => vec![b"0".to_vec()],
+            Some(VariableValue::Indexed(elements)) => elements.keys().map(|index| index.to_string().into_bytes()).collect(),
+            Some(VariableValue::Associative(elements)) => elements.keys().cloned().collect(),
+            None => vec![],
+        }
+    }
+
+    fn transform_value(&self, value: Vec<u8>) -> Vec<u8> {
+        if !self.attributes.is_lowercase && !self.attributes.is_uppercase {
+            return value;
+        }
+        match String::from_utf8(value) {
+            Ok(text) if self.attributes.is_lowercase => text.to_lowercase().into_bytes(),
+            Ok(text) => text.to_uppercase().into_bytes(),
+            Err(err) if self.attributes.is_lowercase => err.into_bytes().to_ascii_lowercase(),
+            Err(err) => err.into_bytes().to_ascii_uppercase(),
+        }
+    }
+
+    // A scalar becomes element 0 when the variable is turned into an indexed array
+    fn get_indexed_elements(&mut self) -> &mut BTreeMap<i64, Vec<u8>> {
+        let elements = match self.value.take() {
+            Some(VariableValue::Indexed(elements)) => elements,
+            Some(VariableValue::Scalar(value)) => BTreeMap::from([(0, value)]),
+            Some(VariableValue::Associative(_)) | None => BTreeMap::new(),
+        };
+        self.value = Some(VariableValue::Indexed(elements));
+        match &mut self.value {
+            Some(VariableValue::Indexed(elements)) => elements,
+            _ => unreachable!(),
+        }
+    }
+
+    fn set_element(&mut self, name: &[u8], key: ArrayKey, value: Vec<u8>, is_append: bool) -> Result<(), VariableError> {
+        let value = self.transform_value(value);
+        if let Some(VariableValue::Associative(elements)) = &mut self.value {
+            let key = match key {
+                ArrayKey::Key(key) => key,
+                ArrayKey::Index(index) => index.to_string().into_bytes(),
+            };
+            let element = elements.entry(key).or_default();
+            if !is_append {
+                element.clear();
+            }
+            element.extend(value);
+            return Ok(());
+        }
+        let index = match key {
+            ArrayKey::Index(index) => self.resolve_index(index).ok_or_else(|| VariableError::BadSubscript(name.to_vec()))?,
+            ArrayKey::Key(_) => return Err(VariableError::BadSubscript(name.to_vec())),
+        };
+        let element = self.get_indexed_elements().entry(index).or_default();
+        if !is_append {
+            element.clear();
+        }
+        element.extend(value);
+        Ok(())
+    }
+
+    fn set_scalar(&mut self, name: &[u8], value: Vec<u8>, is_append: bool) -> Result<(), VariableError> {
+        if self.is_array() {
+            return self.set_element(name, ArrayKey::Index(0), value, is_append);
+        }
+        let value = self.transform_value(value);
+        let value = match (&self.value, is_append) {
+            (Some(VariableValue::Scalar(old_value)), true) => [old_value.as_slice(), &value].concat(),
+            _ => value,
+        };
+        self.value = Some(VariableValue::Scalar(value));
+        Ok(())
+    }
+
+    // (a b [5]=c d) sets 0, 1, 5 and 6; associative arrays take the elements without subscripts as key-value pairs
+    fn set_array(&mut self, name: &[u8], elements: Vec<(Option<ArrayKey>, Vec<u8>)>, is_append: bool) -> Result<(), VariableError> {
+        if self.is_associative() {
+            if !is_append {
+                self.value = Some(VariableValue::Associative(BTreeMap::new()));
+            }
+            let mut iter = elements.into_iter();
+            while let Some((key, value)) = iter.next() {
+                let (key, value) = match key {
+                    Some(key) => (key, value),
+                    None => (ArrayKey::Key(value), iter.next().map(|(_, value)| value).unwrap_or_default()),
+                };
+                self.set_element(name, key, value, false)?;
+            }
+            return Ok(());
+        }
+        let mut next_index = match is_append {
+            true => self.get_indexed_elements().keys().next_back().map_or(0, |last| last + 1),
+            false => {
+                self.value = Some(VariableValue::Indexed(BTreeMap::new()));
+                0
+            },
+        };
+        for (key, value) in elements {
+            let index = match key {
+                Some(ArrayKey::Index(index)) => self.resolve_index(index).ok_or_else(|| VariableError::BadSubscript(name.to_vec()))?,
+                Some(ArrayKey::Key(_)) => return Err(VariableError::BadSubscript(name.to_vec())),
+                None => next_index,
+            };
+            self.set_element(name, ArrayKey::Index(index), value, false)?;
+            next_index = index + 1;
+        }
+        Ok(())
+    }
+}
+
+impl Default for VariableStore {
+    fn default() -> Self {
+        let mut globals = HashMap::new();
+        for dynamic in DynamicVariable::ALL {
+            let mut variable = Variable { value: Some(VariableValue::Scalar(vec![])), ..Default::default() };
+            variable.attributes.is_integer = dynamic.is_integer();
+            globals.insert(dynamic.get_name().to_vec(), variable);
+        }
+        Self { frames: vec![globals], temporary_frames: vec![], dynamic: DynamicVariables::default() }
+    }
+}
+
+impl VariableStore {
+    // Called when a function starts, its local variables live until the matching pop_scope
+    pub fn push_scope(&mut self) {
+        self.frames.push(HashMap::new());
+    }
+
+    // Called before a builtin runs with prefix assignments: FOO=1 read x
+    pub fn push_temporary_scope(&mut self) {
+        self.temporary_frames.push(self.frames.len());
+        self.frames.push(HashMap::new());
+    }
+
+    pub fn pop_scope(&mut self) {
+        if self.frames.len() > 1 {
+            self.frames.pop();
+        }
+        if self.temporary_frames.last() == Some(&self.frames.len()) {
+            self.temporary_frames.pop();
+        }
+    }
+
+    // The frame of the function being run, or the global one
+    fn get_current_frame_index(&self) -> usize {
+        (0..self.frames.len()).rev().find(|index| !self.temporary_frames.contains(index)).unwrap_or(0)
+    }
+
+    pub fn is_in_function(&self) -> bool {
+        self.get_current_frame_index() > 0
+    }
+
+    // The innermost visible variable, dynamic scoping makes locals of the callers visible as well;
+    // namerefs are not followed, declare -p and unset -n work on the reference itself
+    pub fn get_unresolved(&self, name: &[u8]) -> Option<&Variable> {
+        self.frames.iter().rev().find_map(|frame| frame.get(name))
+    }
+
+    pub fn is_reference(&self, name: &[u8]) -> bool {
+        self.get_unresolved(name).is_some_and(|variable| variable.attributes.is_nameref)
+    }
+
+    // Follows namerefs to the referenced variable, ref=arr[2] gives ("arr", Some("2")): the subscript is
+    // evaluated by the caller. A nameref without a value resolves to itself, assigning to it sets the target
+    pub fn resolve_reference(&self, name: &[u8]) -> Result<(Vec<u8>, Option<Vec<u8>>), VariableError> {
+        let mut visited: Vec<Vec<u8>> = vec![];
+        let mut current = name.to_vec();
+        loop {
+            let target = match self.get_unresolved(&current) {
+                Some(Variable { value: Some(VariableValue::Scalar(target)), attributes }) if attributes.is_nameref && !target.is_empty() => {
+                    target
+                },
+                _ => return Ok((current, None)),
+            };
+            if visited.contains(&current) {
+                return Err(VariableError::CircularReference(name.to_vec()));
+            }
+            visited.push(current);
+            match split_reference(target) {
+                (target, Some(subscript)) => return Ok((target.to_vec(), Some(subscript.to_vec()))),
+                (target, None) => current = target.to_vec(),
+            }
+        }
+    }
+
+    // Subscripts of nameref targets taken literally, the expansions evaluate them as arithmetic instead
+    fn get_literal_key(&self, name: &[u8], subscript: &[u8]) -> Result<ArrayKey, VariableError> {
+        if self.get_unresolved(name).is_some_and(Variable::is_associative) {
+            return Ok(ArrayKey::Key(subscript.to_vec()));
+        }
+        match String::from_utf8_lossy(subscript).trim().parse() {
+            Ok(index) => Ok(ArrayKey::Index(index)),
+            Err(_) => Err(VariableError::BadSubscript(name.to_vec())),
+        }
+    }
+
+    // The variable a nameref points to; for array elements that is the whole array
+    pub fn get(&self, name: &[u8]) -> Option<&Variable> {
+        let (name, _) = self.resolve_reference(name).ok()?;
+        self.get_unresolved(&name)
+    }
+
+    // Locals named like a dynamic variable are ordinary ones
+    fn get_dynamic(&self, name: &[u8]) -> Option<DynamicVariable> {
+        let dynamic = self.dynamic.get_active(name)?;
+        (self.frames.iter().rposition(|frame| frame.contains_key(name)) == Some(0)).then_some(dynamic)
+    }
+
+    pub fn get_scalar(&self, name: &[u8]) -> Option<Vec<u8>> {
+        let (name, subscript) = self.resolve_reference(name).ok()?;
+        if let Some(subscript) = subscript {
+            let key = self.get_literal_key(&name, &subscript).ok()?;
+            return self.get_element(&name, &key);
+        }
+        if let Some(dynamic) = self.get_dynamic(&name) {
+            return Some(self.dynamic.get_value(dynamic));
+        }
+        self.get_unresolved(&name)?.get_scalar().map(<[u8]>::to_vec)
+    }
+
+    pub fn get_element(&self, name: &[u8], key: &ArrayKey) -> Option<Vec<u8>> {
+        self.get(name)?.get_element(key).map(<[u8]>::to_vec)
+    }
+
+    // Assignments change the innermost visible variable, new variables are global
+    fn get_writable(&mut self, name: &[u8]) -> Result<&mut Variable, VariableError> {
+        let frame_index = self.frames.iter().rposition(|frame| frame.contains_key(name)).unwrap_or(0);
+        let variable = self.frames[frame_index].entry(name.to_vec()).or_default();
+        if variable.attributes.is_readonly {
+            return Err(VariableError::ReadOnly(name.to_vec()));
+        }
+        Ok(variable)
+    }
+
+    // local and declare create the variable in the current frame, declare -g in the global one
+    pub fn declare(&mut self, name: &[u8], is_global: bool) -> &mut Variable {
+        let frame_index = match is_global {
+            true => 0,
+            false => self.get_current_frame_index(),
+        };
+        self.frames[frame_index].entry(name.to_vec()).or_default()
+    }
+
+    // Prefix assignments go to the frame pushed for them, temporary or not
+    pub fn declare_innermost(&mut self, name: &[u8]) -> &mut Variable {
+        self.frames.last_mut().unwrap().entry(name.to_vec()).or_default()
+    }
+
+    // export and readonly change the innermost visible variable, new ones are global
+    pub fn declare_visible(&mut self, name: &[u8]) -> &mut Variable {
+        let frame_index = (0..self.frames.len())
+            .rev()
+            .find(|&index| !self.temporary_frames.contains(&index) && self.frames[index].contains_key(name))
+            .unwrap_or(0);
+        self.frames[frame_index].entry(name.to_vec()).or_default()
+    }
+
+    pub fn set_scalar(&mut self, name: &[u8], value: Vec<u8>, is_append: bool) -> Result<(), VariableError> {
+        let (name, subscript) = self.resolve_reference(name)?;
+        if let Some(subscript) = subscript {
+            let key = self.get_literal_key(&name, &subscript)?;
+            return self.set_element(&name, key, value, is_append);
+        }
+        if let Some(dynamic) = self.get_dynamic(&name) {
+            self.get_writable(&name)?;
+            self.dynamic.assign(dynamic, &value);
+            return Ok(());
+        }
+        let variable = self.get_writable(&name)?;
+        if variable.attributes.is_nameref {
+            return Self::set_reference_target(variable, &name, value);
+        }
+        variable.set_scalar(&name, value, is_append)
+    }
+
+    pub fn set_element(&mut self, name: &[u8], key: ArrayKey, value: Vec<u8>, is_append: bool) -> Result<(), VariableError> {
+        let (name, _) = self.resolve_reference(name)?;
+        self.get_writable(&name)?.set_element(&name, key, value, is_append)
+    }
+
+    pub fn set_array(&mut self, name: &[u8], elements: Vec<(Option<ArrayKey>, Vec<u8>)>, is_append: bool) -> Result<(), VariableError> {
+        let (name, _) = self.resolve_reference(name)?;
+        self.get_writable(&name)?.set_array(&name, elements, is_append)
+    }
+
+    fn set_reference_target(variable: &mut Variable, name: &[u8], target: Vec<u8>) -> Result<(), VariableError> {
+        if !is_valid_reference(&target) {
+            return Err(VariableError::InvalidReference(target));
+        }
+        if target == name {
+            return Err(VariableError::CircularReference(name.to_vec()));
+        }
+        variable.attributes.is_nameref = true;
+        variable.value = Some(VariableValue::Scalar(target));
+        Ok(())
+    }
+
+    // declare -n name=target, the nameref itself is changed instead of the variable it points to
+    pub fn set_reference(&mut self, name: &[u8], target: Vec<u8>) -> Result<(), VariableError> {
+        let variable = self.get_writable(name)?;
+        Self::set_reference_target(variable, name, target)
+    }
+
+    // A for loop over a nameref makes it point to each of the words in turn
+    pub fn set_loop_variable(&mut self, name: &[u8], value: Vec<u8>) -> Result<(), VariableError> {
+        match self.is_reference(name) {
+            true => self.set_reference(name, value),
+            false => self.set_scalar(name, value, false),
+        }
+    }
+
+    // Unsetting a local of the current function leaves it unset but still local until the function
+    // returns, otherwise the variable is removed and the shadowed one becomes visible; unset -n removes
+    // a nameref itself
+    pub fn unset_reference(&mut self, name: &[u8]) -> Result<(), VariableError> {
+        let frame_index = match self.frames.iter().rposition(|frame| frame.contains_key(name)) {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        if self.frames[frame_index][name].attributes.is_readonly {
+            return Err(VariableError::CannotUnset(name.to_vec()));
+        }
+        if frame_index > 0 && frame_index == self.get_current_frame_index() {
+            self.frames[frame_index].insert(name.to_vec(), Variable::default());
+        } else {
+            self.frames[frame_index].remove(name);
+        }
+        if frame_index == 0 {
+            self.dynamic.deactivate(name);
+        }
+        Ok(())
+    }
+
+    // unset ref removes the variable the nameref points to
+    pub fn unset(&mut self, name: &[u8]) -> Result<(), VariableError> {
+        match self.resolve_reference(name)? {
+            (name, Some(subscript)) => {
+                let key = self.get_literal_key(&name, &subscript)?;
+                self.unset_element(&name, &key)
+            },
+            (name, None) => self.unset_reference(&name),
+        }
+    }
+
+    pub fn unset_element(&mut self, name: &[u8], key: &ArrayKey) -> Result<(), VariableError> {
+        let (name, _) = self.resolve_reference(name)?;
+        let name = name.as_slice();
+        let variable = match self.frames.iter_mut().rev().find_map(|frame| frame.get_mut(name)) {
+            Some(value) => value,
+            None => return Ok(()),
+        };
+        if variable.attributes.is_readonly {
+            return Err(VariableError::CannotUnset(name.to_vec()));
+        }
+        match (&mut variable.value, key) {
+            (Some(VariableValue::Associative(elements)), ArrayKey::Key(key)) => {
+                elements.remove(key);
+            },
+            (Some(VariableValue::Indexed(_) | VariableValue::Scalar(_)), ArrayKey::Index(index)) => {
+                let index = variable.resolve_index(*index).ok_or_else(|| VariableError::BadSubscript(name.to_vec()))?;
+                variable.get_indexed_elements().remove(&index);
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    // Visible variables sorted by name, for set and declare -p
+    pub fn get_visible(&self) -> Vec<(&[u8], &Variable)> {
+        let mut result: HashMap<&[u8], &Variable> = HashMap::new();
+        for frame in self.frames.iter() {
+            for (name, variable) in frame.iter() {
+                result.insert(name, variable);
+            }
+        }
+        let mut result: Vec<(&[u8], &Variable)> = result.into_iter().collect();
+        result.sort_by(|a, b| a.0.cmp(b.0));
+        result
+    }
+
+    // Variables of the current function sorted by name, for local without arguments
+    pub fn get_locals(&self) -> Vec<(&[u8], &Variable)> {
+        if !self.is_in_function() {
+            return vec![];
+        }
+        let frame = &self.frames[self.get_current_frame_index()];
+        let mut result: Vec<(&[u8], &Variable)> = frame.iter().map(|(name, variable)| (name.as_slice(), variable)).collect();
+        result.sort_by(|a, b| a.0.cmp(b.0));
+        result
+    }
+
+    // Environment of the executed commands: exported scalars, arrays are never exported
+    pub fn get_exported(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        self.get_visible()
+            .into_iter()
+            .filter(|(_, variable)| variable.attributes.is_exported)
+            .filter_map(|(name, variable)| match &variable.value {
+                Some(VariableValue::Scalar(value)) => Some((name.to_vec(), value.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+}
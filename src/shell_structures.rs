@@ -3,6 +3,8 @@ pub mod shell_pipeline;
 pub mod shell_list;
 pub mod shell_command;
 pub mod shell_variable_assignment;
+pub mod shell_redirection;
+pub mod history_expansion;
 
 
 pub trait ShellToken {
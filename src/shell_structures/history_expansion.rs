@@ -0,0 +1,65 @@
+use super::ShellToken;
+
+#[derive(Clone, Debug)]
+pub enum EventDesignator {
+    PreviousCommand, // !!
+    Absolute(usize), // !n
+    Relative(usize), // !-n
+    StartsWith(Vec<u8>), // !string
+    Contains(Vec<u8>), // !?string?
+    CurrentLine, // !#
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum WordIndex {
+    Number(usize),
+    Last, // $
+    Matched, // % - the word matched by the last !?string? search
+}
+
+#[derive(Clone, Debug)]
+pub enum WordDesignator {
+    Single(WordIndex), // :n, :^, :$, :%
+    Range(WordIndex, WordIndex), // :n-m, :-m, :n*
+    RangeWithoutLast(WordIndex), // :n-
+    AllArguments, // :*
+}
+
+#[derive(Clone, Debug)]
+pub enum HistoryModifier {
+    Head, // :h
+    Tail, // :t
+    RemoveSuffix, // :r
+    Suffix, // :e
+    PrintOnly, // :p
+    Quote, // :q
+    QuoteWords, // :x
+    Substitute { old: Vec<u8>, new: Vec<u8>, is_global: bool }, // :s/old/new/, :gs/old/new/
+    RepeatSubstitution { is_global: bool }, // :&, :g&
+}
+
+#[derive(Clone, Debug)]
+pub struct HistoryExpansion {
+    pub event: EventDesignator,
+    pub word: Option<WordDesignator>,
+    pub modifiers: Vec<HistoryModifier>,
+}
+
+#[derive(Clone, Debug)]
+pub enum HistoryLinePart {
+    Literal(Vec<u8>),
+    Expansion(HistoryExpansion),
+}
+
+impl ShellToken for EventDesignator {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            EventDesignator::PreviousCommand => b"!!".to_vec(),
+            EventDesignator::Absolute(n) => format!("!{n}").into_bytes(),
+            EventDesignator::Relative(n) => format!("!-{n}").into_bytes(),
+            EventDesignator::StartsWith(prefix) => [b"!", prefix.as_slice()].concat(),
+            EventDesignator::Contains(text) => [b"!?", text.as_slice()].concat(),
+            EventDesignator::CurrentLine => b"!#".to_vec(),
+        }
+    }
+}
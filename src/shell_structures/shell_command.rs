@@ -3,25 +3,37 @@ pub mod shell_compound_command;
 pub mod shell_coproc;
 pub mod shell_function_definition;
 
-use super::shell_word::ShellWord;
+use std::rc::Rc;
+
+use shell_compound_command::ShellCompoundCommand;
+use shell_function_definition::ShellFunctionDefinition;
+use shell_simple_command::ShellSimpleCommand;
+
+use super::shell_redirection::ShellRedirection;
+use super::ShellToken;
 
 #[derive(Clone, Debug)]
 pub enum ShellCommand {
-    Simple(),
-    Compound(),
+    Simple(ShellSimpleCommand),
+    Compound(ShellCompoundCommand, Vec<ShellRedirection>),
     Coproc(),
-    FunctionDefinition(),
-}
-
-#[derive(Clone, Debug)]
-pub enum VariableAssignmentValue {
-    Plain(ShellWord),
-    List(Vec<ShellWord>),
+    FunctionDefinition(Rc<ShellFunctionDefinition>), // shared with the function table once defined
 }
 
-#[derive(Clone, Debug)]
-pub struct VariableAssignment {
-    name: Vec<u8>,
-    index: i64, // name[3]=val
-    value: VariableAssignmentValue,
+impl ShellToken for ShellCommand {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            ShellCommand::Simple(command) => command.restore_original(),
+            ShellCommand::Compound(command, redirections) => {
+                let mut result = command.restore_original();
+                for redirection in redirections.iter() {
+                    result.push(b' ');
+                    result.extend(redirection.restore_original());
+                }
+                result
+            },
+            ShellCommand::FunctionDefinition(definition) => definition.restore_original(),
+            ShellCommand::Coproc() => vec![],
+        }
+    }
 }
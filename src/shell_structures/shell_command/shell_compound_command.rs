@@ -0,0 +1,79 @@
+use crate::shell_structures::shell_list::ShellList;
+use crate::shell_structures::shell_word::ShellWord;
+use crate::shell_structures::ShellToken;
+
+#[derive(Clone, Debug)]
+pub struct ShellIfBranch {
+    pub condition: ShellList,
+    pub body: ShellList,
+}
+
+#[derive(Clone, Debug)]
+pub enum ShellCompoundCommand {
+    BraceGroup(ShellList), // { list; }
+    Subshell(ShellList), // ( list ), runs in a forked copy of the shell
+    If {
+        branches: Vec<ShellIfBranch>, // the if branch and the elif ones
+        else_body: Option<ShellList>,
+    },
+    While {
+        condition: ShellList,
+        body: ShellList,
+        is_until: bool,
+    },
+    For {
+        variable: Vec<u8>,
+        words: Option<Vec<ShellWord>>, // None without "in", the loop goes over "$@"
+        body: ShellList,
+    },
+}
+
+fn restore_list(list: &ShellList) -> Vec<u8> {
+    let mut result = list.restore_original();
+    if !list.items.last().is_some_and(|item| item.is_background) {
+        result.push(b';');
+    }
+    result
+}
+
+impl ShellToken for ShellCompoundCommand {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            ShellCompoundCommand::BraceGroup(list) => [b"{ ".as_slice(), &restore_list(list), b" }"].concat(),
+            ShellCompoundCommand::Subshell(list) => [b"(".as_slice(), &list.restore_original(), b")"].concat(),
+            ShellCompoundCommand::If { branches, else_body } => {
+                let mut result = vec![];
+                for (index, branch) in branches.iter().enumerate() {
+                    result.extend(if index == 0 { b"if ".as_slice() } else { b" elif " });
+                    result.extend(restore_list(&branch.condition));
+                    result.extend(b" then ");
+                    result.extend(restore_list(&branch.body));
+                }
+                if let Some(else_body) = else_body {
+                    result.extend(b" else ");
+                    result.extend(restore_list(else_body));
+                }
+                result.extend(b" fi");
+                result
+            },
+            ShellCompoundCommand::While { condition, body, is_until } => {
+                let keyword = if *is_until { b"until ".as_slice() } else { b"while " };
+                [keyword, &restore_list(condition), b" do ", &restore_list(body), b" done"].concat()
+            },
+            ShellCompoundCommand::For { variable, words, body } => {
+                let mut result = [b"for ".as_slice(), variable].concat();
+                if let Some(words) = words {
+                    result.extend(b" in");
+                    for word in words {
+                        result.push(b' ');
+                        result.extend(word.restore_original());
+                    }
+                }
+                result.extend(b"; do ");
+                result.extend(restore_list(body));
+                result.extend(b" done");
+                result
+            },
+        }
+    }
+}
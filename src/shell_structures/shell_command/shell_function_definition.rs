@@ -0,0 +1,23 @@
+use crate::shell_structures::shell_redirection::ShellRedirection;
+use crate::shell_structures::ShellToken;
+
+use super::shell_compound_command::ShellCompoundCommand;
+
+// name() compound-command [redirections], the redirections apply to every call
+#[derive(Clone, Debug)]
+pub struct ShellFunctionDefinition {
+    pub name: Vec<u8>,
+    pub body: ShellCompoundCommand,
+    pub redirections: Vec<ShellRedirection>,
+}
+
+impl ShellToken for ShellFunctionDefinition {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = [self.name.as_slice(), b" () ", &self.body.restore_original()].concat();
+        for redirection in self.redirections.iter() {
+            result.push(b' ');
+            result.extend(redirection.restore_original());
+        }
+        result
+    }
+}
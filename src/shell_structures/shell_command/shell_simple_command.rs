@@ -0,0 +1,39 @@
+use crate::shell_structures::shell_redirection::ShellRedirection;
+use crate::shell_structures::shell_variable_assignment::ShellVariableAssignment;
+use crate::shell_structures::shell_word::ShellWord;
+use crate::shell_structures::ShellToken;
+
+// Words after the name of a declaration command that look like assignments are parsed as ones, so
+// that declare arr=(a b) sets an array and export PATH=~/bin:$PATH expands the tilde
+#[derive(Clone, Debug)]
+pub enum ShellArgument {
+    Word(ShellWord),
+    Assignment(ShellVariableAssignment),
+}
+
+// Assignments before the first word only apply to the command itself: LANG=C sort file;
+// redirections can be anywhere among the words
+#[derive(Clone, Debug, Default)]
+pub struct ShellSimpleCommand {
+    pub assignments: Vec<ShellVariableAssignment>,
+    pub words: Vec<ShellArgument>,
+    pub redirections: Vec<ShellRedirection>,
+}
+
+impl ShellToken for ShellArgument {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            ShellArgument::Word(word) => word.restore_original(),
+            ShellArgument::Assignment(assignment) => assignment.restore_original(),
+        }
+    }
+}
+
+impl ShellToken for ShellSimpleCommand {
+    fn restore_original(&self) -> Vec<u8> {
+        let assignments = self.assignments.iter().map(ShellVariableAssignment::restore_original);
+        let words = self.words.iter().map(ShellArgument::restore_original);
+        let redirections = self.redirections.iter().map(ShellRedirection::restore_original);
+        assignments.chain(words).chain(redirections).collect::<Vec<Vec<u8>>>().join(&b' ')
+    }
+}
@@ -0,0 +1,57 @@
+use super::shell_pipeline::ShellPipeline;
+use super::ShellToken;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ShellListOperator {
+    And, // &&, the next pipeline runs when the previous one succeeded
+    Or, // ||
+}
+
+// Pipelines joined with && and ||, the unit that runs in the background with '&'
+#[derive(Clone, Debug, Default)]
+pub struct ShellAndOrList {
+    pub first: ShellPipeline,
+    pub rest: Vec<(ShellListOperator, ShellPipeline)>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShellListItem {
+    pub and_or_list: ShellAndOrList,
+    pub is_background: bool, // terminated by '&' instead of ';' or a newline
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct ShellList {
+    pub items: Vec<ShellListItem>,
+}
+
+impl ShellToken for ShellAndOrList {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = self.first.restore_original();
+        for (operator, pipeline) in self.rest.iter() {
+            result.extend(match operator {
+                ShellListOperator::And => b" && ",
+                ShellListOperator::Or => b" || ",
+            });
+            result.extend(pipeline.restore_original());
+        }
+        result
+    }
+}
+
+// Written on a single line: a; b & c
+impl ShellToken for ShellList {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = vec![];
+        for (index, item) in self.items.iter().enumerate() {
+            if index > 0 {
+                result.extend(if self.items[index - 1].is_background { b" ".as_slice() } else { b"; " });
+            }
+            result.extend(item.and_or_list.restore_original());
+            if item.is_background {
+                result.extend(b" &");
+            }
+        }
+        result
+    }
+}
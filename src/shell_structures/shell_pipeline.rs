@@ -0,0 +1,31 @@
+use super::shell_command::ShellCommand;
+use super::ShellToken;
+
+#[derive(Clone, Debug)]
+pub struct ShellPipelineStage {
+    pub command: ShellCommand,
+    pub is_stderr_piped: bool, // cmd |& next, the standard error goes through the pipe as well
+}
+
+// cmd1 | cmd2 |& cmd3, the status is the one of the last command, inverted with a leading '!'
+#[derive(Clone, Debug, Default)]
+pub struct ShellPipeline {
+    pub stages: Vec<ShellPipelineStage>,
+    pub is_negated: bool,
+}
+
+impl ShellToken for ShellPipeline {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = vec![];
+        if self.is_negated {
+            result.extend(b"! ");
+        }
+        for (index, stage) in self.stages.iter().enumerate() {
+            if index > 0 {
+                result.extend(if self.stages[index - 1].is_stderr_piped { b" |& ".as_slice() } else { b" | " });
+            }
+            result.extend(stage.command.restore_original());
+        }
+        result
+    }
+}
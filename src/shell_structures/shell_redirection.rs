@@ -0,0 +1,106 @@
+use super::shell_word::ShellWord;
+use super::ShellToken;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RedirectionOperator {
+    Input, // <
+    Output, // >, fails on an existing regular file with set -C
+    Clobber, // >|
+    Append, // >>
+    ReadWrite, // <>
+    DuplicateInput, // <&, the target is a descriptor, n- to move it or - to close
+    DuplicateOutput, // >&, a target that is not a descriptor makes it &> when no descriptor is given
+    OutputAndError, // &>
+    AppendOutputAndError, // &>>
+    HereDocument, // <<, the target is the delimiter and the contents are the lines after the command
+    HereDocumentStrippingTabs, // <<-, leading tabs are removed from the contents and the delimiter line
+    HereString, // <<<, the expanded word and a newline
+}
+
+// Descriptor on the left of the operator
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RedirectionDescriptor {
+    Default, // 0 for the input operators, 1 for the others
+    Number(i32),
+    Variable(Vec<u8>), // {name}, a new descriptor from 10 up is stored in the variable
+}
+
+// Contents of << and <<-, kept as written and expanded when the redirection is performed
+#[derive(Clone, Debug)]
+pub struct HereDocument {
+    pub delimiter: Vec<u8>, // the target with the quotes removed
+    pub contents: Vec<u8>, // every line ends with a newline, the delimiter line is not part of it
+    pub is_quoted: bool, // any quoting in the delimiter leaves the contents unexpanded
+}
+
+#[derive(Clone, Debug)]
+pub struct ShellRedirection {
+    pub descriptor: RedirectionDescriptor,
+    pub operator: RedirectionOperator,
+    pub target: ShellWord,
+    pub here_document: Option<HereDocument>, // with << and <<- only
+}
+
+impl RedirectionOperator {
+    // Longest operators first, so that >> is not read as >
+    pub const ALL: [RedirectionOperator; 12] = [
+        RedirectionOperator::AppendOutputAndError,
+        RedirectionOperator::HereString,
+        RedirectionOperator::HereDocumentStrippingTabs,
+        RedirectionOperator::OutputAndError,
+        RedirectionOperator::HereDocument,
+        RedirectionOperator::Append,
+        RedirectionOperator::Clobber,
+        RedirectionOperator::DuplicateOutput,
+        RedirectionOperator::ReadWrite,
+        RedirectionOperator::DuplicateInput,
+        RedirectionOperator::Output,
+        RedirectionOperator::Input,
+    ];
+
+    pub fn get_text(&self) -> &'static [u8] {
+        match self {
+            RedirectionOperator::Input => b"<",
+            RedirectionOperator::Output => b">",
+            RedirectionOperator::Clobber => b">|",
+            RedirectionOperator::Append => b">>",
+            RedirectionOperator::ReadWrite => b"<>",
+            RedirectionOperator::DuplicateInput => b"<&",
+            RedirectionOperator::DuplicateOutput => b">&",
+            RedirectionOperator::OutputAndError => b"&>",
+            RedirectionOperator::AppendOutputAndError => b"&>>",
+            RedirectionOperator::HereDocument => b"<<",
+            RedirectionOperator::HereDocumentStrippingTabs => b"<<-",
+            RedirectionOperator::HereString => b"<<<",
+        }
+    }
+
+    pub fn get_default_descriptor(&self) -> i32 {
+        match self {
+            RedirectionOperator::Input | RedirectionOperator::ReadWrite | RedirectionOperator::DuplicateInput => 0,
+            RedirectionOperator::HereDocument | RedirectionOperator::HereDocumentStrippingTabs => 0,
+            RedirectionOperator::HereString => 0,
+            _ => 1,
+        }
+    }
+}
+
+impl ShellToken for ShellRedirection {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = match &self.descriptor {
+            RedirectionDescriptor::Default => vec![],
+            RedirectionDescriptor::Number(fd) => fd.to_string().into_bytes(),
+            RedirectionDescriptor::Variable(name) => [b"{".as_slice(), name, b"}"].concat(),
+        };
+        result.extend(self.operator.get_text());
+        result.extend(self.target.restore_original());
+        // The contents follow on the next lines, as they were written
+        if let Some(here_document) = &self.here_document {
+            result.push(b'\n');
+            result.extend(&here_document.contents);
+            result.extend(&here_document.delimiter);
+            result.push(b'\n');
+        }
+        result
+    }
+}
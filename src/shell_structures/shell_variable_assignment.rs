@@ -1,13 +1,54 @@
 use super::shell_word::ShellWord;
+use super::ShellToken;
+
+// Element of a compound assignment: ( a b [k1]=v1 [k2]=v2 )
+#[derive(Clone, Debug)]
+pub struct ShellArrayElement {
+    pub subscript: Option<ShellWord>, // elements without one are split and globbed, and take the next index
+    pub value: ShellWord,
+}
 
 #[derive(Clone, Debug)]
 pub enum VariableAssignmentType {
     Simple(ShellWord),
-    List(Vec<ShellWord>)
+    List(Vec<ShellArrayElement>),
 }
 
 #[derive(Clone, Debug)]
 pub struct ShellVariableAssignment {
     pub identifier: Vec<u8>,
+    pub subscript: Option<ShellWord>, // name[expr]=value, arithmetic for indexed arrays, a key for associative ones
+    pub is_append: bool, // +=
     pub value: VariableAssignmentType,
 }
+
+impl ShellToken for ShellArrayElement {
+    fn restore_original(&self) -> Vec<u8> {
+        match &self.subscript {
+            Some(subscript) => [b"[", subscript.restore_original().as_slice(), b"]=", &self.value.restore_original()].concat(),
+            None => self.value.restore_original(),
+        }
+    }
+}
+
+impl ShellToken for ShellVariableAssignment {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = self.identifier.clone();
+        if let Some(subscript) = &self.subscript {
+            result.push(b'[');
+            result.extend(subscript.restore_original());
+            result.push(b']');
+        }
+        result.extend(if self.is_append { b"+=".as_slice() } else { b"=" });
+        match &self.value {
+            VariableAssignmentType::Simple(word) => result.extend(word.restore_original()),
+            VariableAssignmentType::List(elements) => {
+                let elements: Vec<Vec<u8>> = elements.iter().map(ShellArrayElement::restore_original).collect();
+                result.push(b'(');
+                result.extend(elements.join(&b' '));
+                result.push(b')');
+            },
+        }
+        result
+    }
+}
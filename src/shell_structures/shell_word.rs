@@ -19,6 +19,8 @@ pub enum ShellExpression {
     BraceExpansion(BraceExpansion),
     DollarSignExpansion(DollarSignExpansion),
     QuoteExpression(QuoteExpression),
+    GlobbingPattern(GlobbingPattern),
+    TildePrefix(Vec<u8>), // login name after ~, can be empty, + or -
 }
 
 #[derive(Clone, Debug, Default)]
@@ -34,6 +36,8 @@ impl ShellToken for ShellExpression {
             ShellExpression::BraceExpansion(exp) => exp.restore_original(),
             ShellExpression::DollarSignExpansion(exp) => exp.restore_original(),
             ShellExpression::QuoteExpression(exp) => exp.restore_original(),
+            ShellExpression::GlobbingPattern(exp) => exp.restore_original(),
+            ShellExpression::TildePrefix(login_name) => [b"~", login_name.as_slice()].concat(),
         }
     }
 }
@@ -1,8 +1,212 @@
-use std::collections::HashSet;
+pub mod bracket_expression;
+pub mod extended_glob;
+
+use bracket_expression::BracketExpression;
+use extended_glob::{ExtendedGlob, ExtendedGlobKind};
+
+use super::{ShellExpression, ShellWord};
+use super::quoted_expressions::{QuoteExpression, WeakQuoteExpressionPart};
+use crate::shell_structures::ShellToken;
 
 #[derive(Clone, Debug)]
 pub enum GlobbingPattern {
     AnyString(usize), // ***-like sequences are the same as single * but store the amount in order to fallback on no matches
     AnySingleCharacter,
-    SpecificCharacter(HashSet<u8>)
+    SpecificCharacter(BracketExpression), // [abc], [!a-f], [[:alpha:]]
+    Extended(ExtendedGlob), // !(*.o|*.a), requires extglob
+}
+
+#[derive(Clone, Debug)]
+pub enum PatternElement {
+    Character(char),
+    AnyString(usize),
+    AnySingleCharacter,
+    SpecificCharacter(BracketExpression),
+    Extended(ExtendedGlobKind, Vec<Pattern>), // alternatives are converted once when the pattern is built
+}
+
+// A word with quoting already resolved: only the non-Character elements keep their special meaning
+#[derive(Clone, Debug, Default)]
+pub struct Pattern {
+    pub elements: Vec<PatternElement>,
+}
+
+impl Pattern {
+    pub fn push_literal(&mut self, literal: &[u8]) {
+        self.elements.extend(String::from_utf8_lossy(literal).chars().map(PatternElement::Character));
+    }
+
+    pub fn push_glob(&mut self, glob: GlobbingPattern) {
+        let element = match glob {
+            GlobbingPattern::AnyString(count) => PatternElement::AnyString(count),
+            GlobbingPattern::AnySingleCharacter => PatternElement::AnySingleCharacter,
+            GlobbingPattern::SpecificCharacter(expression) => PatternElement::SpecificCharacter(expression),
+            GlobbingPattern::Extended(extended_glob) => {
+                let alternatives = extended_glob.alternatives.iter().map(Self::from_word).collect();
+                PatternElement::Extended(extended_glob.kind, alternatives)
+            },
+        };
+        self.elements.push(element);
+    }
+
+    // Only words without expansions can be converted, the rest of the parts are taken literally
+    pub fn from_word(word: &ShellWord) -> Self {
+        let mut result = Self::default();
+        for part in word.parts.iter() {
+            match part {
+                ShellExpression::Literal(literal) => result.push_literal(literal),
+                ShellExpression::EscapedLiteral(c) => result.push_literal(&[*c]),
+                ShellExpression::GlobbingPattern(glob) => result.push_glob(glob.clone()),
+                ShellExpression::QuoteExpression(QuoteExpression::Strong(exp)) => result.push_literal(&exp.contents),
+                ShellExpression::QuoteExpression(QuoteExpression::AnsiC(exp)) => result.push_literal(&exp.contents),
+                ShellExpression::QuoteExpression(QuoteExpression::Weak(exp) | QuoteExpression::Translatable(exp)) => {
+                    for weak_part in exp.parts.iter() {
+                        match weak_part {
+                            WeakQuoteExpressionPart::Literal(literal) => result.push_literal(literal),
+                            WeakQuoteExpressionPart::DollarSignExpansion(d) => result.push_literal(&d.restore_original()),
+                        }
+                    }
+                },
+                _ => result.push_literal(&part.restore_original()),
+            }
+        }
+        result
+    }
+
+    pub fn has_globs(&self) -> bool {
+        self.elements.iter().any(|element| !matches!(element, PatternElement::Character(_)))
+    }
+
+    pub fn starts_with(&self, c: char) -> bool {
+        matches!(self.elements.first(), Some(PatternElement::Character(first)) if *first == c)
+    }
+
+    // ** alone in a path component
+    pub fn is_recursive(&self) -> bool {
+        matches!(self.elements.as_slice(), [PatternElement::AnyString(count)] if *count > 1)
+    }
+
+    pub fn split_path_components(&self) -> Vec<Pattern> {
+        let mut result = vec![Self::default()];
+        for element in self.elements.iter() {
+            match element {
+                PatternElement::Character('/') => result.push(Self::default()),
+                _ => result.last_mut().unwrap().elements.push(element.clone()),
+            }
+        }
+        result
+    }
+
+    // Text of the pattern with quoting removed, used when nothing matches
+    pub fn to_literal(&self) -> Vec<u8> {
+        let mut result = vec![];
+        for element in self.elements.iter() {
+            match element {
+                PatternElement::Character(c) => result.extend(c.to_string().as_bytes()),
+                PatternElement::AnyString(count) => result.extend(vec![b'*'; *count]),
+                PatternElement::AnySingleCharacter => result.push(b'?'),
+                PatternElement::SpecificCharacter(expression) => result.extend(expression.restore_original()),
+                PatternElement::Extended(kind, alternatives) => {
+                    result.extend([kind.get_char(), b'(']);
+                    for (i, alternative) in alternatives.iter().enumerate() {
+                        if i > 0 {
+                            result.push(b'|');
+                        }
+                        result.extend(alternative.to_literal());
+                    }
+                    result.push(b')');
+                },
+            }
+        }
+        result
+    }
+
+    pub fn matches(&self, text: &[u8], ignore_case: bool) -> bool {
+        let text: Vec<char> = String::from_utf8_lossy(text).chars().collect();
+        Self::matches_from(&self.elements, &text, ignore_case)
+    }
+
+    fn matches_char(expected: char, c: char, ignore_case: bool) -> bool {
+        expected == c || ignore_case && expected.to_lowercase().eq(c.to_lowercase())
+    }
+
+    fn matches_bracket(expression: &BracketExpression, c: char, ignore_case: bool) -> bool {
+        if !ignore_case {
+            return expression.matches(c);
+        }
+        let contains = expression.contains(c)
+            || c.to_lowercase().any(|lower| expression.contains(lower))
+            || c.to_uppercase().any(|upper| expression.contains(upper));
+        contains != expression.is_negated
+    }
+
+    fn matches_any(alternatives: &[Pattern], text: &[char], ignore_case: bool) -> bool {
+        alternatives.iter().any(|alternative| Self::matches_from(&alternative.elements, text, ignore_case))
+    }
+
+    // One or more non-empty pieces each matched by one of the alternatives.
+    // Whether the text from an offset splits into pieces is computed once per offset, from the end,
+    // so the alternatives are tried on each of the O(n^2) substrings at most once
+    fn matches_repeated(alternatives: &[Pattern], text: &[char], ignore_case: bool) -> bool {
+        let mut splits_from = vec![false; text.len() + 1];
+        splits_from[text.len()] = true;
+        for start in (0..text.len()).rev() {
+            splits_from[start] = (start + 1..=text.len()).any(|end| {
+                splits_from[end] && Self::matches_any(alternatives, &text[start..end], ignore_case)
+            });
+        }
+        !text.is_empty() && splits_from[0]
+    }
+
+    fn matches_extended(kind: ExtendedGlobKind, alternatives: &[Pattern], text: &[char], ignore_case: bool) -> bool {
+        match kind {
+            ExtendedGlobKind::ExactlyOne => Self::matches_any(alternatives, text, ignore_case),
+            ExtendedGlobKind::ZeroOrOne => text.is_empty() || Self::matches_any(alternatives, text, ignore_case),
+            ExtendedGlobKind::OneOrMore if text.is_empty() => Self::matches_any(alternatives, text, ignore_case),
+            ExtendedGlobKind::OneOrMore => Self::matches_repeated(alternatives, text, ignore_case),
+            ExtendedGlobKind::ZeroOrMore => text.is_empty() || Self::matches_repeated(alternatives, text, ignore_case),
+            ExtendedGlobKind::AnythingExcept => !Self::matches_any(alternatives, text, ignore_case),
+        }
+    }
+
+    fn matches_from(elements: &[PatternElement], text: &[char], ignore_case: bool) -> bool {
+        let (element, rest) = match elements.split_first() {
+            Some(value) => value,
+            None => return text.is_empty(),
+        };
+        match element {
+            PatternElement::AnyString(_) => {
+                (0..=text.len()).any(|skipped| Self::matches_from(rest, &text[skipped..], ignore_case))
+            },
+            PatternElement::AnySingleCharacter => {
+                !text.is_empty() && Self::matches_from(rest, &text[1..], ignore_case)
+            },
+            PatternElement::SpecificCharacter(expression) => {
+                text.first().is_some_and(|&c| Self::matches_bracket(expression, c, ignore_case))
+                    && Self::matches_from(rest, &text[1..], ignore_case)
+            },
+            PatternElement::Character(c) => {
+                text.first().is_some_and(|&first| Self::matches_char(*c, first, ignore_case))
+                    && Self::matches_from(rest, &text[1..], ignore_case)
+            },
+            // Backtracks over every split of the text between the group and the rest of the pattern
+            PatternElement::Extended(kind, alternatives) => {
+                (0..=text.len()).any(|length| {
+                    Self::matches_extended(*kind, alternatives, &text[..length], ignore_case)
+                        && Self::matches_from(rest, &text[length..], ignore_case)
+                })
+            },
+        }
+    }
+}
+
+impl ShellToken for GlobbingPattern {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            GlobbingPattern::AnyString(count) => vec![b'*'; *count],
+            GlobbingPattern::AnySingleCharacter => vec![b'?'],
+            GlobbingPattern::SpecificCharacter(expression) => expression.restore_original(),
+            GlobbingPattern::Extended(extended_glob) => extended_glob.restore_original(),
+        }
+    }
 }
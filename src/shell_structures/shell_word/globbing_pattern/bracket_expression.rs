@@ -0,0 +1,211 @@
+use crate::shell_structures::ShellToken;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CharacterClass {
+    Alnum,
+    Alpha,
+    Ascii,
+    Blank,
+    Cntrl,
+    Digit,
+    Graph,
+    Lower,
+    Print,
+    Punct,
+    Space,
+    Upper,
+    Word,
+    Xdigit,
+}
+
+#[derive(Clone, Debug)]
+pub enum BracketExpressionItem {
+    Character(char),
+    Range(char, char), // [a-f]
+    Class(CharacterClass), // [[:alpha:]]
+    EquivalenceClass(char), // [[=e=]]
+    CollatingSymbol(char), // [[.x.]], [[.hyphen.]]
+}
+
+#[derive(Clone, Debug)]
+pub struct BracketExpression {
+    pub is_negated: bool,
+    pub items: Vec<BracketExpressionItem>,
+}
+
+const CHARACTER_CLASS_NAMES: [(&str, CharacterClass); 14] = [
+    ("alnum", CharacterClass::Alnum),
+    ("alpha", CharacterClass::Alpha),
+    ("ascii", CharacterClass::Ascii),
+    ("blank", CharacterClass::Blank),
+    ("cntrl", CharacterClass::Cntrl),
+    ("digit", CharacterClass::Digit),
+    ("graph", CharacterClass::Graph),
+    ("lower", CharacterClass::Lower),
+    ("print", CharacterClass::Print),
+    ("punct", CharacterClass::Punct),
+    ("space", CharacterClass::Space),
+    ("upper", CharacterClass::Upper),
+    ("word", CharacterClass::Word),
+    ("xdigit", CharacterClass::Xdigit),
+];
+
+// Subset of the POSIX portable character set names, enough for the usual [[.hyphen.]]-like symbols
+const COLLATING_SYMBOL_NAMES: [(&str, char); 14] = [
+    ("tab", '\t'),
+    ("newline", '\n'),
+    ("space", ' '),
+    ("exclamation-mark", '!'),
+    ("quotation-mark", '"'),
+    ("number-sign", '#'),
+    ("hyphen", '-'),
+    ("hyphen-minus", '-'),
+    ("period", '.'),
+    ("full-stop", '.'),
+    ("slash", '/'),
+    ("backslash", '\\'),
+    ("left-square-bracket", '['),
+    ("right-square-bracket", ']'),
+];
+
+impl CharacterClass {
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        CHARACTER_CLASS_NAMES
+            .iter()
+            .find(|(class_name, _)| class_name.as_bytes() == name)
+            .map(|&(_, class)| class)
+    }
+
+    fn get_name(&self) -> &'static str {
+        CHARACTER_CLASS_NAMES
+            .iter()
+            .find(|(_, class)| class == self)
+            .map(|&(name, _)| name)
+            .unwrap()
+    }
+
+    pub fn matches(&self, c: char) -> bool {
+        match self {
+            CharacterClass::Alnum => c.is_alphanumeric(),
+            CharacterClass::Alpha => c.is_alphabetic(),
+            CharacterClass::Ascii => c.is_ascii(),
+            CharacterClass::Blank => c == ' ' || c == '\t',
+            CharacterClass::Cntrl => c.is_control(),
+            CharacterClass::Digit => c.is_ascii_digit(),
+            CharacterClass::Graph => !c.is_control() && !c.is_whitespace(),
+            CharacterClass::Lower => c.is_lowercase(),
+            CharacterClass::Print => !c.is_control(),
+            CharacterClass::Punct => {
+                c.is_ascii_punctuation()
+                    || !c.is_ascii() && !c.is_alphanumeric() && !c.is_whitespace() && !c.is_control()
+            },
+            CharacterClass::Space => c.is_whitespace(),
+            CharacterClass::Upper => c.is_uppercase(),
+            CharacterClass::Word => c.is_alphanumeric() || c == '_',
+            CharacterClass::Xdigit => c.is_ascii_hexdigit(),
+        }
+    }
+}
+
+pub fn get_collating_symbol(name: &str) -> Option<char> {
+    let mut chars = name.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(c);
+    }
+    COLLATING_SYMBOL_NAMES
+        .iter()
+        .find(|(symbol_name, _)| *symbol_name == name)
+        .map(|&(_, c)| c)
+}
+
+// Characters of one equivalence class share the same base letter, e.g. e, é, è, ê and ë
+fn get_base_character(c: char) -> char {
+    const EQUIVALENCES: [(&str, char); 24] = [
+        ("àáâãäåāăą", 'a'),
+        ("ÀÁÂÃÄÅĀĂĄ", 'A'),
+        ("çćĉċč", 'c'),
+        ("ÇĆĈĊČ", 'C'),
+        ("ďđ", 'd'),
+        ("ĎĐ", 'D'),
+        ("èéêëēĕėęě", 'e'),
+        ("ÈÉÊËĒĔĖĘĚ", 'E'),
+        ("ìíîïĩīĭįı", 'i'),
+        ("ÌÍÎÏĨĪĬĮİ", 'I'),
+        ("ñńņňŉ", 'n'),
+        ("ÑŃŅŇ", 'N'),
+        ("òóôõöøōŏő", 'o'),
+        ("ÒÓÔÕÖØŌŎŐ", 'O'),
+        ("ŕŗř", 'r'),
+        ("ŔŖŘ", 'R'),
+        ("śŝşš", 's'),
+        ("ŚŜŞŠ", 'S'),
+        ("ţťŧ", 't'),
+        ("ŢŤŦ", 'T'),
+        ("ùúûüũūŭůűų", 'u'),
+        ("ÙÚÛÜŨŪŬŮŰŲ", 'U'),
+        ("ýÿŷ", 'y'),
+        ("ÝŸŶ", 'Y'),
+    ];
+    EQUIVALENCES
+        .iter()
+        .find(|(variants, _)| variants.contains(c))
+        .map(|&(_, base)| base)
+        .unwrap_or(c)
+}
+
+impl BracketExpressionItem {
+    fn matches(&self, c: char) -> bool {
+        match self {
+            BracketExpressionItem::Character(value) | BracketExpressionItem::CollatingSymbol(value) => *value == c,
+            BracketExpressionItem::Range(start, end) => (*start..=*end).contains(&c),
+            BracketExpressionItem::Class(class) => class.matches(c),
+            BracketExpressionItem::EquivalenceClass(value) => get_base_character(*value) == get_base_character(c),
+        }
+    }
+}
+
+impl BracketExpression {
+    pub fn contains(&self, c: char) -> bool {
+        self.items.iter().any(|item| item.matches(c))
+    }
+
+    pub fn matches(&self, c: char) -> bool {
+        self.contains(c) != self.is_negated
+    }
+}
+
+fn restore_member_char(c: char) -> String {
+    match c {
+        ']' | '[' | '\\' | '-' | '!' | '^' => format!("\\{c}"),
+        _ => c.to_string(),
+    }
+}
+
+impl ShellToken for BracketExpressionItem {
+    fn restore_original(&self) -> Vec<u8> {
+        let result = match self {
+            BracketExpressionItem::Character(c) => restore_member_char(*c),
+            BracketExpressionItem::Range(start, end) => {
+                format!("{}-{}", restore_member_char(*start), restore_member_char(*end))
+            },
+            BracketExpressionItem::Class(class) => format!("[:{}:]", class.get_name()),
+            BracketExpressionItem::EquivalenceClass(c) => format!("[={c}=]"),
+            BracketExpressionItem::CollatingSymbol(c) => format!("[.{c}.]"),
+        };
+        result.into_bytes()
+    }
+}
+
+impl ShellToken for BracketExpression {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = vec![b'['];
+        if self.is_negated {
+            result.push(b'!');
+        }
+        for item in self.items.iter() {
+            result.extend(item.restore_original());
+        }
+        result.push(b']');
+        result
+    }
+}
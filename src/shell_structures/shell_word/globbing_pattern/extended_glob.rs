@@ -0,0 +1,54 @@
+use crate::shell_structures::shell_word::ShellWord;
+use crate::shell_structures::ShellToken;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExtendedGlobKind {
+    ZeroOrOne, // ?(...)
+    ZeroOrMore, // *(...)
+    OneOrMore, // +(...)
+    ExactlyOne, // @(...)
+    AnythingExcept, // !(...)
+}
+
+#[derive(Clone, Debug)]
+pub struct ExtendedGlob {
+    pub kind: ExtendedGlobKind,
+    pub alternatives: Vec<ShellWord>,
+}
+
+impl ExtendedGlobKind {
+    pub fn from_char(c: u8) -> Option<Self> {
+        match c {
+            b'?' => Some(ExtendedGlobKind::ZeroOrOne),
+            b'*' => Some(ExtendedGlobKind::ZeroOrMore),
+            b'+' => Some(ExtendedGlobKind::OneOrMore),
+            b'@' => Some(ExtendedGlobKind::ExactlyOne),
+            b'!' => Some(ExtendedGlobKind::AnythingExcept),
+            _ => None,
+        }
+    }
+
+    pub fn get_char(&self) -> u8 {
+        match self {
+            ExtendedGlobKind::ZeroOrOne => b'?',
+            ExtendedGlobKind::ZeroOrMore => b'*',
+            ExtendedGlobKind::OneOrMore => b'+',
+            ExtendedGlobKind::ExactlyOne => b'@',
+            ExtendedGlobKind::AnythingExcept => b'!',
+        }
+    }
+}
+
+impl ShellToken for ExtendedGlob {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = vec![self.kind.get_char(), b'('];
+        for (i, alternative) in self.alternatives.iter().enumerate() {
+            if i > 0 {
+                result.push(b'|');
+            }
+            result.extend(alternative.restore_original());
+        }
+        result.push(b')');
+        result
+    }
+}
@@ -18,10 +18,18 @@ pub struct StrongQuoteExpression {
     pub contents: Vec<u8>
 }
 
+// $'...', contents are stored with the escape sequences already decoded
+#[derive(Clone, Debug)]
+pub struct AnsiCQuoteExpression {
+    pub contents: Vec<u8>
+}
+
 #[derive(Clone, Debug)]
 pub enum QuoteExpression {
     Weak(WeakQuoteExpression),
     Strong(StrongQuoteExpression),
+    AnsiC(AnsiCQuoteExpression),
+    Translatable(WeakQuoteExpression), // $"...", same as weak but looked up in the message catalog
 }
 
 impl ShellToken for WeakQuoteExpression {
@@ -38,11 +46,33 @@ impl ShellToken for WeakQuoteExpression {
     }
 } //wrong! escaped characters are not counted
 
+impl ShellToken for AnsiCQuoteExpression {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = b"$'".to_vec();
+        for &c in self.contents.iter() {
+            match c {
+                b'\\' => result.extend(b"\\\\"),
+                b'\'' => result.extend(b"\\'"),
+                b'\n' => result.extend(b"\\n"),
+                b'\t' => result.extend(b"\\t"),
+                b'\r' => result.extend(b"\\r"),
+                0x1B => result.extend(b"\\E"),
+                _ if c.is_ascii_control() => result.extend(format!("\\x{c:02x}").as_bytes()),
+                _ => result.push(c),
+            }
+        }
+        result.push(b'\'');
+        result
+    }
+}
+
 impl ShellToken for QuoteExpression {
     fn restore_original(&self) -> Vec<u8> {
         match self {
             QuoteExpression::Weak(exp) => exp.restore_original(),
             QuoteExpression::Strong(exp) => exp.contents.clone(),
+            QuoteExpression::AnsiC(exp) => exp.restore_original(),
+            QuoteExpression::Translatable(exp) => [b"$", exp.restore_original().as_slice()].concat(),
         }
     }
 }
\ No newline at end of file
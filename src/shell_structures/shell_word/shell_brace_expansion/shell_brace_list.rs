@@ -3,7 +3,7 @@ use crate::shell_structures::ShellToken;
 
 #[derive(Clone, Debug)]
 pub struct ShellBraceList {
-    words: Vec<ShellWord>,
+    pub words: Vec<ShellWord>,
 }
 
 impl ShellToken for ShellBraceList {
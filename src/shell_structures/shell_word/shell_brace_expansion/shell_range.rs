@@ -78,17 +78,15 @@ impl ShellIntRange {
         }
     }
 
+    // Goes from start towards end, {5..1..2} is 5 3 1
     fn expand(&self) -> Vec<Vec<u8>> {
-        let mut result = vec![];
-        let range = if self.start < self.end {
-            self.start..=self.end
+        let step = self.step.max(1) as usize;
+        let values: Vec<i64> = if self.start <= self.end {
+            (self.start..=self.end).step_by(step).collect()
         } else {
-            self.end..=self.start
+            (self.end..=self.start).rev().step_by(step).collect()
         };
-        for i in range.step_by(self.step as usize) {
-            result.push(self.get_aligned(i))
-        }
-        result
+        values.into_iter().map(|i| self.get_aligned(i)).collect()
     }
 
     fn get_aligned(&self, value: i64) -> Vec<u8> {
@@ -105,16 +103,13 @@ impl ShellCharRange {
     }
 
     fn expand(&self) -> Vec<Vec<u8>> {
-        let mut result = vec![];
-        let range = if self.start < self.end {
-            self.start..=self.end
+        let step = self.step.unsigned_abs().max(1) as usize;
+        let values: Vec<u8> = if self.start <= self.end {
+            (self.start..=self.end).step_by(step).collect()
         } else {
-            self.end..=self.start
+            (self.end..=self.start).rev().step_by(step).collect()
         };
-        for i in range.step_by(self.step as usize) {
-            result.push(vec![i]);
-        }
-        result
+        values.into_iter().map(|c| vec![c]).collect()
     }
 }
 
@@ -1,17 +1,26 @@
-mod parameter_expansion;
-mod command_expansion;
-mod arithmetic_expansion;
+pub mod parameter_expansion;
+pub mod command_expansion;
+pub mod arithmetic_expansion;
+
+use parameter_expansion::ParameterExpansion;
+use command_expansion::CommandExpansion;
+use arithmetic_expansion::ArithmeticExpansion;
 
 use crate::shell_structures::ShellToken;
 
 #[derive(Clone, Debug)]
 pub enum DollarSignExpansion {
-
+    Parameter(ParameterExpansion),
+    Command(CommandExpansion),
+    Arithmetic(ArithmeticExpansion),
 }
 
 impl ShellToken for DollarSignExpansion {
     fn restore_original(&self) -> Vec<u8> {
-        //match self {  }
-        vec![]
+        match self {
+            DollarSignExpansion::Parameter(expansion) => expansion.restore_original(),
+            DollarSignExpansion::Command(expansion) => expansion.restore_original(),
+            DollarSignExpansion::Arithmetic(expansion) => expansion.restore_original(),
+        }
     }
-}
\ No newline at end of file
+}
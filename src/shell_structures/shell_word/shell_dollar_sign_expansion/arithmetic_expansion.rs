@@ -0,0 +1,23 @@
+use super::super::quoted_expressions::WeakQuoteExpressionPart;
+
+use crate::shell_structures::ShellToken;
+
+// $((...)), the expression is expanded as if it was double-quoted and then evaluated
+#[derive(Clone, Debug)]
+pub struct ArithmeticExpansion {
+    pub parts: Vec<WeakQuoteExpressionPart>,
+}
+
+impl ShellToken for ArithmeticExpansion {
+    fn restore_original(&self) -> Vec<u8> {
+        let mut result = b"$((".to_vec();
+        for part in self.parts.iter() {
+            match part {
+                WeakQuoteExpressionPart::Literal(literal) => result.extend(literal),
+                WeakQuoteExpressionPart::DollarSignExpansion(expansion) => result.extend(expansion.restore_original()),
+            }
+        }
+        result.extend(b"))");
+        result
+    }
+}
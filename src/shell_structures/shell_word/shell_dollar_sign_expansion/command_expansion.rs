@@ -0,0 +1,13 @@
+use crate::shell_structures::ShellToken;
+
+// $(...), the command is kept as written and parsed when the substitution is run
+#[derive(Clone, Debug)]
+pub struct CommandExpansion {
+    pub command: Vec<u8>,
+}
+
+impl ShellToken for CommandExpansion {
+    fn restore_original(&self) -> Vec<u8> {
+        [b"$(", self.command.as_slice(), b")"].concat()
+    }
+}
@@ -1,4 +1,42 @@
 use super::super::quoted_expressions::WeakQuoteExpression;
+use super::super::ShellWord;
+
+use crate::shell_structures::ShellToken;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SpecialParameter {
+    ExitStatus, // ?
+    ShellPid, // $
+    LastBackgroundPid, // !
+    PositionalCount, // #
+    AllPositionalFields, // @
+    AllPositionalJoined, // *
+    ShellName, // 0
+    Flags, // -
+}
+
+#[derive(Clone, Debug)]
+pub enum ArraySubscript {
+    AllFields, // [@]
+    AllJoined, // [*]
+    Expression(ShellWord), // arithmetic for indexed arrays, a key for associative ones
+}
+
+#[derive(Clone, Debug)]
+pub enum Parameter {
+    Named(Vec<u8>), // $_ is a named one as well, the executor keeps it up to date
+    Positional(usize),
+    Special(SpecialParameter),
+    Element(Vec<u8>, ArraySubscript), // ${name[1]}, ${name[@]}
+}
+
+#[derive(Clone, Debug)]
+pub enum ParameterExpansion {
+    Simple(Parameter), // $name, $1, $@
+    Braced(Parameter), // ${name}, ${10}
+    Length(Parameter), // ${#name}, ${#name[@]}
+    Keys(Vec<u8>, bool), // ${!name[@]}, ${!name[*]} when joined
+}
 
 enum ParameterReplacementType {
     Fallback, // :-
@@ -17,4 +55,68 @@ struct ParameterRange {
     identifier: Vec<u8>, // can be @ or *
     offset: i64,
     length: usize,
-}
\ No newline at end of file
+}
+
+impl SpecialParameter {
+    pub fn from_char(c: u8) -> Option<Self> {
+        match c {
+            b'?' => Some(SpecialParameter::ExitStatus),
+            b'$' => Some(SpecialParameter::ShellPid),
+            b'!' => Some(SpecialParameter::LastBackgroundPid),
+            b'#' => Some(SpecialParameter::PositionalCount),
+            b'@' => Some(SpecialParameter::AllPositionalFields),
+            b'*' => Some(SpecialParameter::AllPositionalJoined),
+            b'0' => Some(SpecialParameter::ShellName),
+            b'-' => Some(SpecialParameter::Flags),
+            _ => None,
+        }
+    }
+
+    pub fn get_char(&self) -> u8 {
+        match self {
+            SpecialParameter::ExitStatus => b'?',
+            SpecialParameter::ShellPid => b'$',
+            SpecialParameter::LastBackgroundPid => b'!',
+            SpecialParameter::PositionalCount => b'#',
+            SpecialParameter::AllPositionalFields => b'@',
+            SpecialParameter::AllPositionalJoined => b'*',
+            SpecialParameter::ShellName => b'0',
+            SpecialParameter::Flags => b'-',
+        }
+    }
+}
+
+impl ShellToken for ArraySubscript {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            ArraySubscript::AllFields => b"[@]".to_vec(),
+            ArraySubscript::AllJoined => b"[*]".to_vec(),
+            ArraySubscript::Expression(word) => [b"[", word.restore_original().as_slice(), b"]"].concat(),
+        }
+    }
+}
+
+impl ShellToken for Parameter {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            Parameter::Named(name) => name.clone(),
+            Parameter::Positional(n) => n.to_string().into_bytes(),
+            Parameter::Special(special) => vec![special.get_char()],
+            Parameter::Element(name, subscript) => [name.as_slice(), &subscript.restore_original()].concat(),
+        }
+    }
+}
+
+impl ShellToken for ParameterExpansion {
+    fn restore_original(&self) -> Vec<u8> {
+        match self {
+            ParameterExpansion::Simple(parameter) => [b"$", parameter.restore_original().as_slice()].concat(),
+            ParameterExpansion::Braced(parameter) => [b"${", parameter.restore_original().as_slice(), b"}"].concat(),
+            ParameterExpansion::Length(parameter) => [b"${#", parameter.restore_original().as_slice(), b"}"].concat(),
+            ParameterExpansion::Keys(name, is_joined) => {
+                let subscript: &[u8] = if *is_joined { b"[*]}" } else { b"[@]}" };
+                [b"${!", name.as_slice(), subscript].concat()
+            },
+        }
+    }
+}
@@ -0,0 +1,31 @@
+pub mod message_catalog;
+pub mod po_template;
+
+use message_catalog::{translate_message, MessageCatalog};
+
+use crate::shell_expansions::quote_removal::remove_weak_quotes;
+use crate::shell_input_iterator::ShellInputIterator;
+use crate::shell_parsers::shell_word_parser::quoted_expression_parser::QuotedExpressionParser;
+use crate::shell_structures::shell_word::quoted_expressions::{QuoteExpression, WeakQuoteExpression};
+
+// The message id is the text between the quotes without its escapes, \" and \$ stand for the
+// characters themselves and a backslash-newline for nothing; expansions are kept as written
+pub fn get_message_id(expression: &WeakQuoteExpression) -> Vec<u8> {
+    remove_weak_quotes(expression)
+}
+
+// The translation is parsed again as the contents of double quotes, so expansions in it are still performed
+pub fn translate_quote_expression(expression: &WeakQuoteExpression, catalog: Option<&MessageCatalog>) -> WeakQuoteExpression {
+    let message_id = get_message_id(expression);
+    let translation = translate_message(&message_id, catalog);
+    if translation == message_id {
+        return expression.clone();
+    }
+    let mut input = translation;
+    input.push(b'"');
+    let mut parser = QuotedExpressionParser::new(ShellInputIterator::new(input.into_iter().peekable()));
+    match parser.parse_weak(b'"') {
+        Ok(Some(QuoteExpression::Weak(translated))) => translated,
+        _ => expression.clone(),
+    }
+}
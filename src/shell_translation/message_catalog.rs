@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::PathBuf;
+
+const MO_MAGIC: u32 = 0x950412de;
+const DEFAULT_TEXTDOMAINDIR: &[u8] = b"/usr/share/locale";
+
+// Everything find_message_catalog looks at
+const CATALOG_VARIABLES: [&[u8]; 6] = [b"TEXTDOMAIN", b"TEXTDOMAINDIR", b"LANGUAGE", b"LC_ALL", b"LC_MESSAGES", b"LANG"];
+
+// GNU gettext .mo file: a header followed by two tables of (length, offset) pairs
+// pointing to the original strings and to their translations
+pub struct MessageCatalog {
+    data: Vec<u8>,
+    is_big_endian: bool,
+    string_count: usize,
+    originals_offset: usize,
+    translations_offset: usize,
+}
+
+impl MessageCatalog {
+    pub fn parse(data: Vec<u8>) -> Option<Self> {
+        let magic = u32::from_le_bytes(data.get(0..4)?.try_into().ok()?);
+        let is_big_endian = match magic {
+            MO_MAGIC => false,
+            _ if magic.swap_bytes() == MO_MAGIC => true,
+            _ => return None,
+        };
+        let mut catalog = Self { data, is_big_endian, string_count: 0, originals_offset: 0, translations_offset: 0 };
+        catalog.string_count = catalog.read_u32(8)? as usize;
+        catalog.originals_offset = catalog.read_u32(12)? as usize;
+        catalog.translations_offset = catalog.read_u32(16)? as usize;
+        Some(catalog)
+    }
+
+    pub fn load(path: &PathBuf) -> Option<Self> {
+        Self::parse(fs::read(path).ok()?)
+    }
+
+    fn read_u32(&self, offset: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(offset..offset + 4)?.try_into().ok()?;
+        Some(if self.is_big_endian { u32::from_be_bytes(bytes) } else { u32::from_le_bytes(bytes) })
+    }
+
+    fn read_string(&self, table_offset: usize, index: usize) -> Option<&[u8]> {
+        let length = self.read_u32(table_offset + index * 8)? as usize;
+        let offset = self.read_u32(table_offset + index * 8 + 4)? as usize;
+        self.data.get(offset..offset + length)
+    }
+
+    // Plural forms store "msgid\0msgid_plural", only the singular form is compared and returned
+    fn first_form(string: &[u8]) -> &[u8] {
+        string.split(|&c| c == 0).next().unwrap_or(string)
+    }
+
+    pub fn lookup(&self, message: &[u8]) -> Option<&[u8]> {
+        // Originals are sorted, so a binary search is possible
+        let (mut low, mut high) = (0usize, self.string_count);
+        while low < high {
+            let middle = (low + high) / 2;
+            let original = Self::first_form(self.read_string(self.originals_offset, middle)?);
+            match original.cmp(message) {
+                std::cmp::Ordering::Equal => {
+                    let translation = Self::first_form(self.read_string(self.translations_offset, middle)?);
+                    return if translation.is_empty() { None } else { Some(translation) };
+                },
+                std::cmp::Ordering::Less => low = middle + 1,
+                std::cmp::Ordering::Greater => high = middle,
+            }
+        }
+        None
+    }
+}
+
+// de_DE.UTF-8@euro -> de_DE.UTF-8@euro, de_DE@euro, de_DE.UTF-8, de_DE, de
+fn get_locale_candidates(locale: &[u8]) -> Vec<Vec<u8>> {
+    let (base, modifier) = match locale.iter().position(|&c| c == b'@') {
+        Some(position) => (&locale[..position], &locale[position..]),
+        None => (locale, &b""[..]),
+    };
+    let without_codeset = match base.iter().position(|&c| c == b'.') {
+        Some(position) => &base[..position],
+        None => base,
+    };
+    let language = match without_codeset.iter().position(|&c| c == b'_') {
+        Some(position) => &without_codeset[..position],
+        None => without_codeset,
+    };
+    let mut result: Vec<Vec<u8>> = vec![];
+    for candidate in [
+        [base, modifier].concat(),
+        [without_codeset, modifier].concat(),
+        base.to_vec(),
+        without_codeset.to_vec(),
+        language.to_vec(),
+    ] {
+        if !candidate.is_empty() && !result.contains(&candidate) {
+            result.push(candidate);
+        }
+    }
+    result
+}
+
+// Locale names in the gettext priority order: LANGUAGE, then LC_ALL, LC_MESSAGES and LANG
+fn get_message_locales(get_variable: &dyn Fn(&[u8]) -> Option<Vec<u8>>) -> Vec<Vec<u8>> {
+    let locale = [b"LC_ALL".as_slice(), b"LC_MESSAGES", b"LANG"]
+        .iter()
+        .filter_map(|name| get_variable(name))
+        .find(|value| !value.is_empty());
+    let locale = match locale {
+        Some(value) if value != b"C" && value != b"POSIX" => value,
+        _ => return vec![],
+    };
+    let mut result = vec![];
+    if let Some(languages) = get_variable(b"LANGUAGE") {
+        result.extend(languages.split(|&c| c == b':').filter(|l| !l.is_empty()).map(|l| l.to_vec()));
+    }
+    result.push(locale);
+    result
+}
+
+// Catalog for TEXTDOMAIN in TEXTDOMAINDIR/<locale>/LC_MESSAGES/<TEXTDOMAIN>.mo
+pub fn find_message_catalog(get_variable: &dyn Fn(&[u8]) -> Option<Vec<u8>>) -> Option<MessageCatalog> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let text_domain = get_variable(b"TEXTDOMAIN").filter(|value| !value.is_empty())?;
+    let text_domain_dir = get_variable(b"TEXTDOMAINDIR")
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(|| DEFAULT_TEXTDOMAINDIR.to_vec());
+    for locale in get_message_locales(get_variable) {
+        for candidate in get_locale_candidates(&locale) {
+            let mut path = PathBuf::from(std::ffi::OsStr::from_bytes(&text_domain_dir));
+            path.push(std::ffi::OsStr::from_bytes(&candidate));
+            path.push("LC_MESSAGES");
+            path.push(std::ffi::OsStr::from_bytes(&[text_domain.as_slice(), b".mo"].concat()));
+            if let Some(catalog) = MessageCatalog::load(&path) {
+                return Some(catalog);
+            }
+        }
+    }
+    None
+}
+
+// The catalog found with the current values of the variables that locate it, it is only looked for
+// again when one of them changes
+#[derive(Default)]
+pub struct MessageCatalogCache {
+    variables: Option<Vec<Option<Vec<u8>>>>,
+    catalog: Option<MessageCatalog>,
+}
+
+impl MessageCatalogCache {
+    pub fn get(&mut self, get_variable: &dyn Fn(&[u8]) -> Option<Vec<u8>>) -> Option<&MessageCatalog> {
+        let variables: Vec<Option<Vec<u8>>> = CATALOG_VARIABLES.iter().map(|name| get_variable(name)).collect();
+        if self.variables.as_ref() != Some(&variables) {
+            self.catalog = find_message_catalog(get_variable);
+            self.variables = Some(variables);
+        }
+        self.catalog.as_ref()
+    }
+}
+
+// The message itself is returned when there is no catalog or no translation
+pub fn translate_message(message: &[u8], catalog: Option<&MessageCatalog>) -> Vec<u8> {
+    catalog
+        .and_then(|catalog| catalog.lookup(message).map(|translation| translation.to_vec()))
+        .unwrap_or_else(|| message.to_vec())
+}
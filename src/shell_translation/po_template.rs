@@ -0,0 +1,233 @@
+use crate::shell_input_iterator::ShellInputIterator;
+use crate::shell_parser_base::{ParseError, ShellParsingRules};
+use crate::shell_parsers::shell_input_parser::ShellInputParser;
+use crate::shell_structures::shell_command::shell_compound_command::ShellCompoundCommand;
+use crate::shell_structures::shell_command::shell_simple_command::ShellArgument;
+use crate::shell_structures::shell_command::ShellCommand;
+use crate::shell_structures::shell_list::ShellList;
+use crate::shell_structures::shell_redirection::ShellRedirection;
+use crate::shell_structures::shell_variable_assignment::{ShellVariableAssignment, VariableAssignmentType};
+use crate::shell_structures::shell_word::globbing_pattern::GlobbingPattern;
+use crate::shell_structures::shell_word::quoted_expressions::{QuoteExpression, WeakQuoteExpression, WeakQuoteExpressionPart};
+use crate::shell_structures::shell_word::shell_brace_expansion::BraceExpansion;
+use crate::shell_structures::shell_word::shell_dollar_sign_expansion::DollarSignExpansion;
+use crate::shell_structures::shell_word::{ShellExpression, ShellWord};
+use crate::shell_structures::ShellToken;
+
+use super::get_message_id;
+
+struct PoEntry {
+    message_id: Vec<u8>,
+    references: Vec<String>, // file:line
+}
+
+fn find_subslice(text: &[u8], pattern: &[u8]) -> Option<usize> {
+    text.windows(pattern.len()).position(|window| window == pattern)
+}
+
+// Walks the commands of a script; the tree keeps no positions, so every string is looked up in the
+// script after the previous one, the walk visits them in the order they are written
+struct MessageCollector<'a> {
+    file_name: &'a str,
+    script: &'a [u8],
+    parsing_rules: &'a ShellParsingRules,
+    offset: usize,
+    entries: Vec<PoEntry>,
+}
+
+impl MessageCollector<'_> {
+    // Escaped characters are lost in the tree, the next $" is taken when the exact text is not found
+    fn find_line_number(&mut self, expression: &QuoteExpression) -> usize {
+        let rest = &self.script[self.offset..];
+        if let Some(position) = find_subslice(rest, &expression.restore_original()).or_else(|| find_subslice(rest, b"$\"")) {
+            self.offset += position + 2;
+        }
+        self.script[..self.offset].iter().filter(|&&c| c == b'\n').count() + 1
+    }
+
+    fn add_message(&mut self, expression: &QuoteExpression, message: &WeakQuoteExpression) {
+        let message_id = get_message_id(message);
+        let line_number = self.find_line_number(expression);
+        let reference = format!("{}:{line_number}", self.file_name);
+        match self.entries.iter_mut().find(|entry| entry.message_id == message_id) {
+            Some(entry) if !entry.references.contains(&reference) => entry.references.push(reference),
+            Some(_) => {},
+            None => self.entries.push(PoEntry { message_id, references: vec![reference] }),
+        }
+    }
+
+    // The script itself and the commands of $(...), which are otherwise only parsed when they run
+    fn collect_input(&mut self, input: &[u8]) -> Result<(), ParseError> {
+        let iter = ShellInputIterator::new(input.iter().copied().peekable());
+        let mut parser = ShellInputParser::new(iter, self.parsing_rules);
+        match parser.parse()? {
+            Some(list) => self.collect_list(&list),
+            None => Ok(()),
+        }
+    }
+
+    fn collect_list(&mut self, list: &ShellList) -> Result<(), ParseError> {
+        for item in list.items.iter() {
+            let and_or_list = &item.and_or_list;
+            let pipelines = std::iter::once(&and_or_list.first).chain(and_or_list.rest.iter().map(|(_, pipeline)| pipeline));
+            for pipeline in pipelines {
+                for stage in pipeline.stages.iter() {
+                    self.collect_command(&stage.command)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_command(&mut self, command: &ShellCommand) -> Result<(), ParseError> {
+        match command {
+            ShellCommand::Simple(command) => {
+                for assignment in command.assignments.iter() {
+                    self.collect_assignment(assignment)?;
+                }
+                for argument in command.words.iter() {
+                    match argument {
+                        ShellArgument::Word(word) => self.collect_word(word)?,
+                        ShellArgument::Assignment(assignment) => self.collect_assignment(assignment)?,
+                    }
+                }
+                self.collect_redirections(&command.redirections)
+            },
+            ShellCommand::Compound(command, redirections) => {
+                self.collect_compound_command(command)?;
+                self.collect_redirections(redirections)
+            },
+            ShellCommand::FunctionDefinition(definition) => {
+                self.collect_compound_command(&definition.body)?;
+                self.collect_redirections(&definition.redirections)
+            },
+            ShellCommand::Coproc() => Ok(()),
+        }
+    }
+
+    fn collect_compound_command(&mut self, command: &ShellCompoundCommand) -> Result<(), ParseError> {
+        match command {
+            ShellCompoundCommand::BraceGroup(list) | ShellCompoundCommand::Subshell(list) => self.collect_list(list),
+            ShellCompoundCommand::If { branches, else_body } => {
+                for branch in branches.iter() {
+                    self.collect_list(&branch.condition)?;
+                    self.collect_list(&branch.body)?;
+                }
+                match else_body {
+                    Some(list) => self.collect_list(list),
+                    None => Ok(()),
+                }
+            },
+            ShellCompoundCommand::While { condition, body, .. } => {
+                self.collect_list(condition)?;
+                self.collect_list(body)
+            },
+            ShellCompoundCommand::For { words, body, .. } => {
+                for word in words.iter().flatten() {
+                    self.collect_word(word)?;
+                }
+                self.collect_list(body)
+            },
+        }
+    }
+
+    fn collect_redirections(&mut self, redirections: &[ShellRedirection]) -> Result<(), ParseError> {
+        for redirection in redirections.iter() {
+            self.collect_word(&redirection.target)?;
+        }
+        Ok(())
+    }
+
+    fn collect_assignment(&mut self, assignment: &ShellVariableAssignment) -> Result<(), ParseError> {
+        if let Some(subscript) = &assignment.subscript {
+            self.collect_word(subscript)?;
+        }
+        match &assignment.value {
+            VariableAssignmentType::Simple(word) => self.collect_word(word),
+            VariableAssignmentType::List(elements) => {
+                for element in elements.iter() {
+                    if let Some(subscript) = &element.subscript {
+                        self.collect_word(subscript)?;
+                    }
+                    self.collect_word(&element.value)?;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    fn collect_word(&mut self, word: &ShellWord) -> Result<(), ParseError> {
+        for part in word.parts.iter() {
+            match part {
+                ShellExpression::QuoteExpression(expression @ QuoteExpression::Translatable(message)) => {
+                    self.add_message(expression, message);
+                    self.collect_quoted_parts(&message.parts)?;
+                },
+                ShellExpression::QuoteExpression(QuoteExpression::Weak(expression)) => self.collect_quoted_parts(&expression.parts)?,
+                ShellExpression::DollarSignExpansion(expansion) => self.collect_expansion(expansion)?,
+                ShellExpression::BraceExpansion(BraceExpansion::List(list)) => {
+                    for word in list.words.iter() {
+                        self.collect_word(word)?;
+                    }
+                },
+                ShellExpression::GlobbingPattern(GlobbingPattern::Extended(extended_glob)) => {
+                    for alternative in extended_glob.alternatives.iter() {
+                        self.collect_word(alternative)?;
+                    }
+                },
+                _ => {},
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_quoted_parts(&mut self, parts: &[WeakQuoteExpressionPart]) -> Result<(), ParseError> {
+        for part in parts.iter() {
+            if let WeakQuoteExpressionPart::DollarSignExpansion(expansion) = part {
+                self.collect_expansion(expansion)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn collect_expansion(&mut self, expansion: &DollarSignExpansion) -> Result<(), ParseError> {
+        match expansion {
+            DollarSignExpansion::Command(expansion) => self.collect_input(&expansion.command),
+            DollarSignExpansion::Arithmetic(expansion) => self.collect_quoted_parts(&expansion.parts),
+            DollarSignExpansion::Parameter(_) => Ok(()),
+        }
+    }
+}
+
+fn escape_po_string(message: &[u8]) -> String {
+    let mut result = String::new();
+    for c in String::from_utf8_lossy(message).chars() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '"' => result.push_str("\\\""),
+            '\n' => result.push_str("\\n"),
+            '\t' => result.push_str("\\t"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+// .pot template with every $"..." string of the scripts (file name and contents) and the lines it appears on
+pub fn dump_po_strings(scripts: &[(String, Vec<u8>)], parsing_rules: &ShellParsingRules) -> Result<String, ParseError> {
+    let mut entries = vec![];
+    for (file_name, script) in scripts.iter() {
+        let mut collector = MessageCollector { file_name, script, parsing_rules, offset: 0, entries };
+        collector.collect_input(script)?;
+        entries = collector.entries;
+    }
+    let mut result = String::new();
+    result.push_str("msgid \"\"\nmsgstr \"\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n");
+    for entry in entries {
+        result.push('\n');
+        result.push_str(&format!("#: {}\n", entry.references.join(" ")));
+        result.push_str(&format!("msgid \"{}\"\n", escape_po_string(&entry.message_id)));
+        result.push_str("msgstr \"\"\n");
+    }
+    Ok(result)
+}